@@ -0,0 +1,461 @@
+//! Audio extraction and audio-only processing
+//!
+//! An [`AudioJob`] takes the audio track of a media file, either copying it
+//! as is or re-encoding it with optional resampling, downmixing, trimming
+//! and fades. Running the job returns the probed result.
+
+use crate::cut::material::Material;
+use crate::error::{CutError, Result};
+use crate::ffmpeg::{
+    FFmpeg,
+    codec::{AudioCodec, Format},
+    filter::Filter,
+    input::Input,
+    output::Output,
+};
+use crate::ffprobe::{FFprobe, MediaInfo};
+use crate::options::{FFmpegOptions, FFprobeOptions};
+use std::path::Path;
+
+/// Encoded audio output formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioFormat {
+    /// 16-bit PCM WAV
+    Wav,
+    /// MP3
+    Mp3,
+    /// AAC in an ADTS stream
+    Aac,
+    /// FLAC
+    Flac,
+    /// Opus in Ogg
+    Opus,
+}
+
+/// An audio extraction job
+#[derive(Debug, Clone)]
+pub struct AudioJob {
+    /// Input file path
+    pub input: String,
+    /// Output file path
+    pub output: String,
+    /// Output encoding; `None` copies the source audio stream
+    pub format: Option<AudioFormat>,
+    /// Audio bitrate in kbps, for lossy formats
+    pub bitrate: Option<i32>,
+    /// Sample rate in Hz
+    pub sample_rate: Option<i32>,
+    /// Channel count, e.g. 1 to downmix to mono
+    pub channels: Option<i32>,
+    /// Start of the extracted range in milliseconds
+    pub start: u32,
+    /// Length of the extracted range in milliseconds; `None` reads to the end
+    pub duration: Option<u32>,
+    /// Fade in length in milliseconds
+    pub fade_in: u32,
+    /// Fade out length in milliseconds
+    pub fade_out: u32,
+    /// FFmpeg options
+    pub ffmpeg_options: FFmpegOptions,
+    /// FFprobe options
+    pub ffprobe_options: FFprobeOptions,
+}
+
+impl AudioFormat {
+    /// Get the format for a file extension
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "wav" => Some(AudioFormat::Wav),
+            "mp3" => Some(AudioFormat::Mp3),
+            "aac" => Some(AudioFormat::Aac),
+            "flac" => Some(AudioFormat::Flac),
+            "opus" => Some(AudioFormat::Opus),
+            _ => None,
+        }
+    }
+
+    /// Get the file extension
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "wav",
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Aac => "aac",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Opus => "opus",
+        }
+    }
+
+    /// Get the audio encoder
+    pub fn codec(&self) -> AudioCodec {
+        match self {
+            AudioFormat::Wav => AudioCodec::PCM,
+            AudioFormat::Mp3 => AudioCodec::MP3,
+            AudioFormat::Aac => AudioCodec::AAC,
+            AudioFormat::Flac => AudioCodec::FLAC,
+            AudioFormat::Opus => AudioCodec::Opus,
+        }
+    }
+
+    /// Get the container format
+    pub fn format(&self) -> Format {
+        match self {
+            AudioFormat::Wav => Format::WAV,
+            AudioFormat::Mp3 => Format::MP3,
+            AudioFormat::Aac => Format::custom("adts"),
+            AudioFormat::Flac => Format::custom("flac"),
+            AudioFormat::Opus => Format::custom("opus"),
+        }
+    }
+
+    /// Check if the format is lossless
+    pub fn is_lossless(&self) -> bool {
+        matches!(self, AudioFormat::Wav | AudioFormat::Flac)
+    }
+}
+
+impl AudioJob {
+    /// Create a job; the encoding follows the output extension, unknown
+    /// extensions copy the source stream
+    pub fn new<S1: Into<String>, S2: Into<String>>(input: S1, output: S2) -> Self {
+        let output = output.into();
+        let format = Path::new(&output)
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(AudioFormat::from_extension);
+        Self {
+            input: input.into(),
+            output,
+            format,
+            bitrate: None,
+            sample_rate: None,
+            channels: None,
+            start: 0,
+            duration: None,
+            fade_in: 0,
+            fade_out: 0,
+            ffmpeg_options: FFmpegOptions::new(),
+            ffprobe_options: FFprobeOptions::new(),
+        }
+    }
+
+    /// Create a job for the audio of a video or audio material
+    pub fn from_material<S: Into<String>>(material: &Material, output: S) -> Result<Self> {
+        if matches!(material, Material::Image(_)) {
+            return Err(CutError::invalid_params(format!(
+                "Material '{}' is an image and has no audio",
+                material.id()
+            )));
+        }
+        Ok(Self::new(material.src(), output))
+    }
+
+    /// Copy the source audio stream without re-encoding
+    pub fn copy(mut self) -> Self {
+        self.format = None;
+        self
+    }
+
+    /// Re-encode to `format`
+    pub fn format(mut self, format: AudioFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Set the bitrate in kbps
+    pub fn bitrate(mut self, bitrate: i32) -> Self {
+        self.bitrate = Some(bitrate);
+        self
+    }
+
+    /// Set the sample rate in Hz
+    pub fn sample_rate(mut self, rate: i32) -> Self {
+        self.sample_rate = Some(rate);
+        self
+    }
+
+    /// Set the channel count
+    pub fn channels(mut self, channels: i32) -> Self {
+        self.channels = Some(channels);
+        self
+    }
+
+    /// Extract `duration` milliseconds starting at `start`
+    pub fn trim(mut self, start: u32, duration: u32) -> Self {
+        self.start = start;
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Set the fade in and fade out lengths in milliseconds
+    pub fn fade(mut self, fade_in: u32, fade_out: u32) -> Self {
+        self.fade_in = fade_in;
+        self.fade_out = fade_out;
+        self
+    }
+
+    /// Set FFmpeg options
+    pub fn set_ffmpeg_options(mut self, options: FFmpegOptions) -> Self {
+        self.ffmpeg_options = options;
+        self
+    }
+
+    /// Set FFprobe options
+    pub fn set_ffprobe_options(mut self, options: FFprobeOptions) -> Self {
+        self.ffprobe_options = options;
+        self
+    }
+
+    /// Validate the job settings
+    pub fn validate(&self) -> Result<()> {
+        if self.input.is_empty() {
+            return Err(CutError::missing_param("input"));
+        }
+        if self.output.is_empty() {
+            return Err(CutError::missing_param("output"));
+        }
+
+        match self.format {
+            None => {
+                if self.bitrate.is_some()
+                    || self.sample_rate.is_some()
+                    || self.channels.is_some()
+                    || self.fade_in > 0
+                    || self.fade_out > 0
+                {
+                    return Err(CutError::invalid_params(
+                        "Bitrate, resampling, downmixing and fades need the audio to be re-encoded",
+                    ));
+                }
+            }
+            Some(format) => {
+                if format.is_lossless() && self.bitrate.is_some() {
+                    return Err(CutError::invalid_params(format!(
+                        "{} is lossless and takes no bitrate",
+                        format.extension()
+                    )));
+                }
+            }
+        }
+
+        if self.bitrate.is_some_and(|b| b <= 0) {
+            return Err(CutError::invalid_params("Bitrate must be positive"));
+        }
+        if self.sample_rate.is_some_and(|r| r <= 0) {
+            return Err(CutError::invalid_params("Sample rate must be positive"));
+        }
+        if self.channels.is_some_and(|c| c <= 0) {
+            return Err(CutError::invalid_params("Channel count must be positive"));
+        }
+        if let Some(duration) = self.duration {
+            if duration == 0 {
+                return Err(CutError::invalid_params("Duration must be positive"));
+            }
+            if self.fade_in + self.fade_out > duration {
+                return Err(CutError::invalid_params(
+                    "Fades are longer than the extracted audio",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check if the job needs the input duration to place the fade out
+    fn needs_probe(&self) -> bool {
+        self.fade_out > 0 && self.duration.is_none()
+    }
+
+    /// Build the FFmpeg command; a fade out without a trim needs the probed input
+    pub fn build(&self, info: Option<&MediaInfo>) -> Result<FFmpeg> {
+        self.validate()?;
+
+        let mut ffmpeg = FFmpeg::new();
+        ffmpeg.set_ffmpeg_options(self.ffmpeg_options.clone());
+
+        let mut input = Input::with_simple(&self.input);
+        if self.start > 0 {
+            input = input.start_time(self.start as f32 / 1000.0);
+        }
+        if let Some(duration) = self.duration {
+            input = input.duration(duration as f32 / 1000.0);
+        }
+        let input = ffmpeg.add_input(input);
+
+        let Some(format) = self.format else {
+            ffmpeg.add_output(
+                Output::with_simple(&self.output)
+                    .map_stream(input.a())
+                    .audio_codec(AudioCodec::Copy),
+            );
+            return Ok(ffmpeg);
+        };
+
+        let mut stream = input.a();
+        if let Some(rate) = self.sample_rate {
+            stream = ffmpeg.add_filter(Filter::resample(rate), [stream]);
+        }
+        if let Some(channels) = self.channels {
+            stream = ffmpeg.add_filter(Filter::achannels(channels), [stream]);
+        }
+        if self.fade_in > 0 {
+            stream = ffmpeg.add_filter(
+                Filter::afade_in(0.0, self.fade_in as f64 / 1000.0),
+                [stream],
+            );
+        }
+        if self.fade_out > 0 {
+            let length = match self.duration {
+                Some(duration) => duration as f64 / 1000.0,
+                None => {
+                    let total = info
+                        .and_then(|i| i.duration_seconds())
+                        .ok_or_else(|| CutError::missing_param("input duration"))?;
+                    (total - self.start as f64 / 1000.0).max(0.0)
+                }
+            };
+            let fade = self.fade_out as f64 / 1000.0;
+            stream = ffmpeg.add_filter(Filter::afade_out((length - fade).max(0.0), fade), [stream]);
+        }
+
+        let mut output = Output::with_simple(&self.output)
+            .map_stream(stream)
+            .audio_codec(format.codec())
+            .format(format.format());
+        if let Some(bitrate) = self.bitrate {
+            output = output.audio_bitrate(bitrate);
+        }
+        ffmpeg.add_output(output);
+        Ok(ffmpeg)
+    }
+
+    /// Run the job and probe the result
+    pub async fn run(&self) -> Result<MediaInfo> {
+        self.validate()?;
+        let info = if self.needs_probe() {
+            Some(self.probe(&self.input).await?)
+        } else {
+            None
+        };
+
+        self.build(info.as_ref())?.run().await?;
+        self.probe(&self.output).await
+    }
+
+    /// Probe a file with the job's FFprobe options
+    async fn probe(&self, path: &str) -> Result<MediaInfo> {
+        FFprobe::new()
+            .set_options(self.ffprobe_options.clone())
+            .input(path)
+            .run()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffprobe::FormatInfo;
+
+    fn media_info(duration: &str) -> MediaInfo {
+        MediaInfo {
+            format: Some(FormatInfo {
+                duration: Some(duration.to_string()),
+                ..Default::default()
+            }),
+            streams: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_formats() {
+        assert_eq!(
+            AudioJob::new("in.mp4", "out.flac").format,
+            Some(AudioFormat::Flac)
+        );
+        assert_eq!(
+            AudioJob::new("in.mp4", "out.OPUS").format,
+            Some(AudioFormat::Opus)
+        );
+        assert_eq!(AudioJob::new("in.mp4", "out.m4a").format, None);
+        assert_eq!(AudioFormat::Aac.format().as_str(), "adts");
+
+        assert!(AudioJob::from_material(&Material::image("a.png"), "a.wav").is_err());
+        let job = AudioJob::from_material(&Material::video("in.mp4"), "a.wav").unwrap();
+        assert_eq!(job.input, "in.mp4");
+    }
+
+    #[test]
+    fn test_copy() {
+        let job = AudioJob::new("in.mp4", "out.m4a").trim(2000, 5500);
+        let line = job.build(None).unwrap().build_args().join(" ");
+        assert!(line.contains("-ss 2 -t 5.5 -i in.mp4"));
+        assert!(line.contains("-map 0:a"));
+        assert!(line.contains("-c:a copy"));
+        assert!(!line.contains("-filter_complex"));
+    }
+
+    #[test]
+    fn test_encode() {
+        let job = AudioJob::new("in.mp4", "out.mp3")
+            .bitrate(192)
+            .sample_rate(44100)
+            .channels(1)
+            .fade(500, 2000);
+        assert!(job.build(None).is_err());
+
+        let line = job
+            .build(Some(&media_info("10.0")))
+            .unwrap()
+            .build_args()
+            .join(" ");
+        assert!(line.contains("aresample=44100"));
+        assert!(line.contains("aformat=channel_layouts=1"));
+        assert!(line.contains("afade=t=in:st=0:d=0.5"));
+        assert!(line.contains("afade=t=out:st=8:d=2"));
+        assert!(line.contains("-c:a libmp3lame"));
+        assert!(line.contains("-b:a 192k"));
+        assert!(line.contains("-f mp3"));
+
+        let job = AudioJob::new("in.mp4", "out.wav")
+            .trim(1000, 4000)
+            .fade(0, 1000);
+        let line = job.build(None).unwrap().build_args().join(" ");
+        assert!(line.contains("afade=t=out:st=3:d=1"));
+        assert!(line.contains("-c:a pcm_s16le"));
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(AudioJob::new("", "out.mp3").validate().is_err());
+        assert!(
+            AudioJob::new("in.mp4", "out.m4a")
+                .channels(1)
+                .validate()
+                .is_err()
+        );
+        assert!(
+            AudioJob::new("in.mp4", "out.flac")
+                .bitrate(320)
+                .validate()
+                .is_err()
+        );
+        assert!(
+            AudioJob::new("in.mp4", "out.mp3")
+                .sample_rate(0)
+                .validate()
+                .is_err()
+        );
+        assert!(
+            AudioJob::new("in.mp4", "out.mp3")
+                .trim(0, 1000)
+                .fade(800, 800)
+                .validate()
+                .is_err()
+        );
+        assert!(
+            AudioJob::new("in.mp4", "out.mp3")
+                .bitrate(128)
+                .validate()
+                .is_ok()
+        );
+    }
+}
@@ -0,0 +1,197 @@
+//! Lossless concat of simple cuts
+//!
+//! A timeline that only cuts one video track, with no scaling, positioning
+//! or speed changes, can be joined by the concat demuxer with stream copy
+//! instead of being re-encoded. Stream copy can only start a cut on a
+//! keyframe, so every cut is checked against the keyframes around it, and
+//! copied audio is only joined when every source encodes it the same way.
+
+use crate::error::Result;
+use crate::ffprobe::{FFprobe, StreamInfo};
+use crate::options::FFprobeOptions;
+
+/// Cuts starting this close to a keyframe count as aligned, in milliseconds
+pub const KEYFRAME_TOLERANCE: u32 = 20;
+
+/// A source range copied into the concat output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConcatPart {
+    /// Source file path
+    pub path: String,
+    /// Start time in the source in milliseconds
+    pub inpoint: u32,
+    /// End time in the source in milliseconds
+    pub outpoint: u32,
+}
+
+impl ConcatPart {
+    /// Create a part
+    pub fn new<S: Into<String>>(path: S, inpoint: u32, outpoint: u32) -> Self {
+        Self {
+            path: path.into(),
+            inpoint,
+            outpoint,
+        }
+    }
+}
+
+/// Audio stream parameters that have to match across sources for the
+/// concat demuxer to copy the audio
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioParams {
+    /// Codec name
+    pub codec: Option<String>,
+    /// Sample rate in Hz
+    pub sample_rate: Option<String>,
+    /// Number of channels
+    pub channels: Option<i32>,
+    /// Channel layout
+    pub channel_layout: Option<String>,
+}
+
+impl AudioParams {
+    /// Take the parameters of a probed audio stream
+    pub fn from_stream(stream: &StreamInfo) -> Self {
+        Self {
+            codec: stream.codec_name.clone(),
+            sample_rate: stream.sample_rate.clone(),
+            channels: stream.channels,
+            channel_layout: stream.channel_layout.clone(),
+        }
+    }
+}
+
+/// Check that every source has an audio stream and all of them match
+pub fn audio_matches(params: &[Option<AudioParams>]) -> bool {
+    match params.first() {
+        Some(Some(first)) => params.iter().all(|p| p.as_ref() == Some(first)),
+        _ => false,
+    }
+}
+
+/// Write the parts as an ffconcat list
+pub fn concat_list(parts: &[ConcatPart]) -> String {
+    let mut list = String::from("ffconcat version 1.0\n");
+    for part in parts {
+        // Close the quote, escape the single quote and reopen it
+        list.push_str(&format!("file '{}'\n", part.path.replace('\'', "'\\''")));
+        if part.inpoint > 0 {
+            list.push_str(&format!("inpoint {}\n", part.inpoint as f64 / 1000.0));
+        }
+        list.push_str(&format!("outpoint {}\n", part.outpoint as f64 / 1000.0));
+    }
+    list
+}
+
+/// Parse keyframe times in milliseconds from
+/// `ffprobe -show_entries packet=pts_time,flags -of csv=p=0` output
+pub fn parse_keyframes(csv: &str) -> Vec<u32> {
+    csv.lines()
+        .filter_map(|line| {
+            let (time, flags) = line.trim().split_once(',')?;
+            if !flags.contains('K') {
+                return None;
+            }
+            let seconds: f64 = time.parse().ok()?;
+            Some((seconds.max(0.0) * 1000.0).round() as u32)
+        })
+        .collect()
+}
+
+/// Check if `time` falls on one of the keyframes
+pub fn on_keyframe(keyframes: &[u32], time: u32) -> bool {
+    keyframes
+        .iter()
+        .any(|&k| k.abs_diff(time) <= KEYFRAME_TOLERANCE)
+}
+
+/// Probe the video keyframes around `time` in milliseconds.
+///
+/// Reading starts at the keyframe before `time`, so a short interval is
+/// enough to tell whether `time` itself is a keyframe.
+pub async fn keyframes_near(options: &FFprobeOptions, path: &str, time: u32) -> Result<Vec<u32>> {
+    let options = options
+        .clone()
+        .json_format(false)
+        .show_format(false)
+        .show_streams(false)
+        .add_args([
+            "-v".to_string(),
+            "error".to_string(),
+            "-select_streams".to_string(),
+            "v:0".to_string(),
+            "-read_intervals".to_string(),
+            format!("{}%+1", time as f64 / 1000.0),
+            "-show_entries".to_string(),
+            "packet=pts_time,flags".to_string(),
+            "-of".to_string(),
+            "csv=p=0".to_string(),
+        ]);
+    let csv = FFprobe::new()
+        .set_options(options)
+        .input(path)
+        .run_raw()
+        .await?;
+    Ok(parse_keyframes(&csv))
+}
+
+/// Probe the parameters of the first audio stream, `None` when there is no
+/// audio stream
+pub async fn audio_params(options: &FFprobeOptions, path: &str) -> Result<Option<AudioParams>> {
+    let options = options
+        .clone()
+        .json_format(true)
+        .show_format(false)
+        .show_streams(true)
+        .add_args(["-select_streams".to_string(), "a:0".to_string()]);
+    let info = FFprobe::new()
+        .set_options(options)
+        .input(path)
+        .run()
+        .await?;
+    Ok(info.streams.first().map(AudioParams::from_stream))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concat_list() {
+        let list = concat_list(&[
+            ConcatPart::new("/media/a.mp4", 0, 4000),
+            ConcatPart::new("/media/it's.mp4", 12_500, 15_000),
+        ]);
+        assert_eq!(
+            list,
+            "ffconcat version 1.0\n\
+             file '/media/a.mp4'\noutpoint 4\n\
+             file '/media/it'\\''s.mp4'\ninpoint 12.5\noutpoint 15\n"
+        );
+    }
+
+    #[test]
+    fn test_keyframes() {
+        let csv = "0.000000,K__\n0.040000,___\n2.002000,K__\n\n4.004000,__\n";
+        let keyframes = parse_keyframes(csv);
+        assert_eq!(keyframes, vec![0, 2002]);
+        assert!(on_keyframe(&keyframes, 2000));
+        assert!(!on_keyframe(&keyframes, 1000));
+    }
+
+    #[test]
+    fn test_audio_matches() {
+        let aac = |sample_rate: &str| {
+            Some(AudioParams {
+                codec: Some("aac".to_string()),
+                sample_rate: Some(sample_rate.to_string()),
+                channels: Some(2),
+                channel_layout: Some("stereo".to_string()),
+            })
+        };
+        assert!(audio_matches(&[aac("48000"), aac("48000")]));
+        assert!(!audio_matches(&[aac("48000"), aac("44100")]));
+        assert!(!audio_matches(&[aac("48000"), None]));
+        assert!(!audio_matches(&[None, None]));
+    }
+}
@@ -2,25 +2,37 @@
 
 use crate::cut::{
     EditSession,
-    material::Material,
-    protocol::{CutProtocol, ExportType},
-    segment::Segment,
-    stage::Stage,
-    track::Track,
+    concat::{self, ConcatPart},
+    import::{ImportReport, capcut},
+    keyframe,
+    material::{Dimension, Material},
+    protocol::{CutProtocol, ExportConfig, ExportType},
+    segment::{Anchor, FrameFit, Position, Segment, SegmentType, TextAlign, TextStyle, TimeRange},
+    stage::{ChannelLayout, Stage, StageBackground},
+    subtitle::{self, SubtitleMode},
+    track::{Track, TrackType},
 };
 use crate::error::{CutError, Result};
 use crate::ffmpeg::{
     FFmpeg,
-    codec::{AudioCodec, VideoCodec},
-    filter::Filter,
+    abr::AbrLadder,
+    codec::{AudioCodec, SubtitleCodec, VideoCodec},
+    filter::{Filter, util},
     input::Input,
+    loudnorm::{self, LoudnessTarget},
     output::Output,
+    progress::{CancelHandle, Progress},
+    stream::Stream,
+    watermark::{DelogoRegion, Watermark},
 };
-use crate::ffprobe::FFprobe;
+use crate::ffprobe::{FFprobe, LoudnormParams};
 use crate::{FFmpegOptions, FFprobeOptions};
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, path::Path};
 
+/// File name of the concat list written for a stream copy export
+const CONCAT_LIST: &str = "kiva-cut.ffconcat";
+
 /// Main video editor for composition and export
 #[derive(Debug)]
 pub struct Editor {
@@ -49,8 +61,27 @@ pub struct ExportOptions {
     pub video_bitrate: Option<i32>,
     /// Audio bitrate
     pub audio_bitrate: Option<i32>,
-    /// Custom FFmpeg options
+    /// Custom FFmpeg output options, passed as `-key value`
     pub custom_options: HashMap<String, String>,
+    /// How subtitle tracks are exported (for video exports)
+    #[serde(default)]
+    pub subtitle_mode: SubtitleMode,
+    /// Adaptive bitrate ladder; the output file is then the HLS master
+    /// playlist or the DASH manifest (for video exports)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub abr: Option<AbrLadder>,
+    /// Loudness target the audio is normalized to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loudness: Option<LoudnessTarget>,
+    /// Watermarks drawn over the whole export (for video exports)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub watermarks: Vec<Watermark>,
+    /// Regions logos are removed from (for video exports)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub delogo: Vec<DelogoRegion>,
+    /// Timeline range to export, the whole timeline if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<TimeRange>,
 }
 
 impl Editor {
@@ -102,6 +133,13 @@ impl Editor {
         self.load_from_json(&content)
     }
 
+    /// Load session from a CapCut / JianYing `draft_info.json`, reporting dropped features
+    pub fn load_from_capcut<P: AsRef<Path>>(&mut self, path: P) -> Result<ImportReport> {
+        let (session, report) = capcut::from_file(path)?;
+        self.session = session;
+        Ok(report)
+    }
+
     /// Save session to cut protocol
     pub fn save_to_protocol(&self) -> CutProtocol {
         self.session.to_protocol()
@@ -158,12 +196,17 @@ impl Editor {
                     .iter()
                     .find(|s| s.codec_type.as_deref() == Some("video"))
                 {
-                    // Fill dimensions
-                    if let (Some(width), Some(height)) = (video_stream.width, video_stream.height) {
+                    // Fill the upright, square-pixel dimensions
+                    if let Some((width, height)) = video_stream.display_dimensions() {
                         video.dimension.width = width;
                         video.dimension.height = height;
                     }
 
+                    // Fill rotation and sample aspect ratio
+                    let rotation = video_stream.rotation();
+                    video.rotation = (rotation != 0).then_some(rotation);
+                    video.sample_aspect_ratio = video_stream.sar();
+
                     // Fill frame rate
                     if let Some(r_frame_rate) = &video_stream.r_frame_rate {
                         if let Ok(fps) = Self::parse_fraction_to_float(r_frame_rate) {
@@ -268,11 +311,37 @@ impl Editor {
         self.add_track(Track::video())
     }
 
+    /// Create and add an image track
+    pub fn add_image_track(&mut self) -> String {
+        self.add_track(Track::image())
+    }
+
+    /// Create and add a text track
+    pub fn add_text_track(&mut self) -> String {
+        self.add_track(Track::text())
+    }
+
     /// Create and add an audio track
     pub fn add_audio_track(&mut self) -> String {
         self.add_track(Track::audio())
     }
 
+    /// Import a subtitle file (SRT, ASS or WebVTT) as a new subtitle track
+    pub fn import_subtitles<P: AsRef<Path>>(&mut self, path: P) -> Result<String> {
+        let track = subtitle::load_track(path)?;
+        Ok(self.add_track(track))
+    }
+
+    /// Export a subtitle track to an SRT file
+    pub fn export_subtitles<P: AsRef<Path>>(&self, track_id: &str, path: P) -> Result<()> {
+        let track = self
+            .session
+            .get_track(track_id)
+            .ok_or_else(|| CutError::invalid_params(format!("Track '{}' not found", track_id)))?;
+        std::fs::write(path, subtitle::to_srt(&subtitle::track_cues(track)))?;
+        Ok(())
+    }
+
     /// Add segment to track
     pub fn add_segment_to_track(&mut self, track_id: &str, segment: Segment) -> Result<()> {
         if let Some(track) = self.session.get_track_mut(track_id) {
@@ -338,16 +407,23 @@ impl Editor {
                         .iter()
                         .find(|s| s.codec_type.as_deref() == Some("video"))
                     {
-                        // Fill dimensions if missing
+                        // Fill the upright, square-pixel dimensions if missing
                         if video.dimension.width == 0 || video.dimension.height == 0 {
-                            if let (Some(width), Some(height)) =
-                                (video_stream.width, video_stream.height)
-                            {
+                            if let Some((width, height)) = video_stream.display_dimensions() {
                                 video.dimension.width = width;
                                 video.dimension.height = height;
                             }
                         }
 
+                        // Fill rotation and sample aspect ratio if missing
+                        if video.rotation.is_none() {
+                            let rotation = video_stream.rotation();
+                            video.rotation = (rotation != 0).then_some(rotation);
+                        }
+                        if video.sample_aspect_ratio.is_none() {
+                            video.sample_aspect_ratio = video_stream.sar();
+                        }
+
                         // Fill frame rate if missing
                         if video.fps.is_none() {
                             if let Some(r_frame_rate) = &video_stream.r_frame_rate {
@@ -487,152 +563,570 @@ impl Editor {
 
     /// Export video using the built-in composition engine
     pub async fn export(&self, options: ExportOptions) -> Result<()> {
+        let scratch = tempfile::tempdir()?;
+        let (mut ffmpeg, _) = self.prepare_export(&options, scratch.path()).await?;
+        ffmpeg.run().await
+    }
+
+    /// Export with progress reports against the exported duration; cancelling
+    /// stops ffmpeg and removes the partial output
+    pub async fn export_with_progress<F>(
+        &self,
+        options: ExportOptions,
+        cancel: &CancelHandle,
+        on_progress: F,
+    ) -> Result<()>
+    where
+        F: FnMut(Progress),
+    {
+        let scratch = tempfile::tempdir()?;
+        let (mut ffmpeg, duration) = self.prepare_export(&options, scratch.path()).await?;
+        ffmpeg
+            .run_with_progress(Some(duration), cancel, on_progress)
+            .await
+    }
+
+    /// Build the export command and return it with the exported duration.
+    ///
+    /// Files the command reads are written into `scratch`, which has to
+    /// outlive the ffmpeg run.
+    async fn prepare_export(
+        &self,
+        options: &ExportOptions,
+        scratch: &Path,
+    ) -> Result<(FFmpeg, u32)> {
+        match self.range_editor(options)? {
+            Some((editor, options)) => editor.prepare_timeline_export(&options, scratch).await,
+            None => self.prepare_timeline_export(options, scratch).await,
+        }
+    }
+
+    /// Build the export command of the whole timeline, taking the lossless
+    /// concat path when it is a plain cut list whose cuts start on keyframes
+    async fn prepare_timeline_export(
+        &self,
+        options: &ExportOptions,
+        scratch: &Path,
+    ) -> Result<(FFmpeg, u32)> {
+        let duration = self.session.total_duration();
         self.validate()?;
+        if let Some((parts, with_audio)) = self.concat_parts(options)
+            && self.keyframe_aligned(&parts).await
+            && (!with_audio || self.audio_matches(&parts).await)
+        {
+            let ffmpeg = self.concat_command(&parts, options, with_audio, scratch)?;
+            return Ok((ffmpeg, duration));
+        }
+        self.write_subtitle_files(scratch)?;
 
-        let mut ffmpeg = FFmpeg::new();
-        ffmpeg.set_ffmpeg_options(self.ffmpeg_options.clone());
+        // 响度：先测量整条时间线的混音，再线性归一化；试运行不做测量
+        if let Some(ref target) = options.loudness
+            && !self.ffmpeg_options.dry_run
+        {
+            let measured = self.analyze_loudness(target).await?;
+            let ffmpeg = self.compose_export(options, Some(&measured), scratch)?;
+            return Ok((ffmpeg, duration));
+        }
+        Ok((self.compose_export(options, None, scratch)?, duration))
+    }
 
-        // Add inputs for all referenced materials
-        let mut material_inputs: HashMap<String, Input> = HashMap::new();
-        for track in &self.session.tracks {
-            for segment in &track.segments {
-                if !material_inputs.contains_key(&segment.material_id) {
-                    if let Some(material) = self.session.get_material(&segment.material_id) {
-                        let input = ffmpeg.add_input(Input::with_time(
-                            segment.source_timerange.start as f32 / 1000.0,
-                            segment.source_timerange.duration as f32 / 1000.0,
-                            material.src(),
-                        ));
-                        material_inputs.insert(segment.material_id.clone(), input);
-                    }
-                }
+    /// Editor over the export range, rebased to start at zero, with options
+    /// for exporting all of it; `None` when the whole timeline is exported
+    fn range_editor(&self, options: &ExportOptions) -> Result<Option<(Editor, ExportOptions)>> {
+        let Some(range) = options.range else {
+            return Ok(None);
+        };
+        // 导出区间只保留与时间线重叠的部分
+        let timeline = TimeRange::new(0, self.session.total_duration());
+        let Some(range) = range.intersection(&timeline) else {
+            return Err(CutError::invalid_params(format!(
+                "Export range {}-{}ms does not overlap the timeline",
+                range.start,
+                range.end()
+            )));
+        };
+
+        let editor = Editor {
+            ffmpeg_options: self.ffmpeg_options.clone(),
+            ffprobe_options: self.ffprobe_options.clone(),
+            session: self.session.clip_to_range(&range),
+        };
+        // 水印和去标的时间窗同样平移到区间起点
+        let (start, end) = (range.start, range.end());
+        let options = ExportOptions {
+            range: None,
+            watermarks: options
+                .watermarks
+                .iter()
+                .filter_map(|w| w.clip_to(start, end))
+                .collect(),
+            delogo: options
+                .delogo
+                .iter()
+                .filter_map(|r| r.clip_to(start, end))
+                .collect(),
+            ..options.clone()
+        };
+        Ok(Some((editor, options)))
+    }
+
+    /// Build a stream copy export through the concat demuxer, or `None` when
+    /// the timeline needs the composition engine.
+    ///
+    /// Keyframe alignment and audio parameters are not checked here; `export`
+    /// probes them before taking this path and re-encodes otherwise. The
+    /// concat list is written into `scratch`, which has to outlive the ffmpeg
+    /// run.
+    pub fn build_concat_export(
+        &self,
+        options: &ExportOptions,
+        scratch: &Path,
+    ) -> Result<Option<FFmpeg>> {
+        if let Some((editor, options)) = self.range_editor(options)? {
+            return editor.build_concat_export(&options, scratch);
+        }
+        self.validate()?;
+        let Some((parts, with_audio)) = self.concat_parts(options) else {
+            return Ok(None);
+        };
+        Ok(Some(
+            self.concat_command(&parts, options, with_audio, scratch)?,
+        ))
+    }
+
+    /// Collect the source ranges of a timeline that can be stream copied.
+    ///
+    /// That is a single video track of contiguous, untransformed segments
+    /// from same-codec materials at stage size, no encoding settings or
+    /// custom options, and either no audio or an audio track mirroring the
+    /// video segments. The flag tells whether the source audio is copied
+    /// along.
+    fn concat_parts(&self, options: &ExportOptions) -> Option<(Vec<ConcatPart>, bool)> {
+        let copy = |codec: &Option<String>| codec.as_deref().is_none_or(|c| c == "copy");
+        if !matches!(options.export_type, ExportType::Video)
+            || options.abr.is_some()
+            || options.loudness.is_some()
+            || !options.watermarks.is_empty()
+            || !options.delogo.is_empty()
+            || !copy(&options.video_codec)
+            || !copy(&options.audio_codec)
+            || options.quality.is_some()
+            || options.video_bitrate.is_some()
+            || options.audio_bitrate.is_some()
+            || !options.custom_options.is_empty()
+        {
+            return None;
+        }
+
+        let visual: Vec<&Track> = self
+            .session
+            .visual_tracks()
+            .into_iter()
+            .filter(|t| t.enabled && !t.segments.is_empty())
+            .collect();
+        let [track] = visual.as_slice() else {
+            return None;
+        };
+        if track.track_type != TrackType::Video
+            || !track.transitions.is_empty()
+            || track.opacity < 1.0
+            || track.ffmpeg_blend_mode().ok()?.is_some()
+        {
+            return None;
+        }
+
+        if self
+            .session
+            .subtitle_tracks()
+            .iter()
+            .any(|t| t.enabled && !subtitle::track_cues(t).is_empty())
+        {
+            return None;
+        }
+
+        let stage = &self.session.stage;
+        let mut parts = Vec::new();
+        let mut codec = None;
+        let mut time = 0;
+        for segment in &track.segments {
+            let Some(Material::Video(video)) = self.session.get_material(&segment.material_id)
+            else {
+                return None;
+            };
+            let material_codec = video.codec.as_deref()?;
+            if segment.target_timerange.start != time
+                || segment.text.is_some()
+                || segment.scale.is_some_and(|s| s != video.dimension)
+                || segment.fit.is_some()
+                || segment.crop.is_some()
+                || !segment.transform.is_default()
+                || segment.position.is_some_and(|p| p.x != 0 || p.y != 0)
+                || segment.needs_speed_adjustment()
+                || !segment.keyframes.is_empty()
+                || video.needs_orientation()
+                || video.dimension.width != stage.width
+                || video.dimension.height != stage.height
+                || video
+                    .fps
+                    .is_some_and(|fps| (fps as f64 - stage.fps.as_f64()).abs() > 0.01)
+                || *codec.get_or_insert(material_codec) != material_codec
+            {
+                return None;
             }
+            parts.push(ConcatPart::new(
+                video.src.clone(),
+                segment.source_timerange.start,
+                segment.source_end_time(),
+            ));
+            time = segment.target_end_time();
         }
 
-        // Create stage background
-        let mut stage_bg = ffmpeg.add_filter_without_inputs(Filter::color(
-            self.session.stage.width,
-            self.session.stage.height,
-            self.session.total_duration() as f64 / 1000.0,
-        ));
+        // 音频：只能原样带上视频文件里的音轨
+        let audio: Vec<&Track> = self
+            .session
+            .audio_tracks()
+            .into_iter()
+            .filter(|t| t.enabled && !t.muted && !t.segments.is_empty())
+            .collect();
+        let with_audio = match audio.as_slice() {
+            [] => false,
+            [audio]
+                if audio.volume >= 1.0
+                    && audio.transitions.is_empty()
+                    && audio.segments.len() == track.segments.len()
+                    && audio.segments.iter().zip(&track.segments).all(|(a, v)| {
+                        a.material_id == v.material_id
+                            && a.target_timerange == v.target_timerange
+                            && a.source_timerange == v.source_timerange
+                            && a.keyframes.is_empty()
+                            && a.audio.is_default()
+                    }) =>
+            {
+                true
+            }
+            _ => return None,
+        };
 
-        // Process video tracks in reverse order (bottom to top)
-        let video_tracks = self.session.video_tracks();
-        for track in video_tracks.iter().rev() {
-            if !track.enabled {
-                continue;
+        Some((parts, with_audio))
+    }
+
+    /// Check that every cut starts on a keyframe; probe failures count as
+    /// misaligned so the export falls back to re-encoding
+    async fn keyframe_aligned(&self, parts: &[ConcatPart]) -> bool {
+        for part in parts.iter().filter(|p| p.inpoint > 0) {
+            match concat::keyframes_near(&self.ffprobe_options, &part.path, part.inpoint).await {
+                Ok(keyframes) if concat::on_keyframe(&keyframes, part.inpoint) => {}
+                _ => return false,
             }
+        }
+        true
+    }
 
-            for segment in &track.segments {
-                if let Some(input) = material_inputs.get(&segment.material_id) {
-                    let mut f_last_v = input.v();
+    /// Check that every part carries audio encoded the same way; probe
+    /// failures count as a mismatch so the export falls back to re-encoding
+    async fn audio_matches(&self, parts: &[ConcatPart]) -> bool {
+        let mut params = Vec::new();
+        for part in parts {
+            match concat::audio_params(&self.ffprobe_options, &part.path).await {
+                Ok(audio) => params.push(audio),
+                Err(_) => return false,
+            }
+        }
+        concat::audio_matches(&params)
+    }
 
-                    // Apply time-based trimming
-                    let target_start = segment.target_timerange.start as f64 / 1000.0;
-                    let target_duration = segment.target_timerange.duration as f64 / 1000.0;
+    /// Concat list of the parts
+    fn concat_list(parts: &[ConcatPart]) -> String {
+        // Relative paths in the list resolve against the list file, make them absolute
+        let parts: Vec<ConcatPart> = parts
+            .iter()
+            .map(|part| ConcatPart {
+                path: std::path::absolute(&part.path)
+                    .map(|p| p.to_string_lossy().into_owned())
+                    .unwrap_or_else(|_| part.path.clone()),
+                ..part.clone()
+            })
+            .collect();
+        concat::concat_list(&parts)
+    }
 
-                    // 视频流：缩放视频
-                    if let Some(scale) = segment.scale {
-                        f_last_v =
-                            ffmpeg.add_filter(Filter::scale(scale.width, scale.height), [f_last_v]);
-                    }
+    /// Write the concat list of the parts into `scratch` and build the stream
+    /// copy command reading it
+    fn concat_command(
+        &self,
+        parts: &[ConcatPart],
+        options: &ExportOptions,
+        with_audio: bool,
+        scratch: &Path,
+    ) -> Result<FFmpeg> {
+        let list = scratch.join(CONCAT_LIST);
+        std::fs::write(&list, Self::concat_list(parts))?;
 
-                    // 视频流：是否需要倍速
-                    if segment.needs_speed_adjustment() {
-                        let speed = segment.playback_speed();
-                        f_last_v =
-                            ffmpeg.add_filter(Filter::setpts(format!("1/{speed}*PTS")), [f_last_v]);
-                    }
+        let mut ffmpeg = FFmpeg::new();
+        ffmpeg.set_ffmpeg_options(self.ffmpeg_options.clone());
+        let input = ffmpeg.add_input(Input::with_concat(list.to_string_lossy()));
 
-                    // 视频流：设置本段视频在时间线上的位置
-                    f_last_v = ffmpeg
-                        .add_filter(Filter::setpts(format!("PTS+{target_start}/TB")), [f_last_v]);
-
-                    // 视频流：合并视频流到主舞台
-                    let x = segment.position.map(|p| p.x).unwrap_or(0);
-                    let y = segment.position.map(|p| p.y).unwrap_or(0);
-                    stage_bg = ffmpeg.add_filter(
-                        Filter::overlay_with_enable(
-                            x,
-                            y,
-                            format!(
-                                "'between(t,{},{})'",
-                                target_start,
-                                target_start + target_duration
-                            ),
-                        ),
-                        [stage_bg, f_last_v],
-                    );
-                }
+        let mut output = Output::with_simple(&options.output_file)
+            .map_stream(input.v())
+            .video_codec(VideoCodec::Copy);
+        if with_audio {
+            output = output.map_stream(input.a()).audio_codec(AudioCodec::Copy);
+        }
+        ffmpeg.add_output(output.mov_flags("faststart"));
+        Ok(ffmpeg)
+    }
+
+    /// Build the FFmpeg command for an export without running it.
+    ///
+    /// A loudness target is applied in a single dynamic pass here; `export`
+    /// measures the mix first and normalizes linearly. Subtitle tracks are
+    /// written as SRT files into `scratch`, which has to outlive the ffmpeg
+    /// run.
+    pub fn build_export(&self, options: &ExportOptions, scratch: &Path) -> Result<FFmpeg> {
+        if let Some((editor, options)) = self.range_editor(options)? {
+            return editor.build_export(&options, scratch);
+        }
+        self.validate()?;
+        self.write_subtitle_files(scratch)?;
+        self.compose_export(options, None, scratch)
+    }
+
+    /// Build the first loudness pass: the timeline's audio mix measured by
+    /// `loudnorm`, with the output discarded
+    pub fn build_loudness_analysis(&self, target: &LoudnessTarget) -> Result<FFmpeg> {
+        self.validate()?;
+        target.validate()?;
+
+        let mut ffmpeg = FFmpeg::new();
+        ffmpeg.set_ffmpeg_options(loudnorm::analysis_options(&self.ffmpeg_options));
+        let sound = self.mix_audio(&mut ffmpeg);
+        let measured = ffmpeg.add_filter(target.analyze_filter(), [sound]);
+        ffmpeg.add_output(loudnorm::null_output(measured));
+        Ok(ffmpeg)
+    }
+
+    /// Measure the loudness of the timeline's audio mix
+    pub async fn analyze_loudness(&self, target: &LoudnessTarget) -> Result<LoudnormParams> {
+        let log = self.build_loudness_analysis(target)?.run_with_log().await?;
+        loudnorm::parse_log(&log)
+    }
+
+    /// Build the export command, normalizing loudness with `measured` values
+    /// from a first pass when given; subtitle files are read from `scratch`
+    fn compose_export(
+        &self,
+        options: &ExportOptions,
+        measured: Option<&LoudnormParams>,
+        scratch: &Path,
+    ) -> Result<FFmpeg> {
+        self.validate()?;
+        if let Some(ref target) = options.loudness {
+            target.validate()?;
+        }
+        if !options.watermarks.is_empty() || !options.delogo.is_empty() {
+            if !matches!(options.export_type, ExportType::Video) {
+                return Err(CutError::invalid_params(
+                    "Watermarks and delogo need a video export",
+                ));
+            }
+            for region in &options.delogo {
+                region.validate()?;
+            }
+            for watermark in &options.watermarks {
+                watermark.validate()?;
+            }
+        }
+        if let Some(ref ladder) = options.abr {
+            if !matches!(options.export_type, ExportType::Video) {
+                return Err(CutError::invalid_params(
+                    "Bitrate ladders need a video export",
+                ));
+            }
+            // CRF 会压过各码率档的 -b:v:N
+            if options.quality.is_some() || options.video_bitrate.is_some() {
+                return Err(CutError::invalid_params(
+                    "Bitrate ladders set the video bitrate per rendition",
+                ));
             }
+            ladder.validate()?;
         }
 
-        // Handle audio tracks
-        let audio_tracks = self.session.audio_tracks();
-        let mut audio_inputs = Vec::new();
+        let mut ffmpeg = FFmpeg::new();
+        ffmpeg.set_ffmpeg_options(self.ffmpeg_options.clone());
 
-        for track in &audio_tracks {
-            if !track.enabled || track.muted {
+        // Create stage background
+        let mut stage_bg = self.stage_background(&mut ffmpeg);
+
+        // Process video and image tracks in reverse order (bottom to top)
+        let visual_tracks = self.session.visual_tracks();
+        for track in visual_tracks.iter().rev() {
+            if !track.enabled {
                 continue;
             }
 
+            let blend_mode = track.ffmpeg_blend_mode()?;
+
             for segment in &track.segments {
-                if let Some(input) = material_inputs.get(&segment.material_id) {
-                    let mut f_last_a = input.a();
+                // 文字：直接绘制到主舞台
+                if let Some(text) = &segment.text {
+                    stage_bg =
+                        ffmpeg.add_filter(self.draw_text(segment, text, track.opacity), [stage_bg]);
+                    continue;
+                }
 
-                    // Apply time-based trimming for audio
-                    let source_start = segment.source_timerange.start as f64 / 1000.0;
-                    let source_duration = segment.source_timerange.duration as f64 / 1000.0;
-                    let target_start = segment.target_timerange.start as f64 / 1000.0;
-                    let target_duration = segment.target_timerange.duration as f64 / 1000.0;
+                // 转场：由转场链的第一个片段统一处理
+                if track.transition_to(&segment.id).is_some() {
+                    continue;
+                }
+                let chain = Self::transition_chain(track, segment);
+
+                let Some(mut f_last_v) = self.chain_video(&mut ffmpeg, track, &chain)? else {
+                    continue;
+                };
+
+                let target_start = segment.target_timerange.start as f64 / 1000.0;
+                let target_end = chain[chain.len() - 1].target_end_time() as f64 / 1000.0;
+
+                // 视频流：设置本段视频在时间线上的位置
+                f_last_v =
+                    ffmpeg.add_filter(Filter::setpts(format!("PTS+{target_start}/TB")), [f_last_v]);
+
+                // 视频流：合并视频流到主舞台
+                let x = segment.position.map(|p| p.x).unwrap_or(0);
+                let y = segment.position.map(|p| p.y).unwrap_or(0);
+                let enable = format!("'between(t,{},{})'", target_start, target_end);
+
+                // 视频流：旋转后的画面绕锚点摆放，与预览一致
+                let keyframes = &segment.keyframes;
+                let (shift_x, shift_y) = match self.segment_size(segment) {
+                    Some(size) if keyframes.rotation.is_empty() => {
+                        let origin = segment.transform.origin(Position::new(x, y), size);
+                        (origin.x - x, origin.y - y)
+                    }
+                    _ => (0, 0),
+                };
+
+                if let Some(mode) = blend_mode {
+                    if segment.keyframes.has_transform() {
+                        return Err(CutError::invalid_params(format!(
+                            "Segment '{}' animates its transform, which blend modes do not support",
+                            segment.id
+                        )));
+                    }
+                    stage_bg = self.blend_onto_stage(
+                        &mut ffmpeg,
+                        stage_bg,
+                        f_last_v,
+                        segment,
+                        (x + shift_x, y + shift_y),
+                        mode,
+                        track.opacity,
+                        enable,
+                    );
+                    continue;
+                }
 
-                    if source_start > 0.0 || source_duration != target_duration {
-                        f_last_a = ffmpeg
-                            .add_filter(Filter::atrim(source_start, source_duration), [f_last_a]);
+                // 视频流：轨道透明度
+                if track.opacity < 1.0 {
+                    f_last_v = ffmpeg.add_filter(Filter::format("yuva420p"), [f_last_v]);
+                    f_last_v = ffmpeg.add_filter(Filter::alpha(track.opacity), [f_last_v]);
+                }
 
-                        // Reset audio PTS
-                        f_last_a = ffmpeg.add_filter(Filter::asetpts("PTS-STARTPTS"), [f_last_a]);
+                // 视频流：位置关键帧，时间相对于片段起点
+                let overlay = if keyframes.x.is_empty()
+                    && keyframes.y.is_empty()
+                    && keyframes.rotation.is_empty()
+                {
+                    Filter::overlay_with_enable(x + shift_x, y + shift_y, enable)
+                } else {
+                    let time = format!("(t-{})", target_start);
+                    let mut x_expr =
+                        keyframe::expression(&keyframes.x, &time).unwrap_or(x.to_string());
+                    let mut y_expr =
+                        keyframe::expression(&keyframes.y, &time).unwrap_or(y.to_string());
+
+                    if shift_x != 0 || shift_y != 0 {
+                        x_expr = format!("{}{:+}", x_expr, shift_x);
+                        y_expr = format!("{}{:+}", y_expr, shift_y);
                     }
 
-                    // Apply speed adjustment for audio
-                    if segment.needs_speed_adjustment() {
-                        let speed = segment.playback_speed();
-                        f_last_a = ffmpeg.add_filter(Filter::atempo(speed), [f_last_a]);
+                    // Rotated frames grow to their diagonal, keep them centred
+                    if let Some(rotation) = keyframe::expression(&keyframes.rotation, &time)
+                        && let Some(size) = self.segment_size(segment)
+                    {
+                        x_expr = format!("{}+({}-w)/2", x_expr, size.width);
+                        y_expr = format!("{}+({}-h)/2", y_expr, size.height);
+
+                        // Turning around an off-centre anchor moves the centre
+                        let anchor = segment.transform.anchor;
+                        if anchor != Anchor::CENTER {
+                            let dx = (anchor.x - 0.5) * size.width as f64;
+                            let dy = (anchor.y - 0.5) * size.height as f64;
+                            let angle = format!("(({})*PI/180)", rotation);
+                            x_expr =
+                                format!("{}+({})*(1-cos{angle})+({})*sin{angle}", x_expr, dx, dy);
+                            y_expr =
+                                format!("{}+({})*(1-cos{angle})-({})*sin{angle}", y_expr, dy, dx);
+                        }
                     }
+                    Filter::overlay_expr(&x_expr, &y_expr, enable)
+                };
+                stage_bg = ffmpeg.add_filter(overlay, [stage_bg, f_last_v]);
+            }
+        }
 
-                    // Add delay for positioning in time
-                    if target_start > 0.0 {
-                        f_last_a = ffmpeg.add_filter(
-                            Filter::with_name("adelay")
-                                .param(format!("{}ms", (target_start * 1000.0) as i32)),
-                            [f_last_a],
-                        );
-                    }
+        // 去标：先在合成后的画面上抹掉台标
+        for region in &options.delogo {
+            stage_bg = region.apply(&mut ffmpeg, stage_bg);
+        }
 
-                    audio_inputs.push(f_last_a);
+        // 字幕：烧录到画面，或作为软字幕流封装
+        let mut subtitle_streams = Vec::new();
+        if matches!(options.export_type, ExportType::Video) {
+            for (name, _) in self.subtitle_files() {
+                let path = scratch.join(name).to_string_lossy().into_owned();
+                match options.subtitle_mode {
+                    SubtitleMode::Burn => {
+                        stage_bg = ffmpeg.add_filter(Filter::subtitles(&path), [stage_bg]);
+                    }
+                    SubtitleMode::Soft => {
+                        let input = ffmpeg.add_input(Input::with_simple(&path));
+                        subtitle_streams.push(input.s());
+                    }
                 }
             }
         }
 
-        // Mix all audio tracks
-        let sound_bg = if audio_inputs.is_empty() {
-            ffmpeg.add_filter_without_inputs(Filter::anullsrc(
-                self.session.total_duration() as f32 / 1000.0,
-            ))
-        } else if audio_inputs.len() == 1 {
-            audio_inputs[0].clone()
-        } else {
-            ffmpeg.add_filter(Filter::amix(audio_inputs.len() as i32), audio_inputs)
-        };
+        // 水印：盖在所有画面和烧录字幕之上
+        for watermark in &options.watermarks {
+            stage_bg = watermark.apply(&mut ffmpeg, stage_bg);
+        }
+
+        // 输出像素格式
+        stage_bg = ffmpeg.add_filter(
+            Filter::format(self.session.stage.pix_fmt.clone()),
+            [stage_bg],
+        );
+
+        // Handle audio tracks
+        let mut sound_bg = self.mix_audio(&mut ffmpeg);
+
+        // 响度：有首遍测量值时做线性增益，否则单遍动态归一化
+        if let Some(ref target) = options.loudness {
+            sound_bg = ffmpeg.add_filter(target.normalize_filter(measured)?, [sound_bg]);
+            sound_bg =
+                ffmpeg.add_filter(Filter::resample(self.session.stage.sample_rate), [sound_bg]);
+        }
 
         // Create output
         let mut output = Output::with_simple(&options.output_file);
+        for (key, value) in &options.custom_options {
+            output = output.option(key.as_str(), value.as_str());
+        }
 
         match options.export_type {
             ExportType::Video => {
                 output = output
-                    .map_stream(stage_bg)
-                    .map_stream(sound_bg)
                     .video_codec(VideoCodec::from(
                         options.video_codec.as_deref().unwrap_or("libx264"),
                     ))
@@ -652,6 +1146,35 @@ impl Editor {
                     output = output.audio_bitrate(bitrate);
                 }
 
+                // 多码率：拆分画面并打包成 HLS/DASH
+                if let Some(ref ladder) = options.abr {
+                    if !subtitle_streams.is_empty() {
+                        return Err(CutError::invalid_params(
+                            "Bitrate ladders cannot carry soft subtitles",
+                        ));
+                    }
+                    output = ladder.apply(&mut ffmpeg, stage_bg, Some(sound_bg), output);
+                    ffmpeg.add_output(output);
+                    return Ok(ffmpeg);
+                }
+                output = output.map_stream(stage_bg).map_stream(sound_bg);
+
+                if !subtitle_streams.is_empty() {
+                    // WebM only carries WebVTT subtitles, MKV keeps SRT as is
+                    let codec = match Path::new(&options.output_file)
+                        .extension()
+                        .and_then(|e| e.to_str())
+                    {
+                        Some("webm") => SubtitleCodec::WebVTT,
+                        Some("mkv") => SubtitleCodec::SRT,
+                        _ => SubtitleCodec::MovText,
+                    };
+                    for stream in subtitle_streams {
+                        output = output.map_stream(stream);
+                    }
+                    output = output.subtitle_codec(codec);
+                }
+
                 output = output.mov_flags("faststart");
             }
             ExportType::Audio => {
@@ -667,7 +1190,562 @@ impl Editor {
 
         ffmpeg.add_output(output);
 
-        ffmpeg.run().await
+        Ok(ffmpeg)
+    }
+
+    /// Mix the enabled audio tracks into one stream, or silence when there
+    /// are none
+    fn mix_audio(&self, ffmpeg: &mut FFmpeg) -> Stream {
+        let audio_tracks = self.session.audio_tracks();
+        let mut audio_inputs = Vec::new();
+
+        for track in &audio_tracks {
+            if !track.enabled || track.muted {
+                continue;
+            }
+
+            for segment in &track.segments {
+                // Segments joined by transitions are crossfaded into one stream
+                if track.transition_to(&segment.id).is_some() {
+                    continue;
+                }
+                let chain = Self::transition_chain(track, segment);
+                if chain.iter().all(|s| s.audio.muted) {
+                    continue;
+                }
+
+                let Some(mut f_last_a) = self.segment_audio(ffmpeg, track, segment) else {
+                    continue;
+                };
+                for pair in chain.windows(2) {
+                    let Some(next) = self.segment_audio(ffmpeg, track, pair[1]) else {
+                        continue;
+                    };
+                    let overlap = pair[0].target_end_time() - pair[1].target_timerange.start;
+                    f_last_a = ffmpeg.add_filter(
+                        Filter::acrossfade(overlap as f64 / 1000.0),
+                        [f_last_a, next],
+                    );
+                }
+
+                // Add delay for positioning in time
+                let target_start = segment.target_timerange.start;
+                if target_start > 0 {
+                    f_last_a = ffmpeg.add_filter(
+                        Filter::with_name("adelay").param(format!("{}ms", target_start)),
+                        [f_last_a],
+                    );
+                }
+
+                audio_inputs.push(f_last_a);
+            }
+        }
+
+        // Mix all audio tracks
+        if audio_inputs.is_empty() {
+            let stage = &self.session.stage;
+            ffmpeg.add_filter_without_inputs(Filter::anullsrc_with(
+                self.session.total_duration() as f32 / 1000.0,
+                stage.sample_rate,
+                stage.channel_layout.as_str(),
+            ))
+        } else if audio_inputs.len() == 1 {
+            audio_inputs[0].clone()
+        } else {
+            ffmpeg.add_filter(Filter::amix(audio_inputs.len() as i32), audio_inputs)
+        }
+    }
+
+    /// Build the stage background for the whole timeline: a solid colour, or
+    /// an image scaled to the stage, at the stage frame rate
+    fn stage_background(&self, ffmpeg: &mut FFmpeg) -> Stream {
+        let stage = &self.session.stage;
+        let duration = self.session.total_duration() as f64 / 1000.0;
+        match &stage.background {
+            StageBackground::Color(color) => ffmpeg.add_filter_without_inputs(Filter::color_with(
+                color,
+                stage.width,
+                stage.height,
+                duration,
+                stage.fps.to_string(),
+            )),
+            StageBackground::Image(path) => {
+                let input = ffmpeg.add_input(
+                    Input::with_simple(path)
+                        .duration(duration as f32)
+                        .option("loop", "1"),
+                );
+                let mut f_last_v =
+                    ffmpeg.add_filter(Filter::scale(stage.width, stage.height), [input.v()]);
+                f_last_v = ffmpeg.add_filter(Filter::with_name("setsar").param("1"), [f_last_v]);
+                f_last_v = ffmpeg.add_filter(Filter::fps(stage.fps.to_string()), [f_last_v]);
+                ffmpeg.add_filter(Filter::format(stage.pix_fmt.clone()), [f_last_v])
+            }
+        }
+    }
+
+    /// Blend a segment onto the stage with an ffmpeg `blend` mode.
+    ///
+    /// `blend` needs equally sized inputs, so the stage region under the
+    /// segment is cropped out, blended and overlaid back in place.
+    #[allow(clippy::too_many_arguments)]
+    fn blend_onto_stage(
+        &self,
+        ffmpeg: &mut FFmpeg,
+        stage_bg: Stream,
+        segment_v: Stream,
+        segment: &Segment,
+        (x, y): (i32, i32),
+        mode: &str,
+        opacity: f32,
+        enable: String,
+    ) -> Stream {
+        let stage = &self.session.stage;
+        let Some(size) = self
+            .segment_size(segment)
+            .map(|size| segment.transform.rotated_size(size))
+        else {
+            return stage_bg;
+        };
+
+        // Visible part of the segment on the stage
+        let left = x.max(0);
+        let top = y.max(0);
+        let right = (x + size.width).min(stage.width);
+        let bottom = (y + size.height).min(stage.height);
+        if left >= right || top >= bottom {
+            return stage_bg;
+        }
+        let (width, height) = (right - left, bottom - top);
+
+        let mut f_last_v = segment_v;
+        if width != size.width || height != size.height {
+            f_last_v =
+                ffmpeg.add_filter(Filter::crop(width, height, left - x, top - y), [f_last_v]);
+        }
+
+        let stages = ffmpeg.add_filter_with_outputs(Filter::split(2), [stage_bg], 2);
+        let region = ffmpeg.add_filter(Filter::crop(width, height, left, top), [&stages[1]]);
+        let blended = ffmpeg.add_filter(Filter::blend(mode, opacity), [f_last_v, region]);
+
+        ffmpeg.add_filter(
+            Filter::overlay_with_enable(left, top, enable),
+            [stages[0].clone(), blended],
+        )
+    }
+
+    /// SRT file name and contents of every enabled, non-empty subtitle track
+    fn subtitle_files(&self) -> Vec<(String, String)> {
+        self.session
+            .subtitle_tracks()
+            .into_iter()
+            .filter(|track| track.enabled)
+            .filter_map(|track| {
+                let cues = subtitle::track_cues(track);
+                (!cues.is_empty()).then(|| {
+                    (
+                        format!("kiva-cut-{}.srt", track.id),
+                        subtitle::to_srt(&cues),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Write the subtitle files into `dir`.
+    ///
+    /// The `subtitles` filter and subtitle inputs read from files, so the
+    /// files have to exist before ffmpeg runs.
+    fn write_subtitle_files(&self, dir: &Path) -> Result<()> {
+        for (name, srt) in self.subtitle_files() {
+            std::fs::write(dir.join(name), srt)?;
+        }
+        Ok(())
+    }
+
+    /// Build the drawtext filter for a text segment.
+    ///
+    /// The text box starts at the segment position and spans the segment
+    /// width, or the rest of the stage; the text is aligned inside it.
+    fn draw_text(&self, segment: &Segment, text: &TextStyle, opacity: f32) -> Filter {
+        let x = segment.position.map(|p| p.x).unwrap_or(0);
+        let y = segment.position.map(|p| p.y).unwrap_or(0);
+        let box_width = segment
+            .scale
+            .map(|s| s.width)
+            .unwrap_or(self.session.stage.width - x);
+        let x_expr = match text.align {
+            TextAlign::Left => x.to_string(),
+            TextAlign::Center => format!("{}+({}-text_w)/2", x, box_width),
+            TextAlign::Right => format!("{}+{}-text_w", x, box_width),
+        };
+
+        let mut filter = Filter::drawtext(&text.content);
+        if let Some(font) = &text.font {
+            filter = filter.param(util::font_option(font));
+        }
+        filter = filter.params([
+            format!("fontsize={}", text.font_size),
+            format!("fontcolor={}", util::escape_value(&text.color)),
+            format!("x={}", x_expr),
+            format!("y={}", y),
+        ]);
+        if let Some(stroke_color) = &text.stroke_color
+            && text.stroke_width > 0
+        {
+            filter = filter.params([
+                format!("borderw={}", text.stroke_width),
+                format!("bordercolor={}", util::escape_value(stroke_color)),
+            ]);
+        }
+        if opacity < 1.0 {
+            filter = filter.param(format!("alpha={}", opacity));
+        }
+
+        let start = segment.target_timerange.start as f64 / 1000.0;
+        let end = segment.target_end_time() as f64 / 1000.0;
+        filter.param(format!("enable='between(t,{},{})'", start, end))
+    }
+
+    /// Collect a segment and the segments it transitions into, in timeline order
+    fn transition_chain<'a>(track: &'a Track, segment: &'a Segment) -> Vec<&'a Segment> {
+        let mut chain = vec![segment];
+        while let Some(next) = track
+            .transition_from(&chain[chain.len() - 1].id)
+            .and_then(|t| track.get_segment(&t.to))
+        {
+            if chain.iter().any(|s| s.id == next.id) {
+                break;
+            }
+            chain.push(next);
+        }
+        chain
+    }
+
+    /// Build the video of a transition chain, starting at zero.
+    ///
+    /// `xfade` needs inputs of one size, frame rate and pixel format, so
+    /// chained clips are normalized to the first clip before fading.
+    fn chain_video(
+        &self,
+        ffmpeg: &mut FFmpeg,
+        track: &Track,
+        chain: &[&Segment],
+    ) -> Result<Option<Stream>> {
+        let Some(mut f_last_v) = self.segment_video(ffmpeg, chain[0]) else {
+            return Ok(None);
+        };
+        if chain.len() == 1 {
+            return Ok(Some(f_last_v));
+        }
+
+        // Segments already run at the stage frame rate
+        let size = self.segment_size(chain[0]);
+        let normalize = |ffmpeg: &mut FFmpeg, segment: &Segment, mut stream: Stream| {
+            if let Some(size) = size
+                && self.segment_size(segment) != Some(size)
+            {
+                stream = ffmpeg.add_filter(Filter::scale(size.width, size.height), [stream]);
+            }
+            stream = ffmpeg.add_filter(Filter::with_name("settb").param("AVTB"), [stream]);
+            ffmpeg.add_filter(Filter::format("yuva420p"), [stream])
+        };
+
+        f_last_v = normalize(ffmpeg, chain[0], f_last_v);
+        let chain_start = chain[0].target_timerange.start;
+        for pair in chain.windows(2) {
+            let Some(transition) = track.transition_from(&pair[0].id) else {
+                break;
+            };
+            let Some(next) = self.segment_video(ffmpeg, pair[1]) else {
+                break;
+            };
+            let next = normalize(ffmpeg, pair[1], next);
+
+            // The incoming clip starts fading in where it starts on the timeline
+            let offset = (pair[1].target_timerange.start - chain_start) as f64 / 1000.0;
+            f_last_v = ffmpeg.add_filter(
+                Filter::xfade(
+                    transition.ffmpeg_transition()?,
+                    transition.duration as f64 / 1000.0,
+                    offset,
+                ),
+                [f_last_v, next],
+            );
+        }
+
+        Ok(Some(f_last_v))
+    }
+
+    /// Build the video of one segment: scaled and speed adjusted, starting at zero
+    fn segment_video(&self, ffmpeg: &mut FFmpeg, segment: &Segment) -> Option<Stream> {
+        let input = self.add_segment_input(ffmpeg, segment)?;
+        let mut f_last_v = input.v();
+        let is_image = self.is_image_segment(segment);
+
+        // 视频流：按素材的旋转信息摆正画面，并拉伸为方形像素
+        if let Some(Material::Video(video)) = self.session.get_material(&segment.material_id)
+            && video.needs_orientation()
+        {
+            match video.rotation.unwrap_or(0).rem_euclid(360) {
+                90 => f_last_v = ffmpeg.add_filter(Filter::transpose(1), [f_last_v]),
+                180 => {
+                    f_last_v = ffmpeg.add_filter(Filter::hflip(), [f_last_v]);
+                    f_last_v = ffmpeg.add_filter(Filter::vflip(), [f_last_v]);
+                }
+                270 => f_last_v = ffmpeg.add_filter(Filter::transpose(2), [f_last_v]),
+                _ => {}
+            }
+            if video.sample_aspect_ratio.is_some_and(|sar| sar != 1.0) {
+                f_last_v = ffmpeg.add_filter(
+                    Filter::scale(video.dimension.width, video.dimension.height),
+                    [f_last_v],
+                );
+                f_last_v = ffmpeg.add_filter(Filter::with_name("setsar").param("1"), [f_last_v]);
+            }
+        }
+
+        // 图片流：保留透明通道
+        if is_image {
+            f_last_v = ffmpeg.add_filter(Filter::format("rgba"), [f_last_v]);
+        }
+
+        // 视频流：裁剪、缩放，并按适配模式裁掉溢出或填充到目标框
+        match self.segment_fit(segment) {
+            Some(fit) => {
+                if let Some(rect) = fit.crop {
+                    f_last_v = ffmpeg.add_filter(
+                        Filter::crop(rect.width, rect.height, rect.x, rect.y),
+                        [f_last_v],
+                    );
+                }
+                if let Some(size) = fit.scale {
+                    f_last_v =
+                        ffmpeg.add_filter(Filter::scale(size.width, size.height), [f_last_v]);
+                }
+                if let Some(rect) = fit.trim {
+                    f_last_v = ffmpeg.add_filter(
+                        Filter::crop(rect.width, rect.height, rect.x, rect.y),
+                        [f_last_v],
+                    );
+                }
+                if let Some(size) = fit.pad {
+                    // 填充区域透明，露出下层轨道
+                    if !is_image {
+                        f_last_v = ffmpeg.add_filter(Filter::format("yuva420p"), [f_last_v]);
+                    }
+                    f_last_v = ffmpeg.add_filter(
+                        Filter::pad(size.width, size.height, "(ow-iw)/2", "(oh-ih)/2", "black@0"),
+                        [f_last_v],
+                    );
+                }
+            }
+            None => {
+                if let Some(scale) = segment.scale {
+                    f_last_v =
+                        ffmpeg.add_filter(Filter::scale(scale.width, scale.height), [f_last_v]);
+                }
+            }
+        }
+
+        // 视频流：是否需要倍速
+        if !is_image && segment.needs_speed_adjustment() {
+            let speed = segment.playback_speed();
+            f_last_v = ffmpeg.add_filter(Filter::setpts(format!("1/{speed}*PTS")), [f_last_v]);
+        }
+
+        // 视频流：统一为舞台帧率
+        f_last_v = ffmpeg.add_filter(Filter::fps(self.session.stage.fps.to_string()), [f_last_v]);
+
+        // 视频流：尺寸关键帧，缺少的一边沿用静态尺寸或保持比例
+        let keyframes = &segment.keyframes;
+        let width = keyframe::expression(&keyframes.width, "t");
+        let height = keyframe::expression(&keyframes.height, "t");
+        if width.is_some() || height.is_some() {
+            let width = width
+                .or_else(|| segment.scale.map(|s| s.width.to_string()))
+                .unwrap_or("-1".to_string());
+            let height = height
+                .or_else(|| segment.scale.map(|s| s.height.to_string()))
+                .unwrap_or("-1".to_string());
+            f_last_v = ffmpeg.add_filter(Filter::scale_expr(&width, &height), [f_last_v]);
+        }
+
+        // 视频流：透明度关键帧，逐帧更新透明度系数
+        if let Some(opacity) = keyframe::expression(&keyframes.opacity, "T") {
+            let instance = format!("opacity{:x}", rand::random::<u32>());
+            let initial = keyframe::value_at(&keyframes.opacity, 0).unwrap_or(1.0);
+            f_last_v = ffmpeg.add_filter(Filter::format("rgba"), [f_last_v]);
+            f_last_v = ffmpeg.add_filter(
+                Filter::sendcmd_expr(&format!("colorchannelmixer@{}", instance), "aa", &opacity),
+                [f_last_v],
+            );
+            f_last_v = ffmpeg.add_filter(Filter::alpha_mixer(&instance, initial), [f_last_v]);
+        }
+
+        // 视频流：镜像翻转
+        let transform = &segment.transform;
+        if transform.flip_horizontal {
+            f_last_v = ffmpeg.add_filter(Filter::hflip(), [f_last_v]);
+        }
+        if transform.flip_vertical {
+            f_last_v = ffmpeg.add_filter(Filter::vflip(), [f_last_v]);
+        }
+
+        // 视频流：静态旋转，有旋转关键帧时以关键帧为准；整 90 度无损转置
+        if keyframes.rotation.is_empty() && transform.is_rotated() {
+            match transform.quarter_turns() {
+                Some(1) => f_last_v = ffmpeg.add_filter(Filter::transpose(1), [f_last_v]),
+                Some(2) => {
+                    f_last_v = ffmpeg.add_filter(Filter::hflip(), [f_last_v]);
+                    f_last_v = ffmpeg.add_filter(Filter::vflip(), [f_last_v]);
+                }
+                Some(_) => f_last_v = ffmpeg.add_filter(Filter::transpose(2), [f_last_v]),
+                None => {
+                    let angle = transform.rotation.to_radians();
+                    let rotate = match self.segment_size(segment) {
+                        Some(size) => {
+                            let rotated = transform.rotated_size(size);
+                            Filter::rotate_to(angle, rotated.width, rotated.height)
+                        }
+                        None => Filter::rotate_expr(&angle.to_string()),
+                    };
+                    f_last_v = ffmpeg.add_filter(Filter::format("rgba"), [f_last_v]);
+                    f_last_v = ffmpeg.add_filter(rotate, [f_last_v]);
+                }
+            }
+        }
+
+        // 视频流：旋转关键帧，角度为顺时针度数
+        if let Some(rotation) = keyframe::expression(&keyframes.rotation, "t") {
+            f_last_v = ffmpeg.add_filter(Filter::format("rgba"), [f_last_v]);
+            f_last_v = ffmpeg.add_filter(
+                Filter::rotate_expr(&format!("({})*PI/180", rotation)),
+                [f_last_v],
+            );
+        }
+
+        Some(f_last_v)
+    }
+
+    /// Build the audio of one segment with speed and track volume applied
+    fn segment_audio(
+        &self,
+        ffmpeg: &mut FFmpeg,
+        track: &Track,
+        segment: &Segment,
+    ) -> Option<Stream> {
+        // The input is already trimmed to the segment's source range
+        let input = self.add_segment_input(ffmpeg, segment)?;
+        let mut f_last_a = input.a();
+
+        // Apply speed adjustment for audio
+        if segment.needs_speed_adjustment() {
+            let speed = segment.playback_speed();
+            f_last_a = ffmpeg.add_filter(Filter::atempo(speed), [f_last_a]);
+        }
+
+        // Apply segment gain, or silence a muted segment kept for a crossfade
+        let audio = &segment.audio;
+        if audio.muted {
+            f_last_a = ffmpeg.add_filter(Filter::volume(0.0), [f_last_a]);
+        } else if audio.gain != 0.0 {
+            f_last_a = ffmpeg.add_filter(Filter::volume_db(audio.gain), [f_last_a]);
+        }
+
+        // Apply fades, at most as long as the segment
+        let duration = segment.target_timerange.duration;
+        if audio.fade_in > 0 {
+            let fade = audio.fade_in.min(duration) as f64 / 1000.0;
+            f_last_a = ffmpeg.add_filter(Filter::afade_in(0.0, fade), [f_last_a]);
+        }
+        if audio.fade_out > 0 {
+            let fade = audio.fade_out.min(duration);
+            f_last_a = ffmpeg.add_filter(
+                Filter::afade_out((duration - fade) as f64 / 1000.0, fade as f64 / 1000.0),
+                [f_last_a],
+            );
+        }
+
+        // Apply track volume
+        if track.volume < 1.0 {
+            f_last_a = ffmpeg.add_filter(Filter::volume(track.volume.into()), [f_last_a]);
+        }
+
+        // Apply volume keyframes
+        if let Some(volume) = keyframe::expression(&segment.keyframes.volume, "t") {
+            f_last_a = ffmpeg.add_filter(Filter::volume_expr(&volume), [f_last_a]);
+        }
+
+        // Convert to the stage sample rate and channel layout
+        let stage = &self.session.stage;
+        f_last_a = ffmpeg.add_filter(Filter::resample(stage.sample_rate), [f_last_a]);
+        f_last_a = ffmpeg.add_filter(
+            Filter::channel_layout(stage.channel_layout.as_str()),
+            [f_last_a],
+        );
+
+        // Apply stereo pan by lowering the opposite channel
+        if audio.pan != 0.0 && stage.channel_layout == ChannelLayout::Stereo {
+            let left = (1.0 - audio.pan).min(1.0);
+            let right = (1.0 + audio.pan).min(1.0);
+            f_last_a = ffmpeg.add_filter(Filter::pan_stereo(left, right), [f_last_a]);
+        }
+
+        Some(f_last_a)
+    }
+
+    /// Size of a segment on the stage: the fitted frame, or its scale
+    fn segment_size(&self, segment: &Segment) -> Option<Dimension> {
+        self.segment_fit(segment)
+            .map(|fit| fit.size)
+            .or(segment.scale)
+    }
+
+    /// Resolve a segment's crop and fit mode against its material and the
+    /// stage; `None` when the material size is unknown
+    fn segment_fit(&self, segment: &Segment) -> Option<FrameFit> {
+        let source = self
+            .session
+            .get_material(&segment.material_id)
+            .and_then(|m| m.dimensions())?;
+        segment.frame_fit(source, &self.session.stage)
+    }
+
+    /// Add an input trimmed to the segment's source range.
+    ///
+    /// Every segment gets its own input, so a material can be cut into
+    /// several clips without the clips sharing one source window. Still
+    /// images are looped for the segment's duration on the timeline.
+    fn add_segment_input(&self, ffmpeg: &mut FFmpeg, segment: &Segment) -> Option<Input> {
+        let material = self.session.get_material(&segment.material_id)?;
+        let mut input = match material {
+            Material::Image(image) => Input::with_simple(&image.src)
+                .duration(segment.target_timerange.duration as f32 / 1000.0)
+                .option("loop", "1"),
+            _ => Input::with_time(
+                segment.source_timerange.start as f32 / 1000.0,
+                segment.source_timerange.duration as f32 / 1000.0,
+                material.src(),
+            ),
+        };
+
+        // 旋转由滤镜显式处理，关闭解码时的自动旋转
+        if let Material::Video(video) = material
+            && video.rotation.is_some()
+        {
+            input = input.option("autorotate", "0");
+        }
+        Some(ffmpeg.add_input(input))
+    }
+
+    /// Check if a segment shows a still image
+    fn is_image_segment(&self, segment: &Segment) -> bool {
+        segment.segment_type == SegmentType::Image
+            || matches!(
+                self.session.get_material(&segment.material_id),
+                Some(Material::Image(_))
+            )
     }
 
     /// Export with simple options
@@ -681,6 +1759,12 @@ impl Editor {
             video_bitrate: None,
             audio_bitrate: None,
             custom_options: HashMap::new(),
+            subtitle_mode: SubtitleMode::default(),
+            abr: None,
+            loudness: None,
+            watermarks: Vec::new(),
+            delogo: Vec::new(),
+            range: None,
         };
 
         self.export(options).await
@@ -718,9 +1802,51 @@ impl ExportOptions {
             video_bitrate: None,
             audio_bitrate: None,
             custom_options: HashMap::new(),
+            subtitle_mode: SubtitleMode::default(),
+            abr: None,
+            loudness: None,
+            watermarks: Vec::new(),
+            delogo: Vec::new(),
+            range: None,
         }
     }
 
+    /// Package the export as an adaptive bitrate ladder
+    pub fn with_abr(mut self, ladder: AbrLadder) -> Self {
+        self.abr = Some(ladder);
+        self
+    }
+
+    /// Normalize the audio to a loudness target
+    pub fn with_loudness(mut self, target: LoudnessTarget) -> Self {
+        self.loudness = Some(target);
+        self
+    }
+
+    /// Add a watermark
+    pub fn with_watermark(mut self, watermark: Watermark) -> Self {
+        self.watermarks.push(watermark);
+        self
+    }
+
+    /// Add a region to remove a logo from
+    pub fn with_delogo(mut self, region: DelogoRegion) -> Self {
+        self.delogo.push(region);
+        self
+    }
+
+    /// Export only the timeline between the in and out points, in ms
+    pub fn with_range(mut self, in_point: u32, out_point: u32) -> Self {
+        self.range = Some(TimeRange::new(in_point, out_point.saturating_sub(in_point)));
+        self
+    }
+
+    /// Set subtitle export mode
+    pub fn with_subtitle_mode(mut self, mode: SubtitleMode) -> Self {
+        self.subtitle_mode = mode;
+        self
+    }
+
     /// Set video codec
     pub fn with_video_codec<S: Into<String>>(mut self, codec: S) -> Self {
         self.video_codec = Some(codec.into());
@@ -761,3 +1887,1192 @@ impl ExportOptions {
         self
     }
 }
+
+impl TryFrom<&ExportConfig> for ExportOptions {
+    type Error = CutError;
+
+    /// Take the output, type and range of a protocol export config. The
+    /// additional options fill the encoding settings of the same name; any
+    /// other key becomes a custom option passed to the output as `-key value`
+    fn try_from(config: &ExportConfig) -> Result<Self> {
+        let mut options = ExportOptions::new(&config.output_file, config.export_type.clone());
+        if let Some(ref range) = config.range {
+            options.range = Some(TimeRange::new(range.start, range.duration));
+        }
+        for (key, value) in config.options.iter().flatten() {
+            let invalid =
+                || CutError::invalid_params(format!("Invalid export option {}: {}", key, value));
+            let text = || match value {
+                serde_json::Value::String(s) => Some(s.clone()),
+                serde_json::Value::Number(n) => Some(n.to_string()),
+                serde_json::Value::Bool(b) => Some(b.to_string()),
+                _ => None,
+            };
+            let number = || {
+                text()
+                    .and_then(|s| s.trim().parse::<i32>().ok())
+                    .ok_or_else(invalid)
+            };
+            match key.as_str() {
+                "video_codec" => options.video_codec = Some(text().ok_or_else(invalid)?),
+                "audio_codec" => options.audio_codec = Some(text().ok_or_else(invalid)?),
+                "quality" => options.quality = Some(number()?),
+                "video_bitrate" => options.video_bitrate = Some(number()?),
+                "audio_bitrate" => options.audio_bitrate = Some(number()?),
+                "subtitle_mode" => {
+                    options.subtitle_mode =
+                        serde_json::from_value(value.clone()).map_err(|_| invalid())?
+                }
+                _ => {
+                    options
+                        .custom_options
+                        .insert(key.clone(), text().ok_or_else(invalid)?);
+                }
+            }
+        }
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cut::{
+        keyframe::{Easing, Keyframe, Keyframes},
+        material::{AudioMaterial, Dimension, ImageMaterial, VideoMaterial},
+        segment::{AudioSettings, CropRect, FitMode, Transform},
+        stage::FrameRate,
+        subtitle::SubtitleCue,
+        transition::Transition,
+    };
+    use crate::ffmpeg::abr::Rendition;
+
+    fn build_export(editor: &Editor, options: &ExportOptions) -> Result<FFmpeg> {
+        let scratch = tempfile::tempdir().unwrap();
+        editor.build_export(options, scratch.path())
+    }
+
+    fn build_args(editor: &Editor, export_type: ExportType) -> Vec<String> {
+        build_export(editor, &ExportOptions::new("out.mp4", export_type))
+            .unwrap()
+            .build_args()
+    }
+
+    fn filter_complex(args: &[String]) -> &str {
+        &args[args.iter().position(|a| a == "-filter_complex").unwrap() + 1]
+    }
+
+    fn video_editor() -> Editor {
+        let mut editor = Editor::new();
+        editor
+            .session_mut()
+            .add_material(Material::Video(VideoMaterial::new(
+                "clip", "clip.mp4", 1920, 1080,
+            )));
+        editor
+    }
+
+    fn input_windows(args: &[String]) -> Vec<(String, String, String)> {
+        args.windows(6)
+            .filter(|w| w[0] == "-ss" && w[2] == "-t" && w[4] == "-i")
+            .map(|w| (w[1].clone(), w[3].clone(), w[5].clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_export_reused_material_gets_own_inputs() {
+        let mut editor = Editor::new();
+        editor.session_mut().add_material(Material::Video(
+            VideoMaterial::new("long", "long.mp4", 1920, 1080).with_duration(600000),
+        ));
+
+        let mut track = Track::video();
+        track.add_segment(Segment::video(
+            "long",
+            TimeRange::new(0, 5000),
+            TimeRange::new(10000, 5000),
+        ));
+        track.add_segment(Segment::video(
+            "long",
+            TimeRange::new(5000, 3000),
+            TimeRange::new(120000, 3000),
+        ));
+        track.add_segment(Segment::video(
+            "long",
+            TimeRange::new(8000, 2000),
+            TimeRange::new(2500, 4000),
+        ));
+        editor.add_track(track);
+
+        let args = build_args(&editor, ExportType::Video);
+        assert_eq!(
+            input_windows(&args),
+            vec![
+                ("10".into(), "5".into(), "long.mp4".into()),
+                ("120".into(), "3".into(), "long.mp4".into()),
+                ("2.5".into(), "4".into(), "long.mp4".into()),
+            ]
+        );
+
+        let filter_complex = filter_complex(&args);
+        assert!(filter_complex.contains("[0:v]"));
+        assert!(filter_complex.contains("[1:v]"));
+        assert!(filter_complex.contains("[2:v]"));
+        assert!(filter_complex.contains("setpts=1/2*PTS"));
+        assert!(filter_complex.contains("between(t,5,8)"));
+    }
+
+    #[test]
+    fn test_export_audio_segments_are_not_trimmed_twice() {
+        let mut editor = Editor::new();
+        editor
+            .session_mut()
+            .add_material(Material::Audio(AudioMaterial::new("music", "music.wav")));
+
+        let mut track = Track::audio();
+        track.add_segment(Segment::audio(
+            "a1",
+            "music",
+            TimeRange::new(0, 2000),
+            TimeRange::new(30000, 2000),
+        ));
+        track.add_segment(Segment::audio(
+            "a2",
+            "music",
+            TimeRange::new(2000, 2000),
+            TimeRange::new(1000, 2000),
+        ));
+        editor.add_track(track);
+
+        let args = build_args(&editor, ExportType::Audio);
+        assert_eq!(
+            input_windows(&args),
+            vec![
+                ("30".into(), "2".into(), "music.wav".into()),
+                ("1".into(), "2".into(), "music.wav".into()),
+            ]
+        );
+
+        let filter_complex = filter_complex(&args);
+        assert!(!filter_complex.contains("atrim"));
+        assert!(filter_complex.contains("[1:a]aresample=48000"));
+        assert!(filter_complex.contains("adelay=2000ms"));
+        assert!(filter_complex.contains("amix=inputs=2"));
+    }
+
+    #[test]
+    fn test_export_track_opacity() {
+        let mut editor = video_editor();
+        let mut track = Track::video();
+        track.set_opacity(0.5).add_segment(Segment::video(
+            "clip",
+            TimeRange::new(0, 1000),
+            TimeRange::new(0, 1000),
+        ));
+        editor.add_track(track);
+
+        let args = build_args(&editor, ExportType::Video);
+        let filter_complex = filter_complex(&args);
+        assert!(filter_complex.contains("format=yuva420p"));
+        assert!(filter_complex.contains("colorchannelmixer=aa=0.5"));
+        assert!(!filter_complex.contains("blend="));
+    }
+
+    #[test]
+    fn test_export_track_blend_mode() {
+        let mut editor = video_editor();
+        let mut track = Track::video();
+        track
+            .set_opacity(0.8)
+            .set_blend_mode("color-burn")
+            .add_segment(
+                Segment::video("clip", TimeRange::new(0, 1000), TimeRange::new(0, 1000))
+                    .scale(Dimension::new(640, 360))
+                    .position(Position::new(1600, -60)),
+            );
+        editor.add_track(track);
+
+        let args = build_args(&editor, ExportType::Video);
+        let filter_complex = filter_complex(&args);
+        // Only the visible 320x300 part of the segment is blended
+        assert!(filter_complex.contains("crop=320:300:0:60"));
+        assert!(filter_complex.contains("split=2"));
+        assert!(filter_complex.contains("crop=320:300:1600:0"));
+        assert!(filter_complex.contains("blend=all_mode=burn:all_opacity=0.8"));
+        assert!(filter_complex.contains("overlay=1600:0:enable='between(t,0,1)'"));
+    }
+
+    #[test]
+    fn test_export_rejects_unsupported_blend_mode() {
+        let mut editor = video_editor();
+        let mut track = Track::video();
+        track.set_blend_mode("luminosity");
+        editor.add_track(track);
+
+        assert!(build_export(&editor, &ExportOptions::new("out.mp4", ExportType::Video)).is_err());
+    }
+
+    #[test]
+    fn test_export_track_volume() {
+        let mut editor = Editor::new();
+        editor
+            .session_mut()
+            .add_material(Material::Audio(AudioMaterial::new("music", "music.wav")));
+        let mut track = Track::audio();
+        track.set_volume(0.25).add_segment(Segment::audio(
+            "a1",
+            "music",
+            TimeRange::new(0, 1000),
+            TimeRange::new(0, 1000),
+        ));
+        editor.add_track(track);
+
+        let args = build_args(&editor, ExportType::Audio);
+        assert!(filter_complex(&args).contains("[0:a]volume=0.25"));
+    }
+
+    #[test]
+    fn test_export_segment_audio_controls() {
+        let mut editor = Editor::new();
+        editor
+            .session_mut()
+            .add_material(Material::Audio(AudioMaterial::new("music", "music.wav")));
+        let mut track = Track::audio();
+        track
+            .add_segment(
+                Segment::audio(
+                    "a1",
+                    "music",
+                    TimeRange::new(0, 2000),
+                    TimeRange::new(0, 2000),
+                )
+                .with_audio(AudioSettings::gain(-6.0).fade(500, 1000).pan(-0.5)),
+            )
+            .add_segment(
+                Segment::audio(
+                    "a2",
+                    "music",
+                    TimeRange::new(2000, 1000),
+                    TimeRange::new(5000, 1000),
+                )
+                .with_audio(AudioSettings::default().mute()),
+            )
+            .add_segment(
+                Segment::audio(
+                    "a3",
+                    "music",
+                    TimeRange::new(3000, 1000),
+                    TimeRange::new(9000, 1000),
+                )
+                .with_audio(AudioSettings::default().fade(0, 3000)),
+            );
+        editor.add_track(track);
+
+        let args = build_args(&editor, ExportType::Audio);
+        assert_eq!(input_windows(&args).len(), 2);
+
+        let graph = filter_complex(&args);
+        assert!(graph.contains("[0:a]volume=-6dB"));
+        assert!(graph.contains("afade=t=in:st=0:d=0.5"));
+        assert!(graph.contains("afade=t=out:st=1:d=1"));
+        assert!(graph.contains("pan=stereo|c0=1*c0|c1=0.5*c1"));
+
+        // Fades longer than the segment cover all of it
+        assert!(graph.contains("[1:a]afade=t=out:st=0:d=1"));
+        assert!(graph.contains("adelay=3000ms"));
+    }
+
+    #[test]
+    fn test_export_image_segments_in_track_order() {
+        let mut editor = video_editor();
+        editor
+            .session_mut()
+            .add_material(Material::Image(ImageMaterial::new(
+                "logo", "logo.png", 400, 200,
+            )));
+
+        // The first track is the top layer
+        let mut image_track = Track::image();
+        image_track.add_segment(
+            Segment::image(
+                "i1",
+                "logo",
+                TimeRange::new(1000, 3000),
+                TimeRange::new(0, 1000),
+            )
+            .scale(Dimension::new(200, 100))
+            .position(Position::new(20, 20)),
+        );
+        editor.add_track(image_track);
+
+        let mut video_track = Track::video();
+        video_track.add_segment(Segment::video(
+            "clip",
+            TimeRange::new(0, 5000),
+            TimeRange::new(0, 5000),
+        ));
+        editor.add_track(video_track);
+
+        let args = build_args(&editor, ExportType::Video);
+        let inputs: Vec<&[String]> = args.split(|a| a == "-i").take(3).collect();
+        assert!(inputs[1].ends_with(&["-t".into(), "3".into(), "-loop".into(), "1".into()]));
+        assert!(!inputs[1].contains(&"-ss".to_string()));
+
+        let filter_complex = filter_complex(&args);
+        let video_overlay = filter_complex.find("overlay=0:0").unwrap();
+        let image_overlay = filter_complex.find("overlay=20:20").unwrap();
+        assert!(video_overlay < image_overlay);
+        assert!(filter_complex.contains("[1:v]format=rgba"));
+        assert!(!filter_complex.contains("*PTS"));
+        assert!(filter_complex.contains("between(t,1,4)"));
+    }
+
+    #[test]
+    fn test_export_text_segments() {
+        let mut editor = video_editor();
+
+        let text_track = editor.add_text_track();
+        editor
+            .add_segment_to_track(
+                &text_track,
+                Segment::text("t1", "", TimeRange::new(500, 2000), TimeRange::new(0, 2000))
+                    .position(Position::new(100, 900))
+                    .scale(Dimension::new(800, 100))
+                    .with_text(
+                        TextStyle::new("Lower: third")
+                            .with_font("fonts/Title.ttf")
+                            .with_font_size(36)
+                            .with_stroke("black", 2)
+                            .with_align(TextAlign::Center),
+                    ),
+            )
+            .unwrap();
+        editor.session_mut().tracks[0].opacity = 0.5;
+
+        let mut video_track = Track::video();
+        video_track.add_segment(Segment::video(
+            "clip",
+            TimeRange::new(0, 5000),
+            TimeRange::new(0, 5000),
+        ));
+        editor.add_track(video_track);
+
+        let args = build_args(&editor, ExportType::Video);
+        // Text segments need no input of their own
+        assert_eq!(args.iter().filter(|a| *a == "-i").count(), 1);
+
+        let filter_complex = filter_complex(&args);
+        let overlay = filter_complex.find("overlay=0:0").unwrap();
+        let drawtext = filter_complex.find("drawtext=").unwrap();
+        assert!(overlay < drawtext);
+        assert!(filter_complex.contains(
+            "drawtext=text=Lower\\\\: third:expansion=none:fontfile=fonts/Title.ttf:fontsize=36"
+        ));
+        assert!(filter_complex.contains("x=100+(800-text_w)/2:y=900"));
+        assert!(filter_complex.contains("borderw=2:bordercolor=black:alpha=0.5"));
+        assert!(filter_complex.contains("enable='between(t,0.5,2.5)'"));
+    }
+
+    #[test]
+    fn test_export_subtitle_modes() {
+        let mut editor = video_editor();
+        let mut video_track = Track::video();
+        video_track.add_segment(Segment::video(
+            "clip",
+            TimeRange::new(0, 5000),
+            TimeRange::new(0, 5000),
+        ));
+        editor.add_track(video_track);
+
+        let mut subtitle_track = Track::subtitle();
+        subtitle_track.add_segment(SubtitleCue::new(500, 1500, "Hello").to_segment());
+        let track_id = editor.add_track(subtitle_track);
+        let scratch = tempfile::tempdir().unwrap();
+        let srt = scratch.path().join(format!("kiva-cut-{}.srt", track_id));
+
+        let options = ExportOptions::new("out.mp4", ExportType::Video);
+        let args = editor
+            .build_export(&options, scratch.path())
+            .unwrap()
+            .build_args();
+        assert!(filter_complex(&args).contains(&format!("subtitles=filename={}", srt.display())));
+        assert_eq!(
+            std::fs::read_to_string(&srt).unwrap(),
+            "1\n00:00:00,500 --> 00:00:01,500\nHello\n\n"
+        );
+
+        let options = ExportOptions::new("out.webm", ExportType::Video)
+            .with_subtitle_mode(SubtitleMode::Soft);
+        let args = editor
+            .build_export(&options, scratch.path())
+            .unwrap()
+            .build_args();
+        assert!(!filter_complex(&args).contains("subtitles="));
+        assert!(
+            args.windows(2)
+                .any(|w| w == ["-i", &srt.display().to_string()])
+        );
+        assert!(args.windows(2).any(|w| w == ["-map", "1:s"]));
+        assert!(args.windows(2).any(|w| w == ["-c:s", "webvtt"]));
+
+        // Audio exports leave subtitles out
+        let args = build_args(&editor, ExportType::Audio);
+        assert!(!args.iter().any(|a| a.contains("subtitles") || a == "-c:s"));
+    }
+
+    #[test]
+    fn test_import_and_export_subtitles() {
+        let dir = tempfile::tempdir().unwrap();
+        let vtt = dir.path().join("captions.vtt");
+        std::fs::write(&vtt, "WEBVTT\n\n00:01.000 --> 00:02.500\nHi there\n").unwrap();
+
+        let mut editor = Editor::new();
+        let track_id = editor.import_subtitles(&vtt).unwrap();
+        assert!(editor.validate().is_ok());
+
+        let srt = dir.path().join("captions.srt");
+        editor.export_subtitles(&track_id, &srt).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&srt).unwrap(),
+            "1\n00:00:01,000 --> 00:00:02,500\nHi there\n\n"
+        );
+
+        assert!(
+            editor
+                .import_subtitles(dir.path().join("missing.srt"))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_export_video_transitions() {
+        let mut editor = video_editor();
+        editor
+            .session_mut()
+            .add_material(Material::Image(ImageMaterial::new(
+                "card", "card.png", 1280, 720,
+            )));
+
+        let mut track = Track::video();
+        track
+            .add_segment(Segment::new(
+                "s1",
+                SegmentType::Video,
+                "clip",
+                TimeRange::new(1000, 3000),
+                TimeRange::new(0, 3000),
+            ))
+            .add_segment(Segment::new(
+                "s2",
+                SegmentType::Image,
+                "card",
+                TimeRange::new(3500, 2000),
+                TimeRange::new(0, 2000),
+            ))
+            .add_transition(Transition::new("s1", "s2", "wipe", 500));
+        editor.add_track(track);
+        assert!(editor.validate().is_ok());
+
+        let args = build_args(&editor, ExportType::Video);
+        let filter_complex = filter_complex(&args);
+
+        // The image is fitted to the clip it fades from
+        assert!(filter_complex.contains("scale=1920:1080"));
+        assert!(filter_complex.contains("fps=30"));
+        assert!(filter_complex.contains("xfade=transition=wipeleft:duration=0.5:offset=2.5"));
+        assert!(filter_complex.contains("setpts=PTS+1/TB"));
+        assert!(filter_complex.contains("overlay=0:0:enable='between(t,1,5.5)'"));
+        assert_eq!(filter_complex.matches("overlay=").count(), 1);
+    }
+
+    #[test]
+    fn test_export_audio_transitions() {
+        let mut editor = Editor::new();
+        editor
+            .session_mut()
+            .add_material(Material::Audio(AudioMaterial::new("music", "music.mp3")));
+
+        let mut track = Track::audio();
+        track
+            .add_segment(Segment::audio(
+                "a1",
+                "music",
+                TimeRange::new(2000, 4000),
+                TimeRange::new(0, 4000),
+            ))
+            .add_segment(Segment::audio(
+                "a2",
+                "music",
+                TimeRange::new(5000, 4000),
+                TimeRange::new(10000, 4000),
+            ))
+            .add_transition(Transition::crossfade("a1", "a2", 1000));
+        editor.add_track(track);
+        assert!(editor.validate().is_ok());
+
+        let args = build_args(&editor, ExportType::Audio);
+        let filter_complex = filter_complex(&args);
+        assert!(filter_complex.contains("acrossfade=d=1"));
+        assert_eq!(filter_complex.matches("adelay=").count(), 1);
+        assert!(filter_complex.contains("adelay=2000ms"));
+        assert!(!filter_complex.contains("amix"));
+    }
+
+    #[test]
+    fn test_export_keyframes() {
+        let mut editor = video_editor();
+        let mut track = Track::video();
+        track.add_segment(
+            Segment::new(
+                "s1",
+                SegmentType::Video,
+                "clip",
+                TimeRange::new(1000, 2000),
+                TimeRange::new(0, 2000),
+            )
+            .scale(Dimension::new(640, 360))
+            .with_keyframes(Keyframes {
+                x: vec![Keyframe::new(0, 0.0), Keyframe::new(1000, 100.0)],
+                width: vec![Keyframe::new(0, 640.0), Keyframe::new(2000, 1280.0)],
+                opacity: vec![
+                    Keyframe::new(0, 0.0).with_easing(Easing::EaseIn),
+                    Keyframe::new(500, 1.0),
+                ],
+                rotation: vec![Keyframe::new(0, 90.0)],
+                ..Default::default()
+            }),
+        );
+        editor.add_track(track);
+
+        let args = build_args(&editor, ExportType::Video);
+        let filter_complex = filter_complex(&args);
+
+        assert!(filter_complex.contains(
+            "scale=w='if(lt(t,0),640,if(lt(t,2),640+640*(((t)-0)/2),1280))':h='360':eval=frame"
+        ));
+        assert!(!filter_complex.contains("geq="));
+        assert!(filter_complex.contains(r"aa \\\'if(lt(T\,0)\,0\,if(lt(T\,0.5)\,0+1*pow"));
+        let mixer = filter_complex
+            .split(['[', ']', ';'])
+            .find_map(|f| f.strip_prefix("colorchannelmixer@"))
+            .unwrap();
+        assert!(mixer.ends_with("=aa=0"));
+        let instance = mixer.trim_end_matches("=aa=0");
+        assert!(filter_complex.contains(&format!("colorchannelmixer@{} aa", instance)));
+        assert!(filter_complex.contains("rotate=a='(90)*PI/180':c=none"));
+        assert!(filter_complex.contains(
+            "overlay=x='if(lt((t-1),0),0,if(lt((t-1),1),0+100*((((t-1))-0)/1),100))+(640-w)/2'"
+        ));
+        assert!(filter_complex.contains(":y='0+(360-h)/2':enable='between(t,1,3)'"));
+
+        // Blending needs a fixed region on the stage
+        let mut editor = editor;
+        editor.session_mut().tracks[0].set_blend_mode("multiply");
+        assert!(build_export(&editor, &ExportOptions::new("out.mp4", ExportType::Video)).is_err());
+    }
+
+    #[test]
+    fn test_export_volume_keyframes() {
+        let mut editor = Editor::new();
+        editor
+            .session_mut()
+            .add_material(Material::Audio(AudioMaterial::new("music", "music.wav")));
+        let mut track = Track::audio();
+        track.add_segment(
+            Segment::audio(
+                "a1",
+                "music",
+                TimeRange::new(0, 2000),
+                TimeRange::new(0, 2000),
+            )
+            .with_keyframes(Keyframes {
+                volume: vec![Keyframe::new(0, 1.0), Keyframe::new(2000, 0.0)],
+                ..Default::default()
+            }),
+        );
+        editor.add_track(track);
+
+        let args = build_args(&editor, ExportType::Audio);
+        assert!(
+            filter_complex(&args).contains(
+                "volume=volume='if(lt(t,0),1,if(lt(t,2),1+(-1)*(((t)-0)/2),0))':eval=frame"
+            )
+        );
+    }
+
+    #[test]
+    fn test_export_abr_ladder() {
+        let mut editor = video_editor();
+        let mut track = Track::video();
+        track.add_segment(Segment::video(
+            "clip",
+            TimeRange::new(0, 4000),
+            TimeRange::new(0, 4000),
+        ));
+        editor.add_track(track);
+
+        let ladder = AbrLadder::hls(vec![
+            Rendition::new(720, 2800, 128),
+            Rendition::new(360, 800, 64),
+        ]);
+        let options =
+            ExportOptions::new("out/master.m3u8", ExportType::Video).with_abr(ladder.clone());
+        let args = build_export(&editor, &options).unwrap().build_args();
+        let line = args.join(" ");
+        assert!(filter_complex(&args).contains("split=2"));
+        assert!(filter_complex(&args).contains("asplit=2"));
+        assert!(line.contains("-b:v:0 2800k"));
+        assert!(!line.contains("-crf"));
+        assert!(line.contains("-master_pl_name master.m3u8"));
+        assert!(!line.contains("-movflags"));
+        assert_eq!(args.last().unwrap(), "out/master_%v.m3u8");
+
+        let options = ExportOptions::new("out/stream.mpd", ExportType::Video)
+            .with_abr(AbrLadder::dash(ladder.renditions.clone()));
+        let args = build_export(&editor, &options).unwrap().build_args();
+        assert!(args.windows(2).any(|w| w == ["-f", "dash"]));
+
+        // A global CRF or bitrate would override the rendition bitrates
+        let options = ExportOptions::new("out/master.m3u8", ExportType::Video)
+            .with_quality(21)
+            .with_abr(ladder.clone());
+        assert!(build_export(&editor, &options).is_err());
+        let options = ExportOptions::new("out/master.m3u8", ExportType::Video)
+            .with_video_bitrate(3000)
+            .with_abr(ladder.clone());
+        assert!(build_export(&editor, &options).is_err());
+
+        let options = ExportOptions::new("out/master.m3u8", ExportType::Audio).with_abr(ladder);
+        assert!(build_export(&editor, &options).is_err());
+    }
+
+    #[test]
+    fn test_export_loudness() {
+        let mut editor = video_editor();
+        let mut track = Track::audio();
+        track.add_segment(Segment::audio(
+            "voice",
+            "clip",
+            TimeRange::new(0, 4000),
+            TimeRange::new(0, 4000),
+        ));
+        editor.add_track(track);
+
+        let target = LoudnessTarget::broadcast();
+        let args = editor
+            .build_loudness_analysis(&target)
+            .unwrap()
+            .build_args();
+        assert!(filter_complex(&args).contains("loudnorm=I=-23:TP=-1:LRA=7:print_format=json"));
+        assert!(!filter_complex(&args).contains("color="));
+        assert!(args.join(" ").ends_with("-f null -"));
+
+        let options = ExportOptions::new("out.mp4", ExportType::Video).with_loudness(target);
+        let args = build_export(&editor, &options).unwrap().build_args();
+        assert!(filter_complex(&args).contains("loudnorm=I=-23:TP=-1:LRA=7["));
+        assert!(filter_complex(&args).contains("aresample=48000"));
+
+        let json = serde_json::to_string(&options).unwrap();
+        let parsed: ExportOptions = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.loudness, Some(LoudnessTarget::broadcast()));
+
+        let options = options.with_loudness(LoudnessTarget::new(-3.0, -1.0, 11.0));
+        assert!(build_export(&editor, &options).is_err());
+    }
+
+    #[test]
+    fn test_export_watermark_and_delogo() {
+        let mut editor = video_editor();
+        let mut track = Track::video();
+        track.add_segment(Segment::video(
+            "clip",
+            TimeRange::new(0, 4000),
+            TimeRange::new(0, 4000),
+        ));
+        editor.add_track(track);
+
+        let options = ExportOptions::new("out.mp4", ExportType::Video)
+            .with_delogo(DelogoRegion::new(40, 30, 200, 80))
+            .with_watermark(Watermark::image("logo.png").scale(0.1).window(0, 2000));
+        let args = build_export(&editor, &options).unwrap().build_args();
+        let graph = filter_complex(&args);
+        assert!(args.windows(2).any(|w| w == ["-i", "logo.png"]));
+        let delogo = graph.find("delogo=x=40:y=30:w=200:h=80").unwrap();
+        let overlay = graph
+            .find("overlay=W-w-0:H-h-0:enable='between(t,0,2)'")
+            .unwrap();
+        assert!(delogo < overlay);
+        assert!(graph.contains("scale2ref"));
+
+        let options = ExportOptions::new("out.mp3", ExportType::Audio)
+            .with_watermark(Watermark::text("kiva"));
+        assert!(build_export(&editor, &options).is_err());
+    }
+
+    fn cut_list_editor(codecs: [&str; 2]) -> Editor {
+        let mut editor = Editor::new().set_stage(Stage::new(1920, 1080));
+        for (id, codec) in ["a", "b"].iter().zip(codecs) {
+            editor.session_mut().add_material(Material::Video(
+                VideoMaterial::new(*id, format!("{id}.mp4"), 1920, 1080).with_codec(codec),
+            ));
+        }
+
+        let mut video = Track::video();
+        let mut audio = Track::audio();
+        for (material, target, source) in [("a", 0, 2000), ("b", 3000, 0)] {
+            let target = TimeRange::new(target, 3000 - target / 3);
+            let source = TimeRange::new(source, target.duration);
+            video.add_segment(Segment::video(material, target, source));
+            audio.add_segment(Segment::audio(
+                format!("{material}-audio"),
+                material,
+                target,
+                source,
+            ));
+        }
+        editor.add_track(video);
+        editor.add_track(audio);
+        editor
+    }
+
+    #[test]
+    fn test_export_concat_fast_path() {
+        let editor = cut_list_editor(["h264", "h264"]);
+        let options = ExportOptions::new("cut.mp4", ExportType::Video);
+        let scratch = tempfile::tempdir().unwrap();
+        let args = editor
+            .build_concat_export(&options, scratch.path())
+            .unwrap()
+            .unwrap()
+            .build_args();
+        let line = args.join(" ");
+        assert!(line.contains("-f concat -safe 0"));
+        assert!(line.contains("-map 0:v -map 0:a"));
+        assert!(line.contains("-c:v copy"));
+        assert!(line.contains("-c:a copy"));
+        assert!(!line.contains("-filter_complex"));
+
+        let list = &args[args.iter().position(|a| a == "-i").unwrap() + 1];
+        assert_eq!(Path::new(list), scratch.path().join(CONCAT_LIST));
+        let list = std::fs::read_to_string(list).unwrap();
+        assert!(list.starts_with("ffconcat version 1.0\n"));
+        assert!(list.contains("a.mp4'\ninpoint 2\noutpoint 5\n"));
+        assert!(list.contains("b.mp4'\noutpoint 2\n"));
+
+        // A muted audio track leaves only the video to copy
+        let mut muted = cut_list_editor(["h264", "h264"]);
+        let audio_id = muted.session().audio_tracks()[0].id.clone();
+        muted
+            .session_mut()
+            .get_track_mut(&audio_id)
+            .unwrap()
+            .set_muted(true);
+        let options = ExportOptions::new("muted.mp4", ExportType::Video);
+        let line = muted
+            .build_concat_export(&options, scratch.path())
+            .unwrap()
+            .unwrap()
+            .build_args()
+            .join(" ");
+        assert!(!line.contains("0:a"));
+    }
+
+    #[test]
+    fn test_export_concat_fallbacks() {
+        let options = ExportOptions::new("cut.mp4", ExportType::Video);
+        let scratch = tempfile::tempdir().unwrap();
+        let concat = |editor: &Editor, options: &ExportOptions| {
+            editor.build_concat_export(options, scratch.path()).unwrap()
+        };
+        let mixed = cut_list_editor(["h264", "hevc"]);
+        assert!(concat(&mixed, &options).is_none());
+
+        let editor = cut_list_editor(["h264", "h264"]);
+        for options in [
+            options.clone().with_quality(20),
+            options.clone().with_video_codec("libx265"),
+            ExportOptions::new("cut.mp3", ExportType::Audio),
+        ] {
+            assert!(concat(&editor, &options).is_none());
+        }
+
+        let mut scaled = cut_list_editor(["h264", "h264"]);
+        let video_id = scaled.session().visual_tracks()[0].id.clone();
+        let track = scaled.session_mut().get_track_mut(&video_id).unwrap();
+        track.segments[1].scale = Some(Dimension {
+            width: 960,
+            height: 540,
+        });
+        assert!(concat(&scaled, &options).is_none());
+
+        let mut gap = cut_list_editor(["h264", "h264"]);
+        let video_id = gap.session().visual_tracks()[0].id.clone();
+        let track = gap.session_mut().get_track_mut(&video_id).unwrap();
+        track.segments[1].target_timerange.start += 500;
+        assert!(concat(&gap, &options).is_none());
+
+        let mut rotated = cut_list_editor(["h264", "h264"]);
+        if let Some(Material::Video(video)) = rotated.session_mut().materials.get_mut(0) {
+            video.rotation = Some(180);
+        }
+        assert!(concat(&rotated, &options).is_none());
+
+        let mut flipped = cut_list_editor(["h264", "h264"]);
+        let video_id = flipped.session().visual_tracks()[0].id.clone();
+        let track = flipped.session_mut().get_track_mut(&video_id).unwrap();
+        track.segments[0].transform = Transform::default().flip(false, true);
+        assert!(concat(&flipped, &options).is_none());
+
+        let mut faded = cut_list_editor(["h264", "h264"]);
+        let audio_id = faded.session().audio_tracks()[0].id.clone();
+        let track = faded.session_mut().get_track_mut(&audio_id).unwrap();
+        track.segments[1].audio = AudioSettings::default().fade(0, 500);
+        assert!(concat(&faded, &options).is_none());
+
+        let mut mixed_fps = cut_list_editor(["h264", "h264"]);
+        if let Some(Material::Video(video)) = mixed_fps.session_mut().materials.get_mut(1) {
+            *video = video.clone().with_fps(25.0);
+        }
+        assert!(concat(&mixed_fps, &options).is_none());
+    }
+
+    #[test]
+    fn test_export_stage_output_format() {
+        let mut editor = Editor::new();
+        editor.session_mut().stage = Stage::new(1280, 720)
+            .with_fps(FrameRate::ntsc())
+            .with_background(StageBackground::Image("bg.png".to_string()))
+            .with_audio(44100, ChannelLayout::Mono)
+            .with_pix_fmt("yuv444p");
+        editor
+            .session_mut()
+            .add_material(Material::Video(VideoMaterial::new(
+                "clip", "clip.mp4", 1920, 1080,
+            )));
+        editor
+            .session_mut()
+            .add_material(Material::Image(ImageMaterial::new(
+                "logo", "logo.png", 200, 200,
+            )));
+
+        let mut video = Track::video();
+        video.add_segment(Segment::video(
+            "clip",
+            TimeRange::new(0, 3000),
+            TimeRange::new(0, 3000),
+        ));
+        let mut image = Track::image();
+        image.add_segment(Segment::image(
+            "logo",
+            "logo",
+            TimeRange::new(1000, 1000),
+            TimeRange::new(0, 1000),
+        ));
+        let mut audio = Track::audio();
+        audio.add_segment(Segment::audio(
+            "a1",
+            "clip",
+            TimeRange::new(0, 3000),
+            TimeRange::new(0, 3000),
+        ));
+        editor.add_track(image);
+        editor.add_track(video);
+        editor.add_track(audio);
+
+        let args = build_args(&editor, ExportType::Video);
+        let line = args.join(" ");
+        assert!(line.contains("-t 3 -loop 1 -i bg.png"));
+        let graph = filter_complex(&args);
+        assert!(graph.contains("[0:v]scale=1280:720"));
+        // Background and both segments run at the stage frame rate
+        assert_eq!(graph.matches("fps=30000/1001").count(), 3);
+        assert!(graph.contains("aresample=44100"));
+        assert!(graph.contains("aformat=channel_layouts=mono"));
+        assert!(graph.contains("format=yuv444p"));
+        assert!(!graph.contains("yuv420p"));
+
+        // Without audio, silence is generated in the stage format
+        editor.session_mut().tracks.pop();
+        editor.session_mut().stage.background = StageBackground::Color("#202020".to_string());
+        let args = build_args(&editor, ExportType::Video);
+        let graph = filter_complex(&args);
+        assert!(graph.contains("color=color=#202020:size=1280x720:duration=3:rate=30000/1001"));
+        assert!(graph.contains("anullsrc=r=44100:cl=mono:d=3s"));
+    }
+
+    #[test]
+    fn test_export_fit_modes() {
+        let mut editor = Editor::new().set_stage(Stage::new(1920, 1080));
+        editor
+            .session_mut()
+            .add_material(Material::Video(VideoMaterial::new(
+                "phone",
+                "phone.mp4",
+                1080,
+                1920,
+            )));
+
+        let mut track = Track::video();
+        track.add_segment(
+            Segment::video("phone", TimeRange::new(0, 2000), TimeRange::new(0, 2000))
+                .fit(FitMode::Contain),
+        );
+        track.add_segment(
+            Segment::video("phone", TimeRange::new(2000, 2000), TimeRange::new(0, 2000))
+                .crop(CropRect {
+                    x: 0,
+                    y: 420,
+                    width: 1080,
+                    height: 1080,
+                })
+                .scale(Dimension::new(640, 480))
+                .fit(FitMode::Cover)
+                .position(Position::new(100, 100)),
+        );
+        editor.add_track(track);
+
+        let args = build_args(&editor, ExportType::Video);
+        let graph = filter_complex(&args);
+        assert!(graph.contains("[0:v]scale=608:1080"));
+        assert!(graph.contains("format=yuva420p"));
+        assert!(graph.contains("pad=1920:1080:(ow-iw)/2:(oh-ih)/2:black@0"));
+        assert!(graph.contains("[1:v]crop=1080:1080:0:420"));
+        assert!(graph.contains("scale=640:640"));
+        assert!(graph.contains("crop=640:480:0:80"));
+        assert!(graph.contains("overlay=100:100"));
+    }
+
+    #[test]
+    fn test_export_transform() {
+        let mut editor = video_editor();
+        let mut track = Track::video();
+        track.add_segment(
+            Segment::video("clip", TimeRange::new(0, 1000), TimeRange::new(0, 1000))
+                .scale(Dimension::new(400, 200))
+                .position(Position::new(100, 100))
+                .transform(Transform::rotate(90.0).flip(true, false)),
+        );
+        track.add_segment(
+            Segment::video("clip", TimeRange::new(1000, 1000), TimeRange::new(0, 1000))
+                .scale(Dimension::new(300, 400))
+                .transform(Transform::rotate(45.0).anchor(Anchor::new(0.0, 0.0))),
+        );
+        track.add_segment(
+            Segment::video("clip", TimeRange::new(2000, 1000), TimeRange::new(0, 1000))
+                .scale(Dimension::new(400, 200))
+                .transform(Transform::default().anchor(Anchor::new(0.0, 0.0)))
+                .with_keyframes(Keyframes {
+                    rotation: vec![Keyframe::new(0, 0.0), Keyframe::new(1000, 90.0)],
+                    ..Default::default()
+                }),
+        );
+        editor.add_track(track);
+
+        let args = build_args(&editor, ExportType::Video);
+        let graph = filter_complex(&args);
+
+        // Quarter turns are transposed, the centre stays in place
+        assert!(graph.contains("scale=400:200"));
+        assert!(graph.contains("hflip"));
+        assert!(graph.contains("transpose=1"));
+        assert!(graph.contains("overlay=200:0"));
+
+        // Other angles grow to the diagonal and swing around the anchor
+        assert!(graph.contains("rotate=a=0.7853981633974483:c=none:ow=500:oh=500"));
+        assert!(graph.contains("overlay=-285:-3"));
+
+        // Animated rotation around the anchor is resolved per frame
+        assert!(graph.contains("+(-200)*(1-cos(("));
+    }
+
+    #[test]
+    fn test_export_material_orientation() {
+        let mut editor = Editor::new().set_stage(Stage::new(1920, 1080));
+        editor.session_mut().add_material(Material::Video(
+            VideoMaterial::new("phone", "phone.mp4", 1080, 1920).with_rotation(90),
+        ));
+        editor.session_mut().add_material(Material::Video(
+            VideoMaterial::new("dv", "dv.mov", 1024, 576).with_sample_aspect_ratio(64.0 / 45.0),
+        ));
+
+        let mut track = Track::video();
+        track.add_segment(
+            Segment::video("phone", TimeRange::new(0, 1000), TimeRange::new(0, 1000))
+                .fit(FitMode::Contain),
+        );
+        track.add_segment(
+            Segment::video("dv", TimeRange::new(1000, 1000), TimeRange::new(0, 1000))
+                .fit(FitMode::Contain),
+        );
+        editor.add_track(track);
+
+        let args = build_args(&editor, ExportType::Video);
+        assert!(args.join(" ").contains("-autorotate 0 -i phone.mp4"));
+        assert!(!args.join(" ").contains("-autorotate 0 -i dv.mov"));
+
+        // Frames are turned upright before the fit uses the upright size
+        let graph = filter_complex(&args);
+        assert!(graph.contains("[0:v]transpose=1"));
+        assert!(graph.contains("scale=608:1080"));
+        assert!(graph.contains("[1:v]scale=1024:576"));
+        assert!(graph.contains("setsar=1"));
+        assert!(graph.contains("scale=1920:1080"));
+    }
+
+    #[test]
+    fn test_export_range() {
+        let mut editor = video_editor();
+        let mut track = Track::video();
+        track
+            .add_segment(Segment::new(
+                "s1",
+                SegmentType::Video,
+                "clip",
+                TimeRange::new(0, 4000),
+                TimeRange::new(0, 4000),
+            ))
+            .add_segment(Segment::new(
+                "s2",
+                SegmentType::Video,
+                "clip",
+                TimeRange::new(4000, 4000),
+                TimeRange::new(10000, 4000),
+            ))
+            .add_segment(Segment::new(
+                "s3",
+                SegmentType::Video,
+                "clip",
+                TimeRange::new(8000, 2000),
+                TimeRange::new(20000, 2000),
+            ));
+        editor.add_track(track);
+
+        let options = ExportOptions::new("out.mp4", ExportType::Video).with_range(2500, 6000);
+        let args = build_export(&editor, &options).unwrap().build_args();
+        assert_eq!(
+            input_windows(&args),
+            vec![
+                ("2.5".to_string(), "1.5".to_string(), "clip.mp4".to_string()),
+                ("10".to_string(), "2".to_string(), "clip.mp4".to_string()),
+            ]
+        );
+        let graph = filter_complex(&args);
+        assert!(graph.contains("duration=3.5"));
+        assert!(graph.contains("setpts=PTS+1.5/TB"));
+        assert!(graph.contains("overlay=0:0:enable='between(t,0,1.5)'"));
+
+        // The range is limited to the timeline
+        let options = ExportOptions::new("out.mp4", ExportType::Video).with_range(9000, 20000);
+        let args = build_export(&editor, &options).unwrap().build_args();
+        assert_eq!(input_windows(&args).len(), 1);
+        assert!(filter_complex(&args).contains("duration=1"));
+
+        let options = ExportOptions::new("out.mp4", ExportType::Video).with_range(12000, 15000);
+        assert!(build_export(&editor, &options).is_err());
+        let options = ExportOptions::new("out.mp4", ExportType::Video).with_range(3000, 3000);
+        assert!(build_export(&editor, &options).is_err());
+
+        // Protocol export configs carry the range and options over
+        let config: ExportConfig = serde_json::from_str(
+            r#"{"type":"video","output_file":"out.mp4","options":{"video_codec":"libx265","quality":"23","audio_bitrate":192,"subtitle_mode":"burn","preset":"fast","threads":4},"range":{"start":2500,"duration":3500}}"#,
+        )
+        .unwrap();
+        let options = ExportOptions::try_from(&config).unwrap();
+        assert_eq!(options.range, Some(TimeRange::new(2500, 3500)));
+        assert_eq!(options.video_codec.as_deref(), Some("libx265"));
+        assert_eq!(options.quality, Some(23));
+        assert_eq!(options.audio_bitrate, Some(192));
+        assert_eq!(options.subtitle_mode, SubtitleMode::Burn);
+        assert_eq!(options.custom_options.len(), 2);
+        let args = build_export(&editor, &options).unwrap().build_args();
+        assert_eq!(input_windows(&args).len(), 2);
+        let line = args.join(" ");
+        assert!(line.contains("-c:v libx265"));
+        assert!(line.contains("-crf 23"));
+        assert!(line.contains("-preset fast"));
+        assert!(line.contains("-threads 4"));
+
+        for options in [
+            r#"{"quality":"high"}"#,
+            r#"{"video_bitrate":[3000]}"#,
+            r#"{"subtitle_mode":"hidden"}"#,
+            r#"{"preset":{"speed":"fast"}}"#,
+        ] {
+            let config: ExportConfig = serde_json::from_str(&format!(
+                r#"{{"type":"video","output_file":"out.mp4","options":{}}}"#,
+                options
+            ))
+            .unwrap();
+            assert!(ExportOptions::try_from(&config).is_err());
+        }
+    }
+
+    #[test]
+    fn test_export_range_shifts_overlay_windows() {
+        let mut editor = video_editor();
+        let mut track = Track::video();
+        track.add_segment(Segment::video(
+            "clip",
+            TimeRange::new(0, 30000),
+            TimeRange::new(0, 30000),
+        ));
+        editor.add_track(track);
+
+        let options = ExportOptions::new("out.mp4", ExportType::Video)
+            .with_range(10000, 20000)
+            .with_watermark(Watermark::text("early").window(12000, 14000))
+            .with_watermark(Watermark::text("edge").window(18000, 25000))
+            .with_watermark(Watermark::text("outside").window(22000, 24000))
+            .with_watermark(Watermark::text("always"))
+            .with_delogo(DelogoRegion::new(10, 10, 100, 50).window(5000, 11000));
+        let graph =
+            filter_complex(&build_export(&editor, &options).unwrap().build_args()).to_string();
+        assert!(graph.contains("text=early"));
+        assert!(graph.contains("enable='between(t,2,4)'"));
+        assert!(graph.contains("enable='between(t,8,10)'"));
+        assert!(graph.contains("delogo=x=10:y=10:w=100:h=50:enable='between(t,0,1)'"));
+        assert!(!graph.contains("outside"));
+        assert!(graph.contains("text=always"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_fix_materials_orientation() {
+        use crate::test_support::fake_binary;
+
+        let dir = tempfile::tempdir().unwrap();
+        let ffprobe = fake_binary(
+            dir.path(),
+            "ffprobe",
+            r#"echo '{"streams":[{"index":0,"codec_type":"video","codec_name":"h264","width":1920,"height":1080,"sample_aspect_ratio":"4:3","r_frame_rate":"30/1","side_data_list":[{"side_data_type":"Display Matrix","displaymatrix":"","rotation":-90}]}],"format":{"duration":"2.0"}}'"#,
+        );
+        let mut editor =
+            Editor::new().set_ffprobe_options(FFprobeOptions::new().binary_path(ffprobe));
+        let session = editor.session_mut();
+        session.add_material(Material::Video(VideoMaterial::new("v1", "a.mp4", 0, 0)));
+        session.add_material(Material::Video(
+            VideoMaterial::new("v2", "b.mp4", 1440, 1080)
+                .with_rotation(180)
+                .with_sample_aspect_ratio(0.75),
+        ));
+        editor.fix_materials().await.unwrap();
+
+        let Material::Video(fixed) = &editor.session().materials[0] else {
+            panic!("expected a video material");
+        };
+        assert_eq!(
+            (fixed.dimension.width, fixed.dimension.height),
+            (1080, 2560)
+        );
+        assert_eq!(fixed.rotation, Some(90));
+        assert_eq!(fixed.sample_aspect_ratio, Some(4.0 / 3.0));
+        assert_eq!(fixed.duration, Some(2000));
+
+        let Material::Video(kept) = &editor.session().materials[1] else {
+            panic!("expected a video material");
+        };
+        assert_eq!((kept.dimension.width, kept.dimension.height), (1440, 1080));
+        assert_eq!(kept.rotation, Some(180));
+        assert_eq!(kept.sample_aspect_ratio, Some(0.75));
+    }
+}
@@ -0,0 +1,723 @@
+//! CapCut / JianYing draft import
+//!
+//! Reads a `draft_info.json` and maps its canvas, media materials, texts and
+//! tracks into an [`EditSession`]. Times in drafts are in microseconds, clip
+//! transforms are relative to the canvas centre.
+
+use crate::cut::{
+    EditSession,
+    import::ImportReport,
+    keyframe::{Keyframe, Keyframes},
+    material::{AudioMaterial, Dimension, ImageMaterial, Material, VideoMaterial},
+    segment::{Position, Segment, SegmentType, TextAlign, TextStyle, TimeRange},
+    stage::Stage,
+    track::{Track, TrackType},
+};
+use crate::error::{CutError, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{collections::HashMap, path::Path};
+
+/// Pixel height of CapCut text size 1 on a 1080px wide canvas (approximate)
+const TEXT_SIZE_SCALE: f64 = 6.0;
+
+#[derive(Debug, Deserialize)]
+struct Draft {
+    canvas_config: DraftCanvas,
+    #[serde(default)]
+    config: DraftConfig,
+    #[serde(default)]
+    materials: DraftMaterials,
+    #[serde(default)]
+    tracks: Vec<DraftTrack>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DraftCanvas {
+    width: i32,
+    height: i32,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DraftConfig {
+    /// Mutes the original sound of the main video track
+    #[serde(default)]
+    video_mute: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DraftMaterials {
+    #[serde(default)]
+    videos: Vec<DraftVideo>,
+    #[serde(default)]
+    audios: Vec<DraftAudio>,
+    #[serde(default)]
+    texts: Vec<DraftText>,
+    #[serde(default)]
+    speeds: Vec<DraftSpeed>,
+    /// Every other material category, referenced through `extra_material_refs`
+    #[serde(flatten)]
+    other: HashMap<String, Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DraftVideo {
+    id: String,
+    path: String,
+    /// `video` or `photo`
+    #[serde(rename = "type", default)]
+    material_type: String,
+    width: i32,
+    height: i32,
+    #[serde(default)]
+    duration: u64,
+    #[serde(default = "default_true")]
+    has_audio: bool,
+    #[serde(default)]
+    crop: Option<DraftCrop>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DraftCrop {
+    upper_left_x: f64,
+    upper_left_y: f64,
+    lower_right_x: f64,
+    lower_right_y: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct DraftAudio {
+    id: String,
+    path: String,
+    #[serde(default)]
+    duration: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct DraftText {
+    id: String,
+    /// JSON document with the plain `text` and its rich text `styles`
+    content: String,
+    #[serde(default)]
+    font_path: String,
+    #[serde(default = "default_font_size")]
+    font_size: f64,
+    #[serde(default)]
+    text_color: String,
+    /// 0 left, 1 center, 2 right
+    #[serde(default)]
+    alignment: u32,
+    #[serde(default)]
+    border_color: String,
+    #[serde(default)]
+    border_width: f64,
+    #[serde(default)]
+    background_color: String,
+    #[serde(default)]
+    has_shadow: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct DraftSpeed {
+    id: String,
+    #[serde(default)]
+    curve_speed: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DraftTrack {
+    id: String,
+    #[serde(rename = "type")]
+    track_type: String,
+    #[serde(default)]
+    segments: Vec<DraftSegment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DraftSegment {
+    id: String,
+    material_id: String,
+    target_timerange: DraftTimeRange,
+    #[serde(default)]
+    source_timerange: Option<DraftTimeRange>,
+    #[serde(default = "default_one")]
+    volume: f64,
+    #[serde(default = "default_true")]
+    visible: bool,
+    #[serde(default)]
+    reverse: bool,
+    #[serde(default)]
+    clip: Option<DraftClip>,
+    #[serde(default)]
+    extra_material_refs: Vec<String>,
+    #[serde(default)]
+    common_keyframes: Vec<Value>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct DraftTimeRange {
+    start: u64,
+    duration: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct DraftClip {
+    #[serde(default = "default_one")]
+    alpha: f64,
+    #[serde(default)]
+    flip: DraftFlip,
+    #[serde(default)]
+    rotation: f64,
+    scale: DraftPoint,
+    transform: DraftPoint,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DraftFlip {
+    #[serde(default)]
+    horizontal: bool,
+    #[serde(default)]
+    vertical: bool,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct DraftPoint {
+    x: f64,
+    y: f64,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_one() -> f64 {
+    1.0
+}
+
+fn default_font_size() -> f64 {
+    15.0
+}
+
+impl DraftTimeRange {
+    /// Convert to milliseconds, rounding both ends so adjacent ranges stay adjacent
+    fn to_millis(self) -> TimeRange {
+        let start = (self.start as f64 / 1000.0).round() as u32;
+        let end = ((self.start + self.duration) as f64 / 1000.0).round() as u32;
+        TimeRange::new(start, end - start)
+    }
+}
+
+/// Import a draft from its JSON content
+pub fn from_json(json: &str) -> Result<(EditSession, ImportReport)> {
+    let draft: Draft = serde_json::from_str(json)?;
+    Importer::new(&draft).run()
+}
+
+/// Import a draft from a `draft_info.json` file
+pub fn from_file<P: AsRef<Path>>(path: P) -> Result<(EditSession, ImportReport)> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Err(CutError::file_not_found(path.to_string_lossy()));
+    }
+    from_json(&std::fs::read_to_string(path)?)
+}
+
+struct Importer<'a> {
+    draft: &'a Draft,
+    session: EditSession,
+    report: ImportReport,
+}
+
+impl<'a> Importer<'a> {
+    fn new(draft: &'a Draft) -> Self {
+        let canvas = &draft.canvas_config;
+        Self {
+            draft,
+            session: EditSession::new(Stage::new(canvas.width, canvas.height)),
+            report: ImportReport::default(),
+        }
+    }
+
+    fn run(mut self) -> Result<(EditSession, ImportReport)> {
+        let draft = self.draft;
+
+        for video in &draft.materials.videos {
+            let material = if video.material_type == "photo" {
+                Material::Image(ImageMaterial::new(
+                    &video.id,
+                    &video.path,
+                    video.width,
+                    video.height,
+                ))
+            } else {
+                Material::Video(
+                    VideoMaterial::new(&video.id, &video.path, video.width, video.height)
+                        .with_duration(micros_to_millis(video.duration)),
+                )
+            };
+            self.session.add_material(material);
+
+            if let Some(crop) = &video.crop
+                && (
+                    crop.upper_left_x,
+                    crop.upper_left_y,
+                    crop.lower_right_x,
+                    crop.lower_right_y,
+                ) != (0.0, 0.0, 1.0, 1.0)
+            {
+                self.report
+                    .push("crop", &video.id, "Material crop is ignored");
+            }
+        }
+        for audio in &draft.materials.audios {
+            self.session.add_material(Material::Audio(
+                AudioMaterial::new(&audio.id, &audio.path)
+                    .with_duration(micros_to_millis(audio.duration)),
+            ));
+        }
+
+        // Drafts list tracks bottom first, sessions list visual tracks top first
+        let mut visual_tracks = Vec::new();
+        let mut audio_tracks = Vec::new();
+        let mut main_video = true;
+        for draft_track in &draft.tracks {
+            match draft_track.track_type.as_str() {
+                "video" => {
+                    let (track, sound) = self.video_track(draft_track, main_video);
+                    main_video = false;
+                    visual_tracks.push(track);
+                    audio_tracks.extend(sound);
+                }
+                "audio" => audio_tracks.push(self.audio_track(draft_track)),
+                "text" => visual_tracks.push(self.text_track(draft_track)),
+                other => self.report.push(
+                    format!("track:{}", other),
+                    &draft_track.id,
+                    format!(
+                        "{} track dropped with {} segments",
+                        other,
+                        draft_track.segments.len()
+                    ),
+                ),
+            }
+        }
+        for track in visual_tracks.into_iter().rev().chain(audio_tracks) {
+            self.session.add_track(track);
+        }
+
+        self.session.validate()?;
+        Ok((self.session, self.report))
+    }
+
+    /// Build a video track, plus an audio track carrying the clips' original sound
+    fn video_track(&mut self, draft_track: &DraftTrack, main: bool) -> (Track, Option<Track>) {
+        let draft = self.draft;
+        let mut track = Track::new(&draft_track.id, TrackType::Video);
+        let mut sound = Track::new(format!("{}-audio", draft_track.id), TrackType::Audio);
+        let muted = main && draft.config.video_mute;
+
+        for draft_segment in &draft_track.segments {
+            let Some(video) = draft
+                .materials
+                .videos
+                .iter()
+                .find(|v| v.id == draft_segment.material_id)
+            else {
+                self.missing_material(draft_segment);
+                continue;
+            };
+            if !self.check_segment(draft_segment) {
+                continue;
+            }
+
+            let segment_type = if video.material_type == "photo" {
+                SegmentType::Image
+            } else {
+                SegmentType::Video
+            };
+            let (target, source) = timeranges(draft_segment);
+            let mut segment =
+                Segment::new(&draft_segment.id, segment_type, &video.id, target, source);
+            if let Some(clip) = &draft_segment.clip {
+                let (position, size) = self.place(clip, video.width, video.height);
+                segment = segment.position(position).scale(size);
+                segment.keyframes = self.clip_keyframes(&draft_segment.id, clip);
+            }
+            track.add_segment(segment);
+
+            if segment_type == SegmentType::Video
+                && video.has_audio
+                && !muted
+                && draft_segment.volume > 0.0
+            {
+                sound.add_segment(
+                    Segment::audio(
+                        format!("{}-audio", draft_segment.id),
+                        &video.id,
+                        target,
+                        source,
+                    )
+                    .with_keyframes(volume_keyframes(draft_segment.volume)),
+                );
+            }
+        }
+
+        let sound = (!sound.segments.is_empty()).then_some(sound);
+        (track, sound)
+    }
+
+    fn audio_track(&mut self, draft_track: &DraftTrack) -> Track {
+        let mut track = Track::new(&draft_track.id, TrackType::Audio);
+        for draft_segment in &draft_track.segments {
+            if !self
+                .draft
+                .materials
+                .audios
+                .iter()
+                .any(|a| a.id == draft_segment.material_id)
+            {
+                self.missing_material(draft_segment);
+                continue;
+            }
+            if !self.check_segment(draft_segment) {
+                continue;
+            }
+
+            let (target, source) = timeranges(draft_segment);
+            track.add_segment(
+                Segment::audio(
+                    &draft_segment.id,
+                    &draft_segment.material_id,
+                    target,
+                    source,
+                )
+                .with_keyframes(volume_keyframes(draft_segment.volume)),
+            );
+        }
+        track
+    }
+
+    fn text_track(&mut self, draft_track: &DraftTrack) -> Track {
+        let draft = self.draft;
+        let mut track = Track::new(&draft_track.id, TrackType::Text);
+        let stage = self.session.stage.clone();
+
+        for draft_segment in &draft_track.segments {
+            let Some(text) = draft
+                .materials
+                .texts
+                .iter()
+                .find(|t| t.id == draft_segment.material_id)
+            else {
+                self.missing_material(draft_segment);
+                continue;
+            };
+            if !self.check_segment(draft_segment) {
+                continue;
+            }
+
+            let content: Option<Value> = serde_json::from_str(&text.content).ok();
+            let plain = content
+                .as_ref()
+                .and_then(|c| c["text"].as_str())
+                .unwrap_or(&text.content);
+            if plain.is_empty() {
+                continue;
+            }
+            if content
+                .as_ref()
+                .and_then(|c| c["styles"].as_array())
+                .is_some_and(|styles| styles.len() > 1)
+            {
+                self.report.push(
+                    "rich_text",
+                    &draft_segment.id,
+                    "Only the first text style is kept",
+                );
+            }
+            if !text.background_color.is_empty() {
+                self.report.push(
+                    "text_background",
+                    &draft_segment.id,
+                    "Text background is ignored",
+                );
+            }
+            if text.has_shadow {
+                self.report
+                    .push("text_shadow", &draft_segment.id, "Text shadow is ignored");
+            }
+
+            let scale = draft_segment
+                .clip
+                .as_ref()
+                .map(|c| c.scale.x.abs())
+                .unwrap_or(1.0);
+            let font_size = (text.font_size * TEXT_SIZE_SCALE * scale * stage.width as f64 / 1080.0)
+                .round()
+                .max(1.0) as u32;
+            let mut style = TextStyle::new(plain)
+                .with_font_size(font_size)
+                .with_align(match text.alignment {
+                    0 => TextAlign::Left,
+                    2 => TextAlign::Right,
+                    _ => TextAlign::Center,
+                });
+            if !text.font_path.is_empty() {
+                style = style.with_font(&text.font_path);
+            }
+            if !text.text_color.is_empty() {
+                style = style.with_color(&text.text_color);
+            }
+            if !text.border_color.is_empty() && text.border_width > 0.0 {
+                let width = (text.border_width * font_size as f64).round().max(1.0) as u32;
+                style = style.with_stroke(&text.border_color, width);
+            }
+
+            // The text box spans the stage, centred on the clip position
+            let (target, _) = timeranges(draft_segment);
+            let mut segment = Segment::text(&draft_segment.id, "", target, target)
+                .with_text(style)
+                .scale(Dimension::new(stage.width, font_size as i32));
+            if let Some(clip) = &draft_segment.clip {
+                let center_y = stage.height as f64 * (1.0 - clip.transform.y) / 2.0;
+                segment = segment.position(Position::new(
+                    (clip.transform.x * stage.width as f64 / 2.0).round() as i32,
+                    (center_y - font_size as f64 / 2.0).round() as i32,
+                ));
+                segment.keyframes = self.clip_keyframes(&draft_segment.id, clip);
+            }
+            track.add_segment(segment);
+        }
+        track
+    }
+
+    /// Position and size of a clip on the stage.
+    ///
+    /// Drafts fit media inside the canvas, then scale it and move its centre
+    /// by `transform`, in half canvas sizes with y pointing up.
+    fn place(&self, clip: &DraftClip, width: i32, height: i32) -> (Position, Dimension) {
+        let stage = &self.session.stage;
+        let (stage_w, stage_h) = (stage.width as f64, stage.height as f64);
+        let fit = (stage_w / width as f64).min(stage_h / height as f64);
+        let w = (width as f64 * fit * clip.scale.x.abs()).round().max(1.0);
+        let h = (height as f64 * fit * clip.scale.y.abs()).round().max(1.0);
+
+        let center_x = stage_w * (1.0 + clip.transform.x) / 2.0;
+        let center_y = stage_h * (1.0 - clip.transform.y) / 2.0;
+        (
+            Position::new(
+                (center_x - w / 2.0).round() as i32,
+                (center_y - h / 2.0).round() as i32,
+            ),
+            Dimension::new(w as i32, h as i32),
+        )
+    }
+
+    /// Keyframes holding a clip's opacity and rotation
+    fn clip_keyframes(&mut self, segment_id: &str, clip: &DraftClip) -> Keyframes {
+        if clip.flip.horizontal || clip.flip.vertical {
+            self.report.push("flip", segment_id, "Clip flip is ignored");
+        }
+
+        let mut keyframes = Keyframes::default();
+        if clip.alpha < 1.0 {
+            keyframes.opacity = vec![Keyframe::new(0, clip.alpha.clamp(0.0, 1.0))];
+        }
+        if clip.rotation != 0.0 {
+            keyframes.rotation = vec![Keyframe::new(0, clip.rotation)];
+        }
+        keyframes
+    }
+
+    /// Report what a segment carries beyond the session model.
+    ///
+    /// Returns `false` when the segment is dropped.
+    fn check_segment(&mut self, segment: &DraftSegment) -> bool {
+        if !segment.visible {
+            self.report
+                .push("hidden_segment", &segment.id, "Hidden segment dropped");
+            return false;
+        }
+        if segment.reverse {
+            self.report
+                .push("reverse", &segment.id, "Reverse playback is ignored");
+        }
+        if !segment.common_keyframes.is_empty() {
+            self.report
+                .push("keyframes", &segment.id, "Segment keyframes are ignored");
+        }
+
+        let draft = self.draft;
+        for id in &segment.extra_material_refs {
+            if let Some(speed) = draft.materials.speeds.iter().find(|s| &s.id == id) {
+                if speed.curve_speed.as_ref().is_some_and(|c| !c.is_null()) {
+                    self.report
+                        .push("curve_speed", &segment.id, "Curve speed is ignored");
+                }
+                continue;
+            }
+            let Some((category, material)) = self.extra_material(id) else {
+                continue;
+            };
+
+            // Default canvases, animations and audio settings change nothing
+            let feature = match category {
+                "canvases" => (material["type"] != "canvas_color"
+                    || material["color"].as_str().is_some_and(|c| !c.is_empty()))
+                .then_some("canvas"),
+                "material_animations" => material["animations"]
+                    .as_array()
+                    .is_some_and(|a| !a.is_empty())
+                    .then_some("animation"),
+                "sound_channel_mappings" => material["audio_channel_mapping"]
+                    .as_i64()
+                    .is_some_and(|m| m != 0)
+                    .then_some("sound_channel_mapping"),
+                "vocal_separations" => material["choice"]
+                    .as_i64()
+                    .is_some_and(|c| c != 0)
+                    .then_some("vocal_separation"),
+                "placeholders" => None,
+                _ => Some(category.strip_suffix('s').unwrap_or(category)),
+            };
+            if let Some(feature) = feature {
+                self.report.push(
+                    feature,
+                    &segment.id,
+                    format!("{} material '{}' is ignored", feature, id),
+                );
+            }
+        }
+        true
+    }
+
+    /// Find a material by ID in the untyped categories
+    fn extra_material(&self, id: &str) -> Option<(&'a str, &'a Value)> {
+        self.draft
+            .materials
+            .other
+            .iter()
+            .filter_map(|(category, items)| Some((category, items.as_array()?)))
+            .find_map(|(category, items)| {
+                items
+                    .iter()
+                    .find(|item| item["id"] == id)
+                    .map(|item| (category.as_str(), item))
+            })
+    }
+
+    fn missing_material(&mut self, segment: &DraftSegment) {
+        self.report.push(
+            "missing_material",
+            &segment.id,
+            format!(
+                "Material '{}' not found, segment dropped",
+                segment.material_id
+            ),
+        );
+    }
+}
+
+fn micros_to_millis(micros: u64) -> u32 {
+    (micros as f64 / 1000.0).round() as u32
+}
+
+/// Target and source ranges in milliseconds; sources default to the target duration
+fn timeranges(segment: &DraftSegment) -> (TimeRange, TimeRange) {
+    let target = segment.target_timerange.to_millis();
+    let source = segment
+        .source_timerange
+        .map(DraftTimeRange::to_millis)
+        .unwrap_or(TimeRange::new(0, target.duration));
+    (target, source)
+}
+
+/// Constant volume keyframe, none for unchanged volume
+fn volume_keyframes(volume: f64) -> Keyframes {
+    let mut keyframes = Keyframes::default();
+    if (volume - 1.0).abs() > f64::EPSILON {
+        keyframes.volume = vec![Keyframe::new(0, volume.max(0.0))];
+    }
+    keyframes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_sample_draft() {
+        let (session, report) =
+            from_json(include_str!("../../../../../docs/draft_info.json")).unwrap();
+
+        assert_eq!((session.stage.width, session.stage.height), (1080, 1920));
+        assert_eq!(session.materials.len(), 3);
+        assert_eq!(session.total_duration(), 12400);
+
+        // Text on top, then the picture-in-picture track, then the main track
+        let visual_tracks = session.visual_tracks();
+        assert_eq!(visual_tracks.len(), 3);
+        assert_eq!(visual_tracks[0].track_type, TrackType::Text);
+        assert_eq!(visual_tracks[0].segments.len(), 7);
+        let text = visual_tracks[0].segments[0].text.as_ref().unwrap();
+        assert_eq!(text.content, "熟食海格津巴珍");
+        assert_eq!(text.align, TextAlign::Center);
+        assert_eq!(
+            visual_tracks[0].segments[0].target_timerange,
+            TimeRange::new(267, 1400)
+        );
+
+        let pip = &visual_tracks[1].segments[0];
+        assert_eq!(pip.scale, Some(Dimension::new(864, 1536)));
+        assert_eq!(pip.position, Some(Position::new(320, 384)));
+
+        // Original sound of both video tracks, with the main track boosted
+        let audio_tracks = session.audio_tracks();
+        assert_eq!(audio_tracks.len(), 2);
+        let volume = audio_tracks[0].segments[0].keyframes.volume[0].value;
+        assert!((volume - 1.9426).abs() < 1e-4);
+
+        assert_eq!(report.features(), vec!["chroma"]);
+    }
+
+    #[test]
+    fn test_unsupported_features() {
+        let json = r#"{
+            "canvas_config": {"width": 1920, "height": 1080},
+            "materials": {
+                "videos": [{"id": "v", "path": "a.mp4", "type": "video", "width": 1280, "height": 720}],
+                "masks": [{"id": "m", "type": "mask"}],
+                "canvases": [{"id": "c", "type": "canvas_blur", "color": ""}]
+            },
+            "tracks": [
+                {"id": "t1", "type": "video", "segments": [{
+                    "id": "s1", "material_id": "v",
+                    "target_timerange": {"start": 0, "duration": 2000000},
+                    "source_timerange": {"start": 1000000, "duration": 4000000},
+                    "clip": {"alpha": 0.5, "flip": {"horizontal": true, "vertical": false},
+                             "rotation": 90.0, "scale": {"x": 0.5, "y": 0.5},
+                             "transform": {"x": -0.5, "y": 0.5}},
+                    "extra_material_refs": ["m", "c"]
+                }, {
+                    "id": "s2", "material_id": "gone",
+                    "target_timerange": {"start": 2000000, "duration": 1000000}
+                }]},
+                {"id": "t2", "type": "effect", "segments": []}
+            ]
+        }"#;
+
+        let (session, report) = from_json(json).unwrap();
+        let segment = &session.tracks[0].segments[0];
+        assert_eq!(segment.source_timerange, TimeRange::new(1000, 4000));
+        assert_eq!(segment.scale, Some(Dimension::new(960, 540)));
+        assert_eq!(segment.position, Some(Position::new(0, 0)));
+        assert_eq!(segment.keyframes.opacity, vec![Keyframe::new(0, 0.5)]);
+        assert_eq!(segment.keyframes.rotation, vec![Keyframe::new(0, 90.0)]);
+
+        assert_eq!(
+            report.features(),
+            vec!["mask", "canvas", "flip", "missing_material", "track:effect"]
+        );
+        assert!(report.unsupported.iter().all(|f| !f.detail.is_empty()));
+    }
+}
@@ -0,0 +1,58 @@
+//! Import projects from third-party editors
+//!
+//! Importers map what the session model can express and record everything
+//! else in an [`ImportReport`], so nothing is dropped silently.
+
+pub mod capcut;
+
+use serde::{Deserialize, Serialize};
+
+/// A feature of the imported project that was dropped
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnsupportedFeature {
+    /// Feature name, e.g. `chroma` or `track:effect`
+    pub feature: String,
+    /// ID of the track, segment or material carrying the feature
+    pub source_id: String,
+    /// Human readable description
+    pub detail: String,
+}
+
+/// Report of an import
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportReport {
+    /// Dropped features, in the order they were found
+    pub unsupported: Vec<UnsupportedFeature>,
+}
+
+impl ImportReport {
+    /// Check if everything was imported
+    pub fn is_empty(&self) -> bool {
+        self.unsupported.is_empty()
+    }
+
+    /// Record a dropped feature
+    pub fn push<S1, S2, S3>(&mut self, feature: S1, source_id: S2, detail: S3)
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+        S3: Into<String>,
+    {
+        self.unsupported.push(UnsupportedFeature {
+            feature: feature.into(),
+            source_id: source_id.into(),
+            detail: detail.into(),
+        });
+    }
+
+    /// Distinct dropped feature names, in the order they were found
+    pub fn features(&self) -> Vec<&str> {
+        let mut features: Vec<&str> = Vec::new();
+        for item in &self.unsupported {
+            if !features.contains(&item.feature.as_str()) {
+                features.push(&item.feature);
+            }
+        }
+        features
+    }
+}
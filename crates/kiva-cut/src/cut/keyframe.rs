@@ -0,0 +1,364 @@
+//! Keyframe animation for segment properties
+//!
+//! Keyframe lists compile into piecewise ffmpeg expressions, which filters
+//! evaluate per frame.
+
+use crate::error::{CutError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Interpolation from a keyframe to the next one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    /// Constant speed
+    #[default]
+    Linear,
+    /// Start slow, end fast
+    EaseIn,
+    /// Start fast, end slow
+    EaseOut,
+    /// Start and end slow
+    EaseInOut,
+    /// Keep the value until the next keyframe
+    Hold,
+}
+
+/// Property value at a point in time
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    /// Time in milliseconds, relative to the segment start on the timeline
+    pub time: u32,
+    /// Property value
+    pub value: f64,
+    /// Interpolation towards the next keyframe
+    #[serde(default)]
+    pub easing: Easing,
+}
+
+/// Keyframe lists for the animatable segment properties
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Keyframes {
+    /// X position in pixels
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub x: Vec<Keyframe>,
+    /// Y position in pixels
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub y: Vec<Keyframe>,
+    /// Width in pixels
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub width: Vec<Keyframe>,
+    /// Height in pixels
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub height: Vec<Keyframe>,
+    /// Opacity (0.0 to 1.0)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub opacity: Vec<Keyframe>,
+    /// Clockwise rotation in degrees
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rotation: Vec<Keyframe>,
+    /// Volume multiplier (1.0 is unchanged)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub volume: Vec<Keyframe>,
+}
+
+impl Easing {
+    /// Ease a progress value running from 0 to 1
+    fn apply(&self, p: f64) -> f64 {
+        match self {
+            Easing::Linear => p,
+            Easing::EaseIn => p * p,
+            Easing::EaseOut => 1.0 - (1.0 - p) * (1.0 - p),
+            Easing::EaseInOut => p * p * (3.0 - 2.0 * p),
+            Easing::Hold => 0.0,
+        }
+    }
+
+    /// Expression easing a progress expression running from 0 to 1
+    fn expression(&self, p: &str) -> String {
+        match self {
+            Easing::Linear => p.to_string(),
+            Easing::EaseIn => format!("pow({},2)", p),
+            Easing::EaseOut => format!("(1-pow(1-{},2))", p),
+            Easing::EaseInOut => format!("(pow({p},2)*(3-2*{p}))", p = p),
+            Easing::Hold => "0".to_string(),
+        }
+    }
+}
+
+impl Keyframe {
+    /// Create a linear keyframe
+    pub fn new(time: u32, value: f64) -> Self {
+        Self {
+            time,
+            value,
+            easing: Easing::Linear,
+        }
+    }
+
+    /// Set easing towards the next keyframe
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+}
+
+impl Keyframes {
+    /// Check if no property is animated
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty()
+            && self.y.is_empty()
+            && self.width.is_empty()
+            && self.height.is_empty()
+            && self.opacity.is_empty()
+            && self.rotation.is_empty()
+            && self.volume.is_empty()
+    }
+
+    /// Check if position, size or rotation is animated
+    pub fn has_transform(&self) -> bool {
+        !(self.x.is_empty()
+            && self.y.is_empty()
+            && self.width.is_empty()
+            && self.height.is_empty()
+            && self.rotation.is_empty())
+    }
+
+    /// Split at `offset` ms, rebasing the second half to start at zero.
+    ///
+    /// Each half gets a keyframe at the cut holding the interpolated value.
+    pub fn split_at(&self, offset: u32) -> (Keyframes, Keyframes) {
+        let split = |keyframes: &[Keyframe]| -> (Vec<Keyframe>, Vec<Keyframe>) {
+            let Some(value) = value_at(keyframes, offset) else {
+                return (Vec::new(), Vec::new());
+            };
+            let easing = keyframes
+                .iter()
+                .filter(|k| k.time <= offset)
+                .max_by_key(|k| k.time)
+                .map(|k| k.easing)
+                .unwrap_or_default();
+            let cut = Keyframe::new(offset, value).with_easing(easing);
+
+            let mut first: Vec<Keyframe> = keyframes
+                .iter()
+                .copied()
+                .filter(|k| k.time < offset)
+                .collect();
+            first.push(cut);
+            first.sort_by_key(|k| k.time);
+
+            let mut second = vec![Keyframe { time: 0, ..cut }];
+            second.extend(
+                keyframes
+                    .iter()
+                    .filter(|k| k.time > offset)
+                    .map(|k| Keyframe {
+                        time: k.time - offset,
+                        ..*k
+                    }),
+            );
+            second.sort_by_key(|k| k.time);
+            (first, second)
+        };
+
+        let mut first = Keyframes::default();
+        let mut second = Keyframes::default();
+        (first.x, second.x) = split(&self.x);
+        (first.y, second.y) = split(&self.y);
+        (first.width, second.width) = split(&self.width);
+        (first.height, second.height) = split(&self.height);
+        (first.opacity, second.opacity) = split(&self.opacity);
+        (first.rotation, second.rotation) = split(&self.rotation);
+        (first.volume, second.volume) = split(&self.volume);
+        (first, second)
+    }
+
+    /// Validate keyframe times and value ranges
+    pub fn validate(&self) -> Result<()> {
+        let lists = [
+            ("x", &self.x),
+            ("y", &self.y),
+            ("width", &self.width),
+            ("height", &self.height),
+            ("opacity", &self.opacity),
+            ("rotation", &self.rotation),
+            ("volume", &self.volume),
+        ];
+        for (name, keyframes) in lists {
+            let mut times: Vec<u32> = keyframes.iter().map(|k| k.time).collect();
+            times.sort_unstable();
+            times.dedup();
+            if times.len() != keyframes.len() {
+                return Err(CutError::invalid_params(format!(
+                    "Duplicate {} keyframe time",
+                    name
+                )));
+            }
+            if keyframes.iter().any(|k| !k.value.is_finite()) {
+                return Err(CutError::invalid_params(format!(
+                    "{} keyframe value must be finite",
+                    name
+                )));
+            }
+        }
+
+        if self
+            .width
+            .iter()
+            .chain(&self.height)
+            .any(|k| k.value <= 0.0)
+        {
+            return Err(CutError::invalid_params(
+                "Size keyframe values must be positive",
+            ));
+        }
+        if self.opacity.iter().any(|k| !(0.0..=1.0).contains(&k.value)) {
+            return Err(CutError::invalid_params(
+                "Opacity keyframe values must be between 0.0 and 1.0",
+            ));
+        }
+        if self.volume.iter().any(|k| k.value < 0.0) {
+            return Err(CutError::invalid_params(
+                "Volume keyframe values cannot be negative",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Interpolated value at `time` ms, `None` when there are no keyframes
+pub fn value_at(keyframes: &[Keyframe], time: u32) -> Option<f64> {
+    let mut sorted = keyframes.to_vec();
+    sorted.sort_by_key(|k| k.time);
+    let first = sorted.first()?;
+    if time <= first.time {
+        return Some(first.value);
+    }
+
+    for pair in sorted.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        if time < to.time {
+            let p = (time - from.time) as f64 / (to.time - from.time) as f64;
+            return Some(from.value + (to.value - from.value) * from.easing.apply(p));
+        }
+    }
+    sorted.last().map(|k| k.value)
+}
+
+/// Compile keyframes into an ffmpeg expression of `time`, in seconds.
+///
+/// The value holds before the first and after the last keyframe. Returns
+/// `None` when there are no keyframes.
+pub fn expression(keyframes: &[Keyframe], time: &str) -> Option<String> {
+    let mut sorted = keyframes.to_vec();
+    sorted.sort_by_key(|k| k.time);
+    let last = sorted.last()?;
+
+    let mut expr = format_number(last.value);
+    for pair in sorted.windows(2).rev() {
+        let (from, to) = (pair[0], pair[1]);
+        let start = from.time as f64 / 1000.0;
+        let end = to.time as f64 / 1000.0;
+
+        let progress = format!("((({})-{})/{})", time, start, end - start);
+        let value = format!(
+            "{}+{}*{}",
+            format_number(from.value),
+            format_number(to.value - from.value),
+            from.easing.expression(&progress)
+        );
+        expr = format!("if(lt({},{}),{},{})", time, end, value, expr);
+    }
+
+    let first = sorted[0];
+    if sorted.len() > 1 {
+        expr = format!(
+            "if(lt({},{}),{},{})",
+            time,
+            first.time as f64 / 1000.0,
+            format_number(first.value),
+            expr
+        );
+    }
+    Some(expr)
+}
+
+/// Format a value, wrapping negatives so they can follow an operator
+fn format_number(value: f64) -> String {
+    if value < 0.0 {
+        format!("({})", value)
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expression() {
+        assert_eq!(expression(&[], "t"), None);
+        assert_eq!(expression(&[Keyframe::new(500, 2.0)], "t").unwrap(), "2");
+
+        let keyframes = [Keyframe::new(2000, -50.0), Keyframe::new(0, 100.0)];
+        assert_eq!(
+            expression(&keyframes, "t").unwrap(),
+            "if(lt(t,0),100,if(lt(t,2),100+(-150)*(((t)-0)/2),(-50)))"
+        );
+
+        let eased = [
+            Keyframe::new(1000, 0.0).with_easing(Easing::EaseInOut),
+            Keyframe::new(1500, 1.0).with_easing(Easing::Hold),
+            Keyframe::new(2000, 0.5),
+        ];
+        assert_eq!(
+            expression(&eased, "(t-1)").unwrap(),
+            "if(lt((t-1),1),0,if(lt((t-1),1.5),\
+             0+1*(pow(((((t-1))-1)/0.5),2)*(3-2*((((t-1))-1)/0.5))),\
+             if(lt((t-1),2),1+(-0.5)*0,0.5)))"
+        );
+    }
+
+    #[test]
+    fn test_split() {
+        let keyframes = Keyframes {
+            x: vec![Keyframe::new(0, 0.0), Keyframe::new(1000, 100.0)],
+            volume: vec![Keyframe::new(200, 0.5)],
+            ..Default::default()
+        };
+        assert_eq!(value_at(&keyframes.x, 250), Some(25.0));
+
+        let (first, second) = keyframes.split_at(400);
+        assert_eq!(
+            first.x,
+            vec![Keyframe::new(0, 0.0), Keyframe::new(400, 40.0)]
+        );
+        assert_eq!(
+            second.x,
+            vec![Keyframe::new(0, 40.0), Keyframe::new(600, 100.0)]
+        );
+        assert_eq!(second.volume, vec![Keyframe::new(0, 0.5)]);
+        assert!(second.opacity.is_empty());
+    }
+
+    #[test]
+    fn test_validation() {
+        let mut keyframes = Keyframes::default();
+        assert!(keyframes.is_empty());
+        assert!(keyframes.validate().is_ok());
+
+        keyframes.opacity = vec![Keyframe::new(0, 0.0), Keyframe::new(1000, 1.0)];
+        assert!(!keyframes.is_empty());
+        assert!(!keyframes.has_transform());
+        assert!(keyframes.validate().is_ok());
+
+        keyframes.opacity.push(Keyframe::new(1000, 0.5));
+        assert!(keyframes.validate().is_err());
+
+        keyframes.opacity.pop();
+        keyframes.width = vec![Keyframe::new(0, 0.0)];
+        assert!(keyframes.has_transform());
+        assert!(keyframes.validate().is_err());
+    }
+}
@@ -34,6 +34,14 @@ pub struct VideoMaterial {
     pub codec: Option<String>,
     /// Bitrate in kbps (optional)
     pub bitrate: Option<u32>,
+    /// Clockwise rotation in degrees that turns the stored frames upright
+    /// (optional); the dimension is already the upright size
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<i32>,
+    /// Sample aspect ratio of non-square pixels (optional); the dimension is
+    /// already in square pixels
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_aspect_ratio: Option<f64>,
 }
 
 /// Audio material with metadata
@@ -173,6 +181,8 @@ impl Material {
             fps: None,
             codec: None,
             bitrate: None,
+            rotation: None,
+            sample_aspect_ratio: None,
         })
     }
 
@@ -219,6 +229,8 @@ impl VideoMaterial {
             fps: None,
             codec: None,
             bitrate: None,
+            rotation: None,
+            sample_aspect_ratio: None,
         }
     }
 
@@ -255,6 +267,24 @@ impl VideoMaterial {
         }
     }
 
+    /// Set the clockwise rotation that turns the stored frames upright
+    pub fn with_rotation(mut self, rotation: i32) -> Self {
+        self.rotation = Some(rotation);
+        self
+    }
+
+    /// Set the sample aspect ratio of non-square pixels
+    pub fn with_sample_aspect_ratio(mut self, sar: f64) -> Self {
+        self.sample_aspect_ratio = Some(sar);
+        self
+    }
+
+    /// Check if frames have to be turned or stretched before use
+    pub fn needs_orientation(&self) -> bool {
+        self.rotation.is_some_and(|r| r.rem_euclid(360) != 0)
+            || self.sample_aspect_ratio.is_some_and(|sar| sar != 1.0)
+    }
+
     /// Validate the video material
     pub fn validate(&self) -> Result<()> {
         if self.dimension.width <= 0 {
@@ -268,6 +298,19 @@ impl VideoMaterial {
                 return Err(CutError::invalid_params("Video FPS must be positive"));
             }
         }
+        if self.rotation.is_some_and(|r| r % 90 != 0) {
+            return Err(CutError::invalid_params(
+                "Video rotation must be a multiple of 90 degrees",
+            ));
+        }
+        if self
+            .sample_aspect_ratio
+            .is_some_and(|sar| !sar.is_finite() || sar <= 0.0)
+        {
+            return Err(CutError::invalid_params(
+                "Sample aspect ratio must be positive",
+            ));
+        }
         Ok(())
     }
 }
@@ -554,6 +597,11 @@ mod tests {
         let invalid_fps = VideoMaterial::new("v1", "test.mp4", 1920, 1080).with_fps(0.0);
         assert!(invalid_fps.validate().is_err());
 
+        let portrait = VideoMaterial::new("v1", "test.mp4", 1080, 1920).with_rotation(90);
+        assert!(portrait.needs_orientation());
+        assert!(portrait.clone().with_rotation(45).validate().is_err());
+        assert!(portrait.with_sample_aspect_ratio(0.0).validate().is_err());
+
         let invalid_audio = AudioMaterial::new("a1", "test.wav").with_channels(0);
         assert!(invalid_audio.validate().is_err());
 
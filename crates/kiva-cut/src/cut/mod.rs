@@ -3,22 +3,34 @@
 //! This module provides functionality for video editing operations including
 //! track-based composition, material management, and export capabilities.
 
+pub mod concat;
 pub mod editor;
+pub mod import;
+pub mod keyframe;
 pub mod material;
 pub mod protocol;
 pub mod segment;
 pub mod stage;
+pub mod subtitle;
 pub mod track;
+pub mod transition;
 
 // Re-export main types
 pub use editor::Editor;
+pub use import::{ImportReport, UnsupportedFeature};
+pub use keyframe::{Easing, Keyframe, Keyframes};
 pub use material::{
     AudioMaterial, Dimension, ImageMaterial, Material, MaterialType, VideoMaterial,
 };
 pub use protocol::{CutProtocol, ExportConfig, ExportType};
-pub use segment::{Position, Scale, Segment, TimeRange};
-pub use stage::Stage;
+pub use segment::{
+    Anchor, AudioSettings, CropRect, FitMode, Position, Scale, Segment, TextAlign, TextStyle,
+    TimeRange, Transform,
+};
+pub use stage::{ChannelLayout, FrameRate, Stage, StageBackground};
+pub use subtitle::{SubtitleCue, SubtitleFormat, SubtitleMode};
 pub use track::{Track, TrackType};
+pub use transition::Transition;
 
 use crate::error::{CutError, Result};
 use serde::{Deserialize, Serialize};
@@ -91,7 +103,7 @@ impl EditSession {
 
             // Check if all referenced materials exist
             for segment in &track.segments {
-                if self.get_material(&segment.material_id).is_none() {
+                if segment.text.is_none() && self.get_material(&segment.material_id).is_none() {
                     return Err(CutError::invalid_params(format!(
                         "Material '{}' not found",
                         segment.material_id
@@ -112,6 +124,20 @@ impl EditSession {
             .unwrap_or(0)
     }
 
+    /// Copy of the session clipped to a timeline range, rebased so the
+    /// range starts at zero
+    pub fn clip_to_range(&self, range: &TimeRange) -> Self {
+        Self {
+            stage: self.stage.clone(),
+            materials: self.materials.clone(),
+            tracks: self
+                .tracks
+                .iter()
+                .map(|track| track.clip_to_range(range))
+                .collect(),
+        }
+    }
+
     /// Get all video tracks
     pub fn video_tracks(&self) -> Vec<&Track> {
         self.tracks
@@ -120,6 +146,19 @@ impl EditSession {
             .collect()
     }
 
+    /// Get all tracks rendered onto the stage (video, image and text), top track first
+    pub fn visual_tracks(&self) -> Vec<&Track> {
+        self.tracks
+            .iter()
+            .filter(|t| {
+                matches!(
+                    t.track_type,
+                    TrackType::Video | TrackType::Image | TrackType::Text
+                )
+            })
+            .collect()
+    }
+
     /// Get all audio tracks
     pub fn audio_tracks(&self) -> Vec<&Track> {
         self.tracks
@@ -128,6 +167,14 @@ impl EditSession {
             .collect()
     }
 
+    /// Get all subtitle tracks
+    pub fn subtitle_tracks(&self) -> Vec<&Track> {
+        self.tracks
+            .iter()
+            .filter(|t| t.track_type == TrackType::Subtitle)
+            .collect()
+    }
+
     /// Convert to cut protocol format
     pub fn to_protocol(&self) -> CutProtocol {
         protocol::CutProtocol::from_session(self)
@@ -179,6 +226,8 @@ mod tests {
             fps: Some(30.),
             codec: Some("h264".to_string()),
             bitrate: Some(0),
+            rotation: None,
+            sample_aspect_ratio: None,
         });
 
         let audio_material = Material::Audio(AudioMaterial {
@@ -219,6 +268,8 @@ mod tests {
             fps: Some(30.),
             codec: Some("h264".to_string()),
             bitrate: Some(0),
+            rotation: None,
+            sample_aspect_ratio: None,
         });
 
         session.add_material(material);
@@ -248,10 +299,17 @@ mod tests {
         session.add_track(Track::new("audio1".to_string(), TrackType::Audio));
         session.add_track(Track::new("video2".to_string(), TrackType::Video));
 
+        session.add_track(Track::new("image1".to_string(), TrackType::Image));
+        session.add_track(Track::new("text1".to_string(), TrackType::Text));
+
         let video_tracks = session.video_tracks();
         let audio_tracks = session.audio_tracks();
+        let visual_tracks = session.visual_tracks();
 
         assert_eq!(video_tracks.len(), 2);
         assert_eq!(audio_tracks.len(), 1);
+        assert_eq!(visual_tracks.len(), 4);
+        assert_eq!(visual_tracks[2].id, "image1");
+        assert_eq!(visual_tracks[3].id, "text1");
     }
 }
@@ -2,10 +2,15 @@
 
 use crate::cut::{
     EditSession,
+    keyframe::{Easing, Keyframe, Keyframes},
     material::{AudioMaterial, Dimension, ImageMaterial, Material, VideoMaterial},
-    segment::{Position, Segment, SegmentType, TimeRange},
-    stage::Stage,
-    track::{Track, TrackType},
+    segment::{
+        Anchor, AudioSettings, CropRect, FitMode, Position, Segment, SegmentType, TextAlign,
+        TextStyle, TimeRange, Transform,
+    },
+    stage::{ChannelLayout, FrameRate, Stage, StageBackground},
+    track::{self, Track, TrackType},
+    transition::{self, Transition},
 };
 use crate::error::{CutError, Result};
 use serde::{Deserialize, Serialize};
@@ -29,6 +34,54 @@ pub struct StageConfig {
     pub width: i32,
     /// Stage height in pixels
     pub height: i32,
+    /// Output frame rate, 30 fps if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fps: Option<FrameRateProto>,
+    /// Background, black if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background: Option<StageBackgroundProto>,
+    /// Output audio sample rate in Hz, 48000 if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_rate: Option<i32>,
+    /// Output audio channel layout, stereo if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel_layout: Option<ChannelLayoutProto>,
+    /// Output pixel format, yuv420p if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pix_fmt: Option<String>,
+}
+
+/// Frame rate in protocol format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrameRateProto {
+    /// Numerator
+    pub num: u32,
+    /// Denominator
+    pub den: u32,
+}
+
+/// Stage background in protocol format
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StageBackgroundProto {
+    /// Solid colour
+    Color(String),
+    /// Image file scaled to the stage
+    Image(String),
+}
+
+/// Audio channel layout in protocol format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChannelLayoutProto {
+    /// One channel
+    #[serde(rename = "mono")]
+    Mono,
+    /// Two channels
+    #[serde(rename = "stereo")]
+    Stereo,
+    /// Six channels
+    #[serde(rename = "5.1")]
+    Surround51,
 }
 
 /// Materials container
@@ -64,6 +117,12 @@ pub struct VideoMaterialProto {
     pub codec: Option<String>,
     /// Bitrate in kbps (optional)
     pub bitrate: Option<u32>,
+    /// Clockwise rotation in degrees that turns the frames upright (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<i32>,
+    /// Sample aspect ratio of non-square pixels (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_aspect_ratio: Option<f64>,
 }
 
 /// Image material in protocol format
@@ -121,6 +180,32 @@ pub struct ProtocolTrack {
     pub track_type: String,
     /// Track segments
     pub segments: Vec<ProtocolSegment>,
+    /// Track is enabled
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Track is muted (for audio tracks)
+    #[serde(default)]
+    pub muted: bool,
+    /// Track volume (0.0 to 1.0, for audio tracks)
+    #[serde(default = "default_unit")]
+    pub volume: f32,
+    /// Track opacity (0.0 to 1.0, for video tracks)
+    #[serde(default = "default_unit")]
+    pub opacity: f32,
+    /// Track blend mode (for video tracks)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blend_mode: Option<String>,
+    /// Transitions between neighbouring segments
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transitions: Vec<TransitionProto>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_unit() -> f32 {
+    1.0
 }
 
 /// Segment in protocol format
@@ -131,7 +216,8 @@ pub struct ProtocolSegment {
     /// Segment type
     #[serde(rename = "type")]
     pub segment_type: String,
-    /// Referenced material ID
+    /// Referenced material ID (may be empty for text segments)
+    #[serde(default)]
     pub material_id: String,
     /// Target time range on timeline
     pub target_timerange: TimeRangeProto,
@@ -141,6 +227,38 @@ pub struct ProtocolSegment {
     pub scale: Option<ScaleProto>,
     /// Position on stage (optional)
     pub position: Option<PositionProto>,
+    /// How the frame is fitted into its box (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fit: Option<FitModeProto>,
+    /// Crop of the material frame (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crop: Option<CropRectProto>,
+    /// Rotation, flips and anchor of the frame (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transform: Option<TransformProto>,
+    /// Gain, fades, mute and pan of the sound (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio: Option<AudioSettingsProto>,
+    /// Text payload for text segments (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<TextProto>,
+    /// Property keyframes (optional)
+    #[serde(default, skip_serializing_if = "KeyframesProto::is_empty")]
+    pub keyframes: KeyframesProto,
+}
+
+/// Transition in protocol format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransitionProto {
+    /// Outgoing segment ID
+    pub from: String,
+    /// Incoming segment ID
+    pub to: String,
+    /// Transition type
+    #[serde(rename = "type")]
+    pub transition_type: String,
+    /// Duration in milliseconds
+    pub duration: u32,
 }
 
 /// Time range in protocol format
@@ -170,6 +288,173 @@ pub struct PositionProto {
     pub y: i32,
 }
 
+/// Fit mode in protocol format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FitModeProto {
+    /// Fit inside the box, padding the rest
+    Contain,
+    /// Fill the box, cropping the overflow
+    Cover,
+    /// Scale to the box size
+    Stretch,
+    /// Keep the frame size
+    Crop,
+}
+
+/// Crop rectangle in protocol format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CropRectProto {
+    /// Left edge in pixels
+    pub x: i32,
+    /// Top edge in pixels
+    pub y: i32,
+    /// Width in pixels
+    pub width: i32,
+    /// Height in pixels
+    pub height: i32,
+}
+
+/// Anchor in protocol format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnchorProto {
+    /// Horizontal fraction of the frame width
+    pub x: f64,
+    /// Vertical fraction of the frame height
+    pub y: f64,
+}
+
+/// Transform in protocol format
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TransformProto {
+    /// Clockwise rotation in degrees
+    pub rotation: f64,
+    /// Mirror horizontally
+    pub flip_horizontal: bool,
+    /// Mirror vertically
+    pub flip_vertical: bool,
+    /// Rotation pivot, the frame centre if not set
+    pub anchor: AnchorProto,
+}
+
+/// Segment audio controls in protocol format
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettingsProto {
+    /// Gain in dB
+    pub gain: f64,
+    /// Fade-in duration in milliseconds
+    pub fade_in: u32,
+    /// Fade-out duration in milliseconds
+    pub fade_out: u32,
+    /// Silence the segment
+    pub muted: bool,
+    /// Stereo pan, from -1 (left) to 1 (right)
+    pub pan: f64,
+}
+
+/// Text alignment in protocol format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextAlignProto {
+    /// Align to the left edge
+    #[default]
+    Left,
+    /// Center horizontally
+    Center,
+    /// Align to the right edge
+    Right,
+}
+
+/// Text payload in protocol format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextProto {
+    /// Text content
+    pub content: String,
+    /// Font family name or font file path (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font: Option<String>,
+    /// Font size in pixels
+    #[serde(default = "default_font_size")]
+    pub font_size: u32,
+    /// Fill color
+    #[serde(default = "default_text_color")]
+    pub color: String,
+    /// Stroke color (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stroke_color: Option<String>,
+    /// Stroke width in pixels
+    #[serde(default)]
+    pub stroke_width: u32,
+    /// Horizontal alignment
+    #[serde(default)]
+    pub align: TextAlignProto,
+}
+
+/// Keyframe interpolation in protocol format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EasingProto {
+    /// Constant speed
+    #[default]
+    Linear,
+    /// Start slow, end fast
+    EaseIn,
+    /// Start fast, end slow
+    EaseOut,
+    /// Start and end slow
+    EaseInOut,
+    /// Keep the value until the next keyframe
+    Hold,
+}
+
+/// Keyframe in protocol format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyframeProto {
+    /// Time in milliseconds, relative to the segment start on the timeline
+    pub time: u32,
+    /// Property value
+    pub value: f64,
+    /// Interpolation towards the next keyframe
+    #[serde(default)]
+    pub easing: EasingProto,
+}
+
+/// Property keyframes in protocol format
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KeyframesProto {
+    /// X position in pixels
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub x: Vec<KeyframeProto>,
+    /// Y position in pixels
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub y: Vec<KeyframeProto>,
+    /// Width in pixels
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub width: Vec<KeyframeProto>,
+    /// Height in pixels
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub height: Vec<KeyframeProto>,
+    /// Opacity (0.0 to 1.0)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub opacity: Vec<KeyframeProto>,
+    /// Clockwise rotation in degrees
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rotation: Vec<KeyframeProto>,
+    /// Volume multiplier (1.0 is unchanged)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub volume: Vec<KeyframeProto>,
+}
+
+fn default_font_size() -> u32 {
+    48
+}
+
+fn default_text_color() -> String {
+    "white".to_string()
+}
+
 /// Export configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExportConfig {
@@ -180,6 +465,10 @@ pub struct ExportConfig {
     pub output_file: String,
     /// Additional export options
     pub options: Option<HashMap<String, serde_json::Value>>,
+    /// Timeline range to export, the whole timeline if not set; becomes
+    /// `ExportOptions::range`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<TimeRangeProto>,
 }
 
 /// Export type enumeration
@@ -192,6 +481,281 @@ pub enum ExportType {
     Audio,
 }
 
+impl From<FrameRate> for FrameRateProto {
+    fn from(fps: FrameRate) -> Self {
+        Self {
+            num: fps.num,
+            den: fps.den,
+        }
+    }
+}
+
+impl From<FrameRateProto> for FrameRate {
+    fn from(fps: FrameRateProto) -> Self {
+        FrameRate::new(fps.num, fps.den)
+    }
+}
+
+impl From<&StageBackground> for StageBackgroundProto {
+    fn from(background: &StageBackground) -> Self {
+        match background {
+            StageBackground::Color(color) => StageBackgroundProto::Color(color.clone()),
+            StageBackground::Image(path) => StageBackgroundProto::Image(path.clone()),
+        }
+    }
+}
+
+impl From<&StageBackgroundProto> for StageBackground {
+    fn from(background: &StageBackgroundProto) -> Self {
+        match background {
+            StageBackgroundProto::Color(color) => StageBackground::Color(color.clone()),
+            StageBackgroundProto::Image(path) => StageBackground::Image(path.clone()),
+        }
+    }
+}
+
+impl From<ChannelLayout> for ChannelLayoutProto {
+    fn from(layout: ChannelLayout) -> Self {
+        match layout {
+            ChannelLayout::Mono => ChannelLayoutProto::Mono,
+            ChannelLayout::Stereo => ChannelLayoutProto::Stereo,
+            ChannelLayout::Surround51 => ChannelLayoutProto::Surround51,
+        }
+    }
+}
+
+impl From<ChannelLayoutProto> for ChannelLayout {
+    fn from(layout: ChannelLayoutProto) -> Self {
+        match layout {
+            ChannelLayoutProto::Mono => ChannelLayout::Mono,
+            ChannelLayoutProto::Stereo => ChannelLayout::Stereo,
+            ChannelLayoutProto::Surround51 => ChannelLayout::Surround51,
+        }
+    }
+}
+
+impl From<FitMode> for FitModeProto {
+    fn from(mode: FitMode) -> Self {
+        match mode {
+            FitMode::Contain => FitModeProto::Contain,
+            FitMode::Cover => FitModeProto::Cover,
+            FitMode::Stretch => FitModeProto::Stretch,
+            FitMode::Crop => FitModeProto::Crop,
+        }
+    }
+}
+
+impl From<FitModeProto> for FitMode {
+    fn from(mode: FitModeProto) -> Self {
+        match mode {
+            FitModeProto::Contain => FitMode::Contain,
+            FitModeProto::Cover => FitMode::Cover,
+            FitModeProto::Stretch => FitMode::Stretch,
+            FitModeProto::Crop => FitMode::Crop,
+        }
+    }
+}
+
+impl From<TextAlign> for TextAlignProto {
+    fn from(align: TextAlign) -> Self {
+        match align {
+            TextAlign::Left => TextAlignProto::Left,
+            TextAlign::Center => TextAlignProto::Center,
+            TextAlign::Right => TextAlignProto::Right,
+        }
+    }
+}
+
+impl From<TextAlignProto> for TextAlign {
+    fn from(align: TextAlignProto) -> Self {
+        match align {
+            TextAlignProto::Left => TextAlign::Left,
+            TextAlignProto::Center => TextAlign::Center,
+            TextAlignProto::Right => TextAlign::Right,
+        }
+    }
+}
+
+impl From<CropRect> for CropRectProto {
+    fn from(crop: CropRect) -> Self {
+        Self {
+            x: crop.x,
+            y: crop.y,
+            width: crop.width,
+            height: crop.height,
+        }
+    }
+}
+
+impl From<&CropRectProto> for CropRect {
+    fn from(crop: &CropRectProto) -> Self {
+        Self {
+            x: crop.x,
+            y: crop.y,
+            width: crop.width,
+            height: crop.height,
+        }
+    }
+}
+
+impl Default for AnchorProto {
+    fn default() -> Self {
+        Self { x: 0.5, y: 0.5 }
+    }
+}
+
+impl From<Transform> for TransformProto {
+    fn from(transform: Transform) -> Self {
+        Self {
+            rotation: transform.rotation,
+            flip_horizontal: transform.flip_horizontal,
+            flip_vertical: transform.flip_vertical,
+            anchor: AnchorProto {
+                x: transform.anchor.x,
+                y: transform.anchor.y,
+            },
+        }
+    }
+}
+
+impl From<&TransformProto> for Transform {
+    fn from(transform: &TransformProto) -> Self {
+        Transform::rotate(transform.rotation)
+            .flip(transform.flip_horizontal, transform.flip_vertical)
+            .anchor(Anchor::new(transform.anchor.x, transform.anchor.y))
+    }
+}
+
+impl From<AudioSettings> for AudioSettingsProto {
+    fn from(audio: AudioSettings) -> Self {
+        Self {
+            gain: audio.gain,
+            fade_in: audio.fade_in,
+            fade_out: audio.fade_out,
+            muted: audio.muted,
+            pan: audio.pan,
+        }
+    }
+}
+
+impl From<&AudioSettingsProto> for AudioSettings {
+    fn from(audio: &AudioSettingsProto) -> Self {
+        Self {
+            gain: audio.gain,
+            fade_in: audio.fade_in,
+            fade_out: audio.fade_out,
+            muted: audio.muted,
+            pan: audio.pan,
+        }
+    }
+}
+
+impl KeyframesProto {
+    /// Check if no property is animated
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty()
+            && self.y.is_empty()
+            && self.width.is_empty()
+            && self.height.is_empty()
+            && self.opacity.is_empty()
+            && self.rotation.is_empty()
+            && self.volume.is_empty()
+    }
+}
+
+impl From<Easing> for EasingProto {
+    fn from(easing: Easing) -> Self {
+        match easing {
+            Easing::Linear => EasingProto::Linear,
+            Easing::EaseIn => EasingProto::EaseIn,
+            Easing::EaseOut => EasingProto::EaseOut,
+            Easing::EaseInOut => EasingProto::EaseInOut,
+            Easing::Hold => EasingProto::Hold,
+        }
+    }
+}
+
+impl From<EasingProto> for Easing {
+    fn from(easing: EasingProto) -> Self {
+        match easing {
+            EasingProto::Linear => Easing::Linear,
+            EasingProto::EaseIn => Easing::EaseIn,
+            EasingProto::EaseOut => Easing::EaseOut,
+            EasingProto::EaseInOut => Easing::EaseInOut,
+            EasingProto::Hold => Easing::Hold,
+        }
+    }
+}
+
+impl From<&Keyframe> for KeyframeProto {
+    fn from(keyframe: &Keyframe) -> Self {
+        Self {
+            time: keyframe.time,
+            value: keyframe.value,
+            easing: EasingProto::from(keyframe.easing),
+        }
+    }
+}
+
+impl From<&KeyframeProto> for Keyframe {
+    fn from(keyframe: &KeyframeProto) -> Self {
+        Keyframe::new(keyframe.time, keyframe.value).with_easing(Easing::from(keyframe.easing))
+    }
+}
+
+impl From<&Keyframes> for KeyframesProto {
+    fn from(keyframes: &Keyframes) -> Self {
+        let list = |keyframes: &[Keyframe]| keyframes.iter().map(KeyframeProto::from).collect();
+        Self {
+            x: list(&keyframes.x),
+            y: list(&keyframes.y),
+            width: list(&keyframes.width),
+            height: list(&keyframes.height),
+            opacity: list(&keyframes.opacity),
+            rotation: list(&keyframes.rotation),
+            volume: list(&keyframes.volume),
+        }
+    }
+}
+
+impl From<&KeyframesProto> for Keyframes {
+    fn from(keyframes: &KeyframesProto) -> Self {
+        let list = |keyframes: &[KeyframeProto]| keyframes.iter().map(Keyframe::from).collect();
+        Self {
+            x: list(&keyframes.x),
+            y: list(&keyframes.y),
+            width: list(&keyframes.width),
+            height: list(&keyframes.height),
+            opacity: list(&keyframes.opacity),
+            rotation: list(&keyframes.rotation),
+            volume: list(&keyframes.volume),
+        }
+    }
+}
+
+impl StageConfig {
+    /// Convert to a stage, filling unset settings with the defaults
+    pub fn to_stage(&self) -> Stage {
+        let mut stage = Stage::new(self.width, self.height);
+        if let Some(fps) = self.fps {
+            stage.fps = fps.into();
+        }
+        if let Some(ref background) = self.background {
+            stage.background = background.into();
+        }
+        if let Some(sample_rate) = self.sample_rate {
+            stage.sample_rate = sample_rate;
+        }
+        if let Some(channel_layout) = self.channel_layout {
+            stage.channel_layout = channel_layout.into();
+        }
+        if let Some(ref pix_fmt) = self.pix_fmt {
+            stage.pix_fmt = pix_fmt.clone();
+        }
+        stage
+    }
+}
+
 impl CutProtocol {
     /// Create a new empty protocol
     pub fn new(stage_width: i32, stage_height: i32) -> Self {
@@ -199,6 +763,11 @@ impl CutProtocol {
             stage: StageConfig {
                 width: stage_width,
                 height: stage_height,
+                fps: None,
+                background: None,
+                sample_rate: None,
+                channel_layout: None,
+                pix_fmt: None,
             },
             materials: Materials {
                 videos: Vec::new(),
@@ -212,6 +781,11 @@ impl CutProtocol {
     /// Convert from EditSession
     pub fn from_session(session: &EditSession) -> Self {
         let mut protocol = CutProtocol::new(session.stage.width, session.stage.height);
+        protocol.stage.fps = Some(session.stage.fps.into());
+        protocol.stage.background = Some((&session.stage.background).into());
+        protocol.stage.sample_rate = Some(session.stage.sample_rate);
+        protocol.stage.channel_layout = Some(session.stage.channel_layout.into());
+        protocol.stage.pix_fmt = Some(session.stage.pix_fmt.clone());
 
         // Convert materials
         for material in &session.materials {
@@ -236,6 +810,8 @@ impl CutProtocol {
                         fps: video.fps,
                         codec: video.codec.clone(),
                         bitrate: video.bitrate,
+                        rotation: video.rotation,
+                        sample_aspect_ratio: video.sample_aspect_ratio,
                     });
                 }
                 Material::Audio(audio) => {
@@ -285,6 +861,21 @@ impl CutProtocol {
                 id: track.id.clone(),
                 track_type: track.track_type.to_string(),
                 segments: Vec::new(),
+                enabled: track.enabled,
+                muted: track.muted,
+                volume: track.volume,
+                opacity: track.opacity,
+                blend_mode: track.blend_mode.clone(),
+                transitions: track
+                    .transitions
+                    .iter()
+                    .map(|t| TransitionProto {
+                        from: t.from.clone(),
+                        to: t.to.clone(),
+                        transition_type: t.transition_type.clone(),
+                        duration: t.duration,
+                    })
+                    .collect(),
             };
 
             // Convert segments
@@ -306,6 +897,22 @@ impl CutProtocol {
                         height: s.height,
                     }),
                     position: segment.position.map(|p| PositionProto { x: p.x, y: p.y }),
+                    fit: segment.fit.map(FitModeProto::from),
+                    crop: segment.crop.map(CropRectProto::from),
+                    transform: (!segment.transform.is_default())
+                        .then(|| TransformProto::from(segment.transform)),
+                    audio: (!segment.audio.is_default())
+                        .then(|| AudioSettingsProto::from(segment.audio)),
+                    text: segment.text.as_ref().map(|t| TextProto {
+                        content: t.content.clone(),
+                        font: t.font.clone(),
+                        font_size: t.font_size,
+                        color: t.color.clone(),
+                        stroke_color: t.stroke_color.clone(),
+                        stroke_width: t.stroke_width,
+                        align: TextAlignProto::from(t.align),
+                    }),
+                    keyframes: KeyframesProto::from(&segment.keyframes),
                 };
 
                 protocol_track.segments.push(protocol_segment);
@@ -319,7 +926,7 @@ impl CutProtocol {
 
     /// Convert to EditSession
     pub fn to_session(protocol: &CutProtocol) -> Result<EditSession> {
-        let stage = Stage::new(protocol.stage.width, protocol.stage.height);
+        let stage = protocol.stage.to_stage();
         let mut session = EditSession::new(stage);
 
         // Convert materials
@@ -335,6 +942,8 @@ impl CutProtocol {
                 fps: video.fps,
                 codec: video.codec.clone(),
                 bitrate: video.bitrate,
+                rotation: video.rotation,
+                sample_aspect_ratio: video.sample_aspect_ratio,
             });
             session.add_material(material);
         }
@@ -382,6 +991,19 @@ impl CutProtocol {
             };
 
             let mut track = Track::new(&protocol_track.id, track_type);
+            track.enabled = protocol_track.enabled;
+            track.muted = protocol_track.muted;
+            track.volume = protocol_track.volume;
+            track.opacity = protocol_track.opacity;
+            track.blend_mode = protocol_track.blend_mode.clone();
+            for transition in &protocol_track.transitions {
+                track.add_transition(Transition::new(
+                    &transition.from,
+                    &transition.to,
+                    &transition.transition_type,
+                    transition.duration,
+                ));
+            }
 
             // Convert segments
             for protocol_segment in &protocol_track.segments {
@@ -427,6 +1049,28 @@ impl CutProtocol {
                     });
                 }
 
+                if let Some(text) = &protocol_segment.text {
+                    segment.text = Some(TextStyle {
+                        content: text.content.clone(),
+                        font: text.font.clone(),
+                        font_size: text.font_size,
+                        color: text.color.clone(),
+                        stroke_color: text.stroke_color.clone(),
+                        stroke_width: text.stroke_width,
+                        align: TextAlign::from(text.align),
+                    });
+                }
+
+                segment.fit = protocol_segment.fit.map(FitMode::from);
+                segment.crop = protocol_segment.crop.as_ref().map(CropRect::from);
+                if let Some(transform) = &protocol_segment.transform {
+                    segment.transform = Transform::from(transform);
+                }
+                if let Some(audio) = &protocol_segment.audio {
+                    segment.audio = AudioSettings::from(audio);
+                }
+                segment.keyframes = Keyframes::from(&protocol_segment.keyframes);
+
                 track.add_segment(segment);
             }
 
@@ -454,6 +1098,7 @@ impl CutProtocol {
                 "Stage dimensions must be positive",
             ));
         }
+        self.stage.to_stage().validate()?;
 
         // Collect all material IDs for reference checking
         let mut material_ids = std::collections::HashSet::new();
@@ -513,6 +1158,20 @@ impl CutProtocol {
                 )));
             }
 
+            if !(0.0..=1.0).contains(&track.volume) {
+                return Err(CutError::invalid_params(
+                    "Volume must be between 0.0 and 1.0",
+                ));
+            }
+            if !(0.0..=1.0).contains(&track.opacity) {
+                return Err(CutError::invalid_params(
+                    "Opacity must be between 0.0 and 1.0",
+                ));
+            }
+            if let Some(blend_mode) = &track.blend_mode {
+                track::ffmpeg_blend_mode(blend_mode)?;
+            }
+
             // Validate segments
             let mut segment_ids = std::collections::HashSet::new();
             for segment in &track.segments {
@@ -526,8 +1185,8 @@ impl CutProtocol {
                     )));
                 }
 
-                // Check if referenced material exists
-                if !material_ids.contains(&segment.material_id) {
+                // Check if referenced material exists, text segments carry their own content
+                if segment.text.is_none() && !material_ids.contains(&segment.material_id) {
                     return Err(CutError::invalid_params(format!(
                         "Referenced material '{}' not found",
                         segment.material_id
@@ -550,6 +1209,40 @@ impl CutProtocol {
                         ));
                     }
                 }
+
+                if let Some(crop) = &segment.crop {
+                    CropRect::from(crop).validate()?;
+                }
+                if let Some(transform) = &segment.transform {
+                    Transform::from(transform).validate()?;
+                }
+                if let Some(audio) = &segment.audio {
+                    AudioSettings::from(audio).validate()?;
+                }
+
+                // Validate text payload if present
+                if let Some(text) = &segment.text {
+                    if text.content.is_empty() {
+                        return Err(CutError::invalid_params("Text content cannot be empty"));
+                    }
+                    if text.font_size == 0 {
+                        return Err(CutError::invalid_params("Font size must be positive"));
+                    }
+                }
+
+                Keyframes::from(&segment.keyframes).validate()?;
+            }
+
+            // Validate transitions
+            for transition in &track.transitions {
+                transition::ffmpeg_transition(&transition.transition_type)?;
+                if !segment_ids.contains(&transition.from) || !segment_ids.contains(&transition.to)
+                {
+                    return Err(CutError::invalid_params(format!(
+                        "Transition references unknown segment: {} -> {}",
+                        transition.from, transition.to
+                    )));
+                }
             }
         }
 
@@ -633,6 +1326,50 @@ mod tests {
 
         assert_eq!(deserialized.stage.width, 1280);
         assert_eq!(deserialized.stage.height, 720);
+        assert!(!json.contains("fps"));
+    }
+
+    #[test]
+    fn test_stage_output_settings() {
+        let stage = Stage::new(1280, 720)
+            .with_fps(FrameRate::ntsc())
+            .with_background(StageBackground::Color("#202020".to_string()))
+            .with_audio(44100, ChannelLayout::Mono)
+            .with_pix_fmt("yuv422p");
+        let session = EditSession::new(stage.clone());
+
+        let json = CutProtocol::from_session(&session).to_json().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            value["stage"]["fps"],
+            serde_json::json!({"num": 30000, "den": 1001})
+        );
+        assert_eq!(
+            value["stage"]["background"],
+            serde_json::json!({"color": "#202020"})
+        );
+        assert_eq!(value["stage"]["channel_layout"], "mono");
+        let protocol = CutProtocol::from_json(&json).unwrap();
+        assert_eq!(
+            protocol.stage.fps,
+            Some(FrameRateProto {
+                num: 30000,
+                den: 1001
+            })
+        );
+        assert_eq!(
+            protocol.stage.background,
+            Some(StageBackgroundProto::Color("#202020".to_string()))
+        );
+        assert_eq!(
+            protocol.stage.channel_layout,
+            Some(ChannelLayoutProto::Mono)
+        );
+        assert_eq!(CutProtocol::to_session(&protocol).unwrap().stage, stage);
+
+        let mut protocol = CutProtocol::new(1280, 720);
+        protocol.stage.sample_rate = Some(0);
+        assert!(protocol.validate().is_err());
     }
 
     #[test]
@@ -653,6 +1390,8 @@ mod tests {
             fps: Some(30.0),
             codec: Some("h264".to_string()),
             bitrate: Some(5000),
+            rotation: None,
+            sample_aspect_ratio: None,
         });
 
         // Add a track with segment
@@ -676,7 +1415,19 @@ mod tests {
                     height: 720,
                 }),
                 position: Some(PositionProto { x: 0, y: 0 }),
+                fit: None,
+                crop: None,
+                transform: None,
+                audio: None,
+                text: None,
+                keyframes: KeyframesProto::default(),
             }],
+            enabled: true,
+            muted: false,
+            volume: 1.0,
+            opacity: 1.0,
+            blend_mode: None,
+            transitions: Vec::new(),
         };
         protocol.add_track(track);
 
@@ -710,6 +1461,284 @@ mod tests {
         assert_eq!(converted_session.tracks.len(), 1);
     }
 
+    #[test]
+    fn test_material_orientation_round_trip() {
+        let mut session = EditSession::new(Stage::new(1920, 1080));
+        session.add_material(Material::Video(
+            VideoMaterial::new("video1", "test.mp4", 1080, 1920)
+                .with_rotation(90)
+                .with_sample_aspect_ratio(0.75),
+        ));
+        session.add_material(Material::Video(VideoMaterial::new(
+            "video2", "b.mp4", 1920, 1080,
+        )));
+
+        let json = CutProtocol::from_session(&session).to_json().unwrap();
+        assert_eq!(json.matches("\"rotation\"").count(), 1);
+        let converted = CutProtocol::to_session(&CutProtocol::from_json(&json).unwrap()).unwrap();
+        let Material::Video(video) = &converted.materials[0] else {
+            panic!("expected a video material");
+        };
+        assert_eq!(video.rotation, Some(90));
+        assert_eq!(video.sample_aspect_ratio, Some(0.75));
+        let Material::Video(video) = &converted.materials[1] else {
+            panic!("expected a video material");
+        };
+        assert_eq!(video.rotation, None);
+        assert_eq!(video.sample_aspect_ratio, None);
+    }
+
+    #[test]
+    fn test_track_properties_round_trip() {
+        let mut session = EditSession::new(Stage::new(1920, 1080));
+        let mut track = Track::video();
+        track.set_opacity(0.5).set_blend_mode("screen");
+        session.add_track(track);
+
+        let json = CutProtocol::from_session(&session).to_json().unwrap();
+        let protocol = CutProtocol::from_json(&json).unwrap();
+        assert!(protocol.validate().is_ok());
+
+        let converted = CutProtocol::to_session(&protocol).unwrap();
+        assert_eq!(converted.tracks[0].opacity, 0.5);
+        assert_eq!(converted.tracks[0].blend_mode, Some("screen".to_string()));
+
+        // Older protocols without track properties fall back to defaults
+        let legacy: ProtocolTrack =
+            serde_json::from_str(r#"{"id":"t1","type":"audio","segments":[]}"#).unwrap();
+        assert!(legacy.enabled);
+        assert_eq!(legacy.volume, 1.0);
+        assert_eq!(legacy.opacity, 1.0);
+
+        let mut invalid = protocol.clone();
+        invalid.tracks[0].blend_mode = Some("hue".to_string());
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_transition_round_trip() {
+        let mut session = EditSession::new(Stage::new(1920, 1080));
+        session.add_material(Material::Video(VideoMaterial::new(
+            "v1", "a.mp4", 1920, 1080,
+        )));
+        let mut track = Track::video();
+        track
+            .add_segment(Segment::new(
+                "s1",
+                SegmentType::Video,
+                "v1",
+                TimeRange::new(0, 2000),
+                TimeRange::new(0, 2000),
+            ))
+            .add_segment(Segment::new(
+                "s2",
+                SegmentType::Video,
+                "v1",
+                TimeRange::new(1000, 2000),
+                TimeRange::new(0, 2000),
+            ))
+            .add_transition(Transition::new("s1", "s2", "wipe", 1000));
+        session.add_track(track);
+
+        let json = CutProtocol::from_session(&session).to_json().unwrap();
+        let protocol = CutProtocol::from_json(&json).unwrap();
+        assert!(protocol.validate().is_ok());
+
+        let converted = CutProtocol::to_session(&protocol).unwrap();
+        assert!(converted.validate().is_ok());
+        assert_eq!(
+            converted.tracks[0].transitions,
+            vec![Transition::new("s1", "s2", "wipe", 1000)]
+        );
+
+        let mut invalid = protocol.clone();
+        invalid.tracks[0].transitions[0].to = "s3".to_string();
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_text_segment_round_trip() {
+        let mut session = EditSession::new(Stage::new(1920, 1080));
+        let mut track = Track::new("text1", TrackType::Text);
+        track.add_segment(
+            Segment::text("t1", "", TimeRange::new(0, 3000), TimeRange::new(0, 3000)).with_text(
+                TextStyle::new("Title")
+                    .with_font("Noto Sans")
+                    .with_stroke("#000000", 3)
+                    .with_align(TextAlign::Center),
+            ),
+        );
+        session.add_track(track);
+
+        let json = CutProtocol::from_session(&session).to_json().unwrap();
+        assert!(json.contains(r#""align": "center""#));
+        let protocol = CutProtocol::from_json(&json).unwrap();
+        assert!(protocol.validate().is_ok());
+
+        let converted = CutProtocol::to_session(&protocol).unwrap();
+        assert!(converted.validate().is_ok());
+        let text = converted.tracks[0].segments[0].text.as_ref().unwrap();
+        assert_eq!(text.content, "Title");
+        assert_eq!(text.font, Some("Noto Sans".to_string()));
+        assert_eq!(text.stroke_width, 3);
+        assert_eq!(text.align, TextAlign::Center);
+
+        // Text payloads only need their content
+        let minimal: TextProto = serde_json::from_str(r#"{"content":"Hi"}"#).unwrap();
+        assert_eq!(minimal.font_size, 48);
+        assert_eq!(minimal.color, "white");
+        assert_eq!(minimal.align, TextAlignProto::Left);
+    }
+
+    #[test]
+    fn test_fit_and_crop_round_trip() {
+        let mut session = EditSession::new(Stage::new(1080, 1920));
+        session.add_material(Material::Video(VideoMaterial::new(
+            "v1", "a.mp4", 1920, 1080,
+        )));
+        let crop = CropRect {
+            x: 240,
+            y: 0,
+            width: 1440,
+            height: 1080,
+        };
+        let mut track = Track::video();
+        track.add_segment(
+            Segment::video("v1", TimeRange::new(0, 2000), TimeRange::new(0, 2000))
+                .fit(FitMode::Cover)
+                .crop(crop),
+        );
+        session.add_track(track);
+
+        let json = CutProtocol::from_session(&session).to_json().unwrap();
+        assert!(json.contains("\"fit\": \"cover\""));
+        let protocol = CutProtocol::from_json(&json).unwrap();
+        assert!(protocol.validate().is_ok());
+        let converted = CutProtocol::to_session(&protocol).unwrap();
+        let segment = &converted.tracks[0].segments[0];
+        assert_eq!(segment.fit, Some(FitMode::Cover));
+        assert_eq!(segment.crop, Some(crop));
+
+        let mut invalid = protocol.clone();
+        invalid.tracks[0].segments[0].crop.as_mut().unwrap().width = 0;
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_transform_round_trip() {
+        let mut session = EditSession::new(Stage::new(1920, 1080));
+        session.add_material(Material::Video(VideoMaterial::new(
+            "v1", "a.mp4", 1920, 1080,
+        )));
+        let transform = Transform::rotate(-30.0)
+            .flip(true, false)
+            .anchor(Anchor::new(0.0, 1.0));
+        let mut track = Track::video();
+        track.add_segment(
+            Segment::video("v1", TimeRange::new(0, 2000), TimeRange::new(0, 2000))
+                .transform(transform),
+        );
+        track.add_segment(Segment::video(
+            "v1",
+            TimeRange::new(2000, 2000),
+            TimeRange::new(0, 2000),
+        ));
+        session.add_track(track);
+
+        let json = CutProtocol::from_session(&session).to_json().unwrap();
+        assert_eq!(json.matches("\"transform\"").count(), 1);
+        assert!(json.contains("\"flip_horizontal\": true"));
+        let protocol = CutProtocol::from_json(&json).unwrap();
+        assert!(protocol.validate().is_ok());
+        let converted = CutProtocol::to_session(&protocol).unwrap();
+        let segments = &converted.tracks[0].segments;
+        assert_eq!(segments[0].transform, transform);
+        assert!(segments[1].transform.is_default());
+
+        let mut invalid = protocol.clone();
+        invalid.tracks[0].segments[0]
+            .transform
+            .as_mut()
+            .unwrap()
+            .rotation = f64::NAN;
+        assert!(invalid.validate().is_err());
+
+        // Unset transform fields keep their defaults
+        let partial: TransformProto = serde_json::from_str(r#"{"rotation":90}"#).unwrap();
+        assert_eq!(Transform::from(&partial), Transform::rotate(90.0));
+    }
+
+    #[test]
+    fn test_audio_settings_round_trip() {
+        let mut session = EditSession::new(Stage::new(1920, 1080));
+        session.add_material(Material::Audio(AudioMaterial::new("a1", "a.wav")));
+        let audio = AudioSettings::gain(3.5).fade(200, 800).pan(0.25);
+        let mut track = Track::audio();
+        track.add_segment(
+            Segment::audio("s1", "a1", TimeRange::new(0, 2000), TimeRange::new(0, 2000))
+                .with_audio(audio),
+        );
+        session.add_track(track);
+
+        let json = CutProtocol::from_session(&session).to_json().unwrap();
+        assert!(json.contains("\"fade_out\": 800"));
+        let protocol = CutProtocol::from_json(&json).unwrap();
+        assert!(protocol.validate().is_ok());
+        let converted = CutProtocol::to_session(&protocol).unwrap();
+        assert_eq!(converted.tracks[0].segments[0].audio, audio);
+
+        let mut invalid = protocol.clone();
+        invalid.tracks[0].segments[0].audio.as_mut().unwrap().pan = 1.5;
+        assert!(invalid.validate().is_err());
+
+        // Unset audio fields keep their defaults
+        let partial: AudioSettingsProto = serde_json::from_str(r#"{"pan":-0.5}"#).unwrap();
+        assert_eq!(
+            AudioSettings::from(&partial),
+            AudioSettings::default().pan(-0.5)
+        );
+    }
+
+    #[test]
+    fn test_keyframes_round_trip() {
+        let mut session = EditSession::new(Stage::new(1920, 1080));
+        session.add_material(Material::Video(VideoMaterial::new(
+            "v1", "a.mp4", 1920, 1080,
+        )));
+        let keyframes = Keyframes {
+            x: vec![
+                Keyframe::new(0, 0.0),
+                Keyframe::new(1000, 200.0).with_easing(Easing::EaseOut),
+            ],
+            opacity: vec![Keyframe::new(0, 0.0), Keyframe::new(500, 1.0)],
+            ..Default::default()
+        };
+        let mut track = Track::video();
+        track.add_segment(
+            Segment::new(
+                "s1",
+                SegmentType::Video,
+                "v1",
+                TimeRange::new(0, 2000),
+                TimeRange::new(0, 2000),
+            )
+            .with_keyframes(keyframes.clone()),
+        );
+        session.add_track(track);
+
+        let json = CutProtocol::from_session(&session).to_json().unwrap();
+        assert!(json.contains("\"ease_out\""));
+        let protocol = CutProtocol::from_json(&json).unwrap();
+        assert!(protocol.validate().is_ok());
+
+        let converted = CutProtocol::to_session(&protocol).unwrap();
+        assert_eq!(converted.tracks[0].segments[0].keyframes, keyframes);
+
+        let mut invalid = protocol.clone();
+        invalid.tracks[0].segments[0].keyframes.opacity[1].value = 2.0;
+        assert!(invalid.validate().is_err());
+    }
+
     #[test]
     fn test_invalid_protocol() {
         let mut protocol = CutProtocol::new(0, 1080); // Invalid width
@@ -730,6 +1759,8 @@ mod tests {
             fps: None,
             codec: None,
             bitrate: None,
+            rotation: None,
+            sample_aspect_ratio: None,
         });
 
         protocol.add_video_material(VideoMaterialProto {
@@ -744,6 +1775,8 @@ mod tests {
             fps: None,
             codec: None,
             bitrate: None,
+            rotation: None,
+            sample_aspect_ratio: None,
         });
 
         assert!(protocol.validate().is_err());
@@ -2,7 +2,9 @@
 
 use crate::{
     Dimension,
+    cut::{keyframe::Keyframes, stage::Stage},
     error::{CutError, Result},
+    ffmpeg::util::fix_even_number,
 };
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -34,6 +36,125 @@ pub struct Scale {
     pub height: i32,
 }
 
+/// Horizontal text alignment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextAlign {
+    /// Align to the left edge
+    #[default]
+    Left,
+    /// Center horizontally
+    Center,
+    /// Align to the right edge
+    Right,
+}
+
+/// Text payload of a text segment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextStyle {
+    /// Text content, may contain line breaks
+    pub content: String,
+    /// Font family name or font file path (optional)
+    pub font: Option<String>,
+    /// Font size in pixels
+    pub font_size: u32,
+    /// Fill color, e.g. `white` or `#ffcc00`
+    pub color: String,
+    /// Stroke color (optional)
+    pub stroke_color: Option<String>,
+    /// Stroke width in pixels
+    pub stroke_width: u32,
+    /// Horizontal alignment within the segment box
+    pub align: TextAlign,
+}
+
+/// How a segment frame is fitted into its box: the segment scale, or the
+/// whole stage when no scale is set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FitMode {
+    /// Scale to fit inside the box keeping the aspect ratio, padding the rest
+    Contain,
+    /// Scale to fill the box keeping the aspect ratio, cropping the overflow
+    Cover,
+    /// Scale to the box size, ignoring the aspect ratio
+    Stretch,
+    /// Keep the frame size, cropping or padding it to the box
+    Crop,
+}
+
+/// Rectangle in material pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CropRect {
+    /// Left edge
+    pub x: i32,
+    /// Top edge
+    pub y: i32,
+    /// Width in pixels
+    pub width: i32,
+    /// Height in pixels
+    pub height: i32,
+}
+
+/// Resolved steps bringing a material frame into a segment box, applied in
+/// field order; sizes are kept even
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameFit {
+    /// Crop of the material frame
+    pub crop: Option<CropRect>,
+    /// Size the frame is scaled to
+    pub scale: Option<Dimension>,
+    /// Centred crop of the scaled frame
+    pub trim: Option<CropRect>,
+    /// Size the frame is padded to, centred
+    pub pad: Option<Dimension>,
+    /// Resulting frame size
+    pub size: Dimension,
+}
+
+/// Point within a segment frame, as fractions of its width and height
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Anchor {
+    /// Horizontal fraction, 0 is the left edge
+    pub x: f64,
+    /// Vertical fraction, 0 is the top edge
+    pub y: f64,
+}
+
+/// Rotation and flips of a segment frame.
+///
+/// As in the preview renderer, the position is the top-left corner of the
+/// untransformed frame, which turns clockwise around its anchor; flips
+/// mirror the picture in place.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Transform {
+    /// Clockwise rotation in degrees
+    pub rotation: f64,
+    /// Mirror the picture horizontally
+    pub flip_horizontal: bool,
+    /// Mirror the picture vertically
+    pub flip_vertical: bool,
+    /// Rotation pivot, the frame centre by default
+    pub anchor: Anchor,
+}
+
+/// Audio controls of a segment, applied before the track volume
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    /// Gain in dB
+    pub gain: f64,
+    /// Fade-in duration in milliseconds
+    pub fade_in: u32,
+    /// Fade-out duration in milliseconds
+    pub fade_out: u32,
+    /// Silence the segment
+    pub muted: bool,
+    /// Stereo pan, from -1 (left) through 0 (centre) to 1 (right)
+    pub pan: f64,
+}
+
 /// Segment type enumeration
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -68,6 +189,24 @@ pub struct Segment {
     pub scale: Option<Dimension>,
     /// Position on the stage (optional)
     pub position: Option<Position>,
+    /// How the frame is fitted into its box (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fit: Option<FitMode>,
+    /// Crop of the material frame (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crop: Option<CropRect>,
+    /// Rotation, flips and anchor of the frame
+    #[serde(default, skip_serializing_if = "Transform::is_default")]
+    pub transform: Transform,
+    /// Gain, fades, mute and pan of the sound
+    #[serde(default, skip_serializing_if = "AudioSettings::is_default")]
+    pub audio: AudioSettings,
+    /// Text payload for text segments (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<TextStyle>,
+    /// Keyframe animation of segment properties
+    #[serde(default, skip_serializing_if = "Keyframes::is_empty")]
+    pub keyframes: Keyframes,
 }
 
 impl Segment {
@@ -87,6 +226,12 @@ impl Segment {
             source_timerange,
             scale: None,
             position: None,
+            fit: None,
+            crop: None,
+            transform: Transform::default(),
+            audio: AudioSettings::default(),
+            text: None,
+            keyframes: Keyframes::default(),
         }
     }
 
@@ -168,6 +313,42 @@ impl Segment {
         self
     }
 
+    /// Set how the frame is fitted into its box
+    pub fn fit(mut self, mode: FitMode) -> Self {
+        self.fit = Some(mode);
+        self
+    }
+
+    /// Set the crop of the material frame
+    pub fn crop(mut self, rect: CropRect) -> Self {
+        self.crop = Some(rect);
+        self
+    }
+
+    /// Set the rotation, flips and anchor of the frame
+    pub fn transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    /// Set the audio controls
+    pub fn with_audio(mut self, audio: AudioSettings) -> Self {
+        self.audio = audio;
+        self
+    }
+
+    /// Set text payload
+    pub fn with_text(mut self, text: TextStyle) -> Self {
+        self.text = Some(text);
+        self
+    }
+
+    /// Set keyframe animation
+    pub fn with_keyframes(mut self, keyframes: Keyframes) -> Self {
+        self.keyframes = keyframes;
+        self
+    }
+
     /// Get end time on the timeline
     pub fn target_end_time(&self) -> u32 {
         self.target_timerange.start + self.target_timerange.duration
@@ -213,7 +394,8 @@ impl Segment {
             return Err(CutError::invalid_params("Segment ID cannot be empty"));
         }
 
-        if self.material_id.is_empty() {
+        // Text segments carry their own content instead of a material
+        if self.material_id.is_empty() && self.text.is_none() {
             return Err(CutError::invalid_params("Material ID cannot be empty"));
         }
 
@@ -238,6 +420,19 @@ impl Segment {
             }
         }
 
+        if let Some(crop) = self.crop {
+            crop.validate()?;
+        }
+
+        self.transform.validate()?;
+        self.audio.validate()?;
+
+        if let Some(text) = &self.text {
+            text.validate()?;
+        }
+
+        self.keyframes.validate()?;
+
         Ok(())
     }
 
@@ -312,6 +507,7 @@ impl Segment {
 
         let speed = self.playback_speed();
         let source_offset = (offset as f64 * speed) as u32;
+        let (first_keyframes, second_keyframes) = self.keyframes.split_at(offset);
 
         // First segment
         let first = Segment {
@@ -328,6 +524,12 @@ impl Segment {
             },
             scale: self.scale,
             position: self.position,
+            fit: self.fit,
+            crop: self.crop,
+            transform: self.transform,
+            audio: self.audio.trimmed(0, second_duration),
+            text: self.text.clone(),
+            keyframes: first_keyframes,
         };
 
         // Second segment
@@ -345,10 +547,217 @@ impl Segment {
             },
             scale: self.scale,
             position: self.position,
+            fit: self.fit,
+            crop: self.crop,
+            transform: self.transform,
+            audio: self.audio.trimmed(offset, 0),
+            text: self.text.clone(),
+            keyframes: second_keyframes,
         };
 
         Ok((first, second))
     }
+
+    /// Resolve the crop, fit mode and scale for a material frame of `source`
+    /// size on `stage`.
+    ///
+    /// Without a fit mode the frame is cropped and then scaled to the segment
+    /// scale, if any, as is. `None` when the source size is unknown, i.e. not
+    /// positive.
+    pub fn frame_fit(&self, source: Dimension, stage: &Stage) -> Option<FrameFit> {
+        if source.width <= 0 || source.height <= 0 {
+            return None;
+        }
+        let even_down = |n: i32| (n - n % 2).max(2);
+        let mut fit = FrameFit {
+            crop: None,
+            scale: None,
+            trim: None,
+            pad: None,
+            size: source,
+        };
+
+        // Crop: clamp to the material frame
+        if let Some(crop) = self.crop {
+            let x = crop.x.clamp(0, source.width - 1);
+            let y = crop.y.clamp(0, source.height - 1);
+            let rect = CropRect {
+                x,
+                y,
+                width: even_down(crop.width.min(source.width - x)),
+                height: even_down(crop.height.min(source.height - y)),
+            };
+            if (rect.width, rect.height) != (source.width, source.height) {
+                fit.crop = Some(rect);
+                fit.size = Dimension::new(rect.width, rect.height);
+            }
+        }
+
+        let Some(mode) = self.fit else {
+            if let Some(scale) = self.scale
+                && scale != fit.size
+            {
+                fit.scale = Some(scale);
+                fit.size = scale;
+            }
+            return Some(fit);
+        };
+
+        let target = self
+            .scale
+            .unwrap_or(Dimension::new(stage.width, stage.height));
+        let target = Dimension::new(
+            fix_even_number(target.width),
+            fix_even_number(target.height),
+        );
+        let size = fit.size;
+        let scaled = |ratio: f64| {
+            Dimension::new(
+                fix_even_number((size.width as f64 * ratio).round() as i32),
+                fix_even_number((size.height as f64 * ratio).round() as i32),
+            )
+        };
+        let width_ratio = target.width as f64 / size.width as f64;
+        let height_ratio = target.height as f64 / size.height as f64;
+
+        let visible = match mode {
+            FitMode::Stretch => {
+                fit.scale = Some(target);
+                target
+            }
+            FitMode::Contain => {
+                let scale = scaled(width_ratio.min(height_ratio));
+                let scale = Dimension::new(
+                    scale.width.min(target.width),
+                    scale.height.min(target.height),
+                );
+                fit.scale = Some(scale);
+                scale
+            }
+            FitMode::Cover => {
+                let scale = scaled(width_ratio.max(height_ratio));
+                fit.scale = Some(scale);
+                scale
+            }
+            FitMode::Crop => size,
+        };
+        if fit.scale == Some(size) {
+            fit.scale = None;
+        }
+
+        // Overflow past the target box is cropped centred, shortfall is padded centred
+        let kept = Dimension::new(
+            even_down(visible.width.min(target.width)),
+            even_down(visible.height.min(target.height)),
+        );
+        if kept != visible {
+            fit.trim = Some(CropRect {
+                x: (visible.width - kept.width) / 2,
+                y: (visible.height - kept.height) / 2,
+                width: kept.width,
+                height: kept.height,
+            });
+        }
+        if kept != target {
+            fit.pad = Some(target);
+        }
+        fit.size = target;
+        Some(fit)
+    }
+
+    /// Clip the segment to a timeline range, rebased so the range starts at
+    /// zero; `None` if the segment lies outside the range
+    pub fn clip_to(&self, range: &TimeRange) -> Option<Segment> {
+        let visible = self.target_timerange.intersection(range)?;
+        let head = visible.start - self.target_timerange.start;
+        let speed = self.playback_speed();
+
+        let source_start = self.source_timerange.start + (head as f64 * speed) as u32;
+        let source_end = if visible.end() == self.target_end_time() {
+            self.source_end_time()
+        } else {
+            source_start + (visible.duration as f64 * speed) as u32
+        };
+
+        // Keyframes: cut the head off first, then the tail
+        let mut keyframes = self.keyframes.clone();
+        if head > 0 {
+            keyframes = keyframes.split_at(head).1;
+        }
+        if visible.end() < self.target_end_time() {
+            keyframes = keyframes.split_at(visible.duration).0;
+        }
+
+        Some(Segment {
+            target_timerange: TimeRange::new(visible.start - range.start, visible.duration),
+            source_timerange: TimeRange::new(source_start, source_end - source_start),
+            audio: self
+                .audio
+                .trimmed(head, self.target_end_time() - visible.end()),
+            keyframes,
+            ..self.clone()
+        })
+    }
+}
+
+impl TextStyle {
+    /// Create a new text payload with default styling
+    pub fn new<S: Into<String>>(content: S) -> Self {
+        Self {
+            content: content.into(),
+            font: None,
+            font_size: 48,
+            color: "white".to_string(),
+            stroke_color: None,
+            stroke_width: 0,
+            align: TextAlign::Left,
+        }
+    }
+
+    /// Set font family name or font file path
+    pub fn with_font<S: Into<String>>(mut self, font: S) -> Self {
+        self.font = Some(font.into());
+        self
+    }
+
+    /// Set font size in pixels
+    pub fn with_font_size(mut self, font_size: u32) -> Self {
+        self.font_size = font_size;
+        self
+    }
+
+    /// Set fill color
+    pub fn with_color<S: Into<String>>(mut self, color: S) -> Self {
+        self.color = color.into();
+        self
+    }
+
+    /// Set stroke color and width
+    pub fn with_stroke<S: Into<String>>(mut self, color: S, width: u32) -> Self {
+        self.stroke_color = Some(color.into());
+        self.stroke_width = width;
+        self
+    }
+
+    /// Set horizontal alignment
+    pub fn with_align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Validate the text payload
+    pub fn validate(&self) -> Result<()> {
+        if self.content.is_empty() {
+            return Err(CutError::invalid_params("Text content cannot be empty"));
+        }
+        if self.font_size == 0 {
+            return Err(CutError::invalid_params("Font size must be positive"));
+        }
+        if self.color.is_empty() {
+            return Err(CutError::invalid_params("Text color cannot be empty"));
+        }
+        Ok(())
+    }
 }
 
 impl TimeRange {
@@ -474,6 +883,176 @@ impl Default for Position {
     }
 }
 
+impl Anchor {
+    /// Centre of the frame
+    pub const CENTER: Self = Self { x: 0.5, y: 0.5 };
+
+    /// Create a new anchor
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+}
+
+impl Default for Anchor {
+    fn default() -> Self {
+        Self::CENTER
+    }
+}
+
+impl CropRect {
+    /// Validate the rectangle
+    pub fn validate(&self) -> Result<()> {
+        if self.x < 0 || self.y < 0 || self.width <= 0 || self.height <= 0 {
+            return Err(CutError::invalid_params(
+                "Crop must have a positive size inside the material",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Transform {
+    /// Create a transform rotating clockwise by `degrees`
+    pub fn rotate(degrees: f64) -> Self {
+        Self {
+            rotation: degrees,
+            ..Self::default()
+        }
+    }
+
+    /// Mirror horizontally and vertically
+    pub fn flip(mut self, horizontal: bool, vertical: bool) -> Self {
+        self.flip_horizontal = horizontal;
+        self.flip_vertical = vertical;
+        self
+    }
+
+    /// Set the rotation pivot
+    pub fn anchor(mut self, anchor: Anchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// Check if nothing is set
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Check if the frame is rotated
+    pub fn is_rotated(&self) -> bool {
+        self.rotation.rem_euclid(360.0) != 0.0
+    }
+
+    /// Number of clockwise quarter turns, if the rotation is a multiple of 90
+    /// degrees
+    pub fn quarter_turns(&self) -> Option<u32> {
+        let degrees = self.rotation.rem_euclid(360.0);
+        (degrees % 90.0 == 0.0).then_some((degrees / 90.0) as u32 % 4)
+    }
+
+    /// Size of a rotated frame of `size`: quarter turns swap the sides, other
+    /// angles grow to the diagonal so no corner is cut off
+    pub fn rotated_size(&self, size: Dimension) -> Dimension {
+        match self.quarter_turns() {
+            Some(0 | 2) => size,
+            Some(_) => Dimension::new(size.height, size.width),
+            None => {
+                let diagonal =
+                    fix_even_number((size.width as f64).hypot(size.height as f64).ceil() as i32);
+                Dimension::new(diagonal, diagonal)
+            }
+        }
+    }
+
+    /// Offset of the rotated frame's centre from the untransformed centre,
+    /// caused by rotating around the anchor instead of the centre
+    pub fn pivot_offset(&self, size: Dimension) -> (f64, f64) {
+        let dx = (self.anchor.x - 0.5) * size.width as f64;
+        let dy = (self.anchor.y - 0.5) * size.height as f64;
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        (dx * (1.0 - cos) + dy * sin, dy * (1.0 - cos) - dx * sin)
+    }
+
+    /// Top-left corner of the rotated frame, for a frame of `size` whose
+    /// untransformed top-left corner is at `position`
+    pub fn origin(&self, position: Position, size: Dimension) -> Position {
+        if !self.is_rotated() {
+            return position;
+        }
+        let rotated = self.rotated_size(size);
+        let (offset_x, offset_y) = self.pivot_offset(size);
+        let x = position.x as f64 + (size.width - rotated.width) as f64 / 2.0 + offset_x;
+        let y = position.y as f64 + (size.height - rotated.height) as f64 / 2.0 + offset_y;
+        Position::new(x.round() as i32, y.round() as i32)
+    }
+
+    /// Validate the transform
+    pub fn validate(&self) -> Result<()> {
+        if !self.rotation.is_finite() || !self.anchor.x.is_finite() || !self.anchor.y.is_finite() {
+            return Err(CutError::invalid_params(
+                "Rotation and anchor must be finite numbers",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl AudioSettings {
+    /// Create audio controls with a gain in dB
+    pub fn gain(gain: f64) -> Self {
+        Self {
+            gain,
+            ..Self::default()
+        }
+    }
+
+    /// Set the fade-in and fade-out durations in milliseconds
+    pub fn fade(mut self, fade_in: u32, fade_out: u32) -> Self {
+        self.fade_in = fade_in;
+        self.fade_out = fade_out;
+        self
+    }
+
+    /// Set the stereo pan
+    pub fn pan(mut self, pan: f64) -> Self {
+        self.pan = pan;
+        self
+    }
+
+    /// Silence the segment
+    pub fn mute(mut self) -> Self {
+        self.muted = true;
+        self
+    }
+
+    /// Check if nothing is set
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Shorten the fades for a segment cut by `head` milliseconds at the
+    /// start and `tail` at the end, so they still end and start where they
+    /// did on the timeline
+    pub fn trimmed(&self, head: u32, tail: u32) -> Self {
+        Self {
+            fade_in: self.fade_in.saturating_sub(head),
+            fade_out: self.fade_out.saturating_sub(tail),
+            ..*self
+        }
+    }
+
+    /// Validate the audio controls
+    pub fn validate(&self) -> Result<()> {
+        if !self.gain.is_finite() {
+            return Err(CutError::invalid_params("Gain must be a finite number"));
+        }
+        if !(-1.0..=1.0).contains(&self.pan) {
+            return Err(CutError::invalid_params("Pan must be between -1 and 1"));
+        }
+        Ok(())
+    }
+}
+
 impl From<(i32, i32)> for Position {
     fn from(tuple: (i32, i32)) -> Self {
         Self::new(tuple.0, tuple.1)
@@ -543,6 +1122,7 @@ impl std::fmt::Display for Scale {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cut::keyframe::Keyframe;
 
     #[test]
     fn test_time_range() {
@@ -670,6 +1250,24 @@ mod tests {
         assert!(zero_duration.validate().is_err());
     }
 
+    #[test]
+    fn test_text_segment() {
+        let text = TextStyle::new("Hello")
+            .with_font_size(64)
+            .with_stroke("black", 2)
+            .with_align(TextAlign::Center);
+        let segment = Segment::text("t1", "", TimeRange::new(0, 2000), TimeRange::new(0, 2000))
+            .with_text(text.clone());
+        assert!(segment.validate().is_ok());
+        assert_eq!(segment.text, Some(text));
+
+        let no_payload = Segment::text("t2", "", TimeRange::new(0, 2000), TimeRange::new(0, 2000));
+        assert!(no_payload.validate().is_err());
+
+        let empty_text = no_payload.with_text(TextStyle::new(""));
+        assert!(empty_text.validate().is_err());
+    }
+
     #[test]
     fn test_segment_trimming() {
         let mut segment =
@@ -698,6 +1296,220 @@ mod tests {
         assert_eq!(second.target_timerange.duration, 1200);
     }
 
+    #[test]
+    fn test_frame_fit() {
+        let stage = Stage::new(1920, 1080);
+        let source = Dimension::new(1080, 1920);
+        let segment = Segment::video("mat1", TimeRange::new(0, 1000), TimeRange::new(0, 1000));
+
+        // Portrait into landscape: pillarboxed
+        let fit = segment
+            .clone()
+            .fit(FitMode::Contain)
+            .frame_fit(source, &stage)
+            .unwrap();
+        assert_eq!(fit.scale, Some(Dimension::new(608, 1080)));
+        assert_eq!(fit.trim, None);
+        assert_eq!(fit.pad, Some(Dimension::new(1920, 1080)));
+        assert_eq!(fit.size, Dimension::new(1920, 1080));
+
+        // Filled and cropped around the centre
+        let fit = segment
+            .clone()
+            .fit(FitMode::Cover)
+            .frame_fit(source, &stage)
+            .unwrap();
+        assert_eq!(fit.scale, Some(Dimension::new(1920, 3414)));
+        assert_eq!(
+            fit.trim,
+            Some(CropRect {
+                x: 0,
+                y: 1167,
+                width: 1920,
+                height: 1080
+            })
+        );
+        assert_eq!(fit.pad, None);
+
+        let fit = segment
+            .clone()
+            .scale(Dimension::new(641, 361))
+            .fit(FitMode::Stretch)
+            .frame_fit(source, &stage)
+            .unwrap();
+        assert_eq!(fit.scale, Some(Dimension::new(642, 362)));
+        assert_eq!(fit.size, Dimension::new(642, 362));
+
+        // Native size: cropped to the box height, padded to its width
+        let fit = segment
+            .clone()
+            .fit(FitMode::Crop)
+            .frame_fit(source, &stage)
+            .unwrap();
+        assert_eq!(fit.scale, None);
+        assert_eq!(
+            fit.trim,
+            Some(CropRect {
+                x: 0,
+                y: 420,
+                width: 1080,
+                height: 1080
+            })
+        );
+        assert_eq!(fit.pad, Some(Dimension::new(1920, 1080)));
+
+        // The crop is applied first and kept inside the material
+        let crop = CropRect {
+            x: 100,
+            y: 1000,
+            width: 1081,
+            height: 1000,
+        };
+        let fit = segment
+            .clone()
+            .crop(crop)
+            .frame_fit(source, &stage)
+            .unwrap();
+        assert_eq!(
+            fit.crop,
+            Some(CropRect {
+                x: 100,
+                y: 1000,
+                width: 980,
+                height: 920
+            })
+        );
+        assert_eq!(fit.size, Dimension::new(980, 920));
+
+        // Nothing to do without a fit mode or crop
+        let fit = segment.frame_fit(source, &stage).unwrap();
+        assert_eq!(
+            (fit.crop, fit.scale, fit.trim, fit.pad),
+            (None, None, None, None)
+        );
+        assert_eq!(fit.size, source);
+
+        // Materials without a probed size have nothing to fit
+        let unknown = Dimension::new(0, 0);
+        assert!(
+            segment
+                .clone()
+                .crop(crop)
+                .frame_fit(unknown, &stage)
+                .is_none()
+        );
+        assert!(
+            segment
+                .frame_fit(Dimension::new(-1, 1080), &stage)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_transform() {
+        let size = Dimension::new(400, 200);
+        assert_eq!(Transform::rotate(-90.0).quarter_turns(), Some(3));
+        assert_eq!(
+            Transform::rotate(450.0).rotated_size(size),
+            Dimension::new(200, 400)
+        );
+        assert_eq!(Transform::rotate(180.0).rotated_size(size), size);
+        assert_eq!(
+            Transform::rotate(45.0).rotated_size(Dimension::new(300, 400)),
+            Dimension::new(500, 500)
+        );
+
+        // Turning around the centre keeps the centre in place
+        let position = Position::new(100, 100);
+        assert_eq!(
+            Transform::rotate(90.0).origin(position, size),
+            Position::new(200, 0)
+        );
+        assert_eq!(Transform::default().origin(position, size), position);
+
+        // Turning around the top-left corner swings the frame down and left
+        let corner = Transform::rotate(90.0).anchor(Anchor::new(0.0, 0.0));
+        assert_eq!(corner.origin(position, size), Position::new(-100, 100));
+
+        // Flips alone do not move the frame
+        let flipped = Transform::default().flip(true, false);
+        assert_eq!(flipped.origin(position, size), position);
+
+        let segment = Segment::video("mat1", TimeRange::new(0, 1000), TimeRange::new(0, 1000));
+        let json = serde_json::to_value(&segment).unwrap();
+        assert!(json.get("transform").is_none());
+
+        let segment = segment.transform(Transform::rotate(30.0).flip(false, true));
+        let json = serde_json::to_string(&segment).unwrap();
+        let parsed: Segment = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.transform, segment.transform);
+
+        let parsed: Transform = serde_json::from_str(r#"{"rotation":15}"#).unwrap();
+        assert_eq!(parsed.anchor, Anchor::CENTER);
+
+        let invalid = segment.transform(Transform::rotate(f64::NAN));
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_audio_settings() {
+        let segment = Segment::audio(
+            "a1",
+            "mat1",
+            TimeRange::new(0, 4000),
+            TimeRange::new(0, 4000),
+        )
+        .with_audio(AudioSettings::gain(-6.0).fade(1000, 500).pan(0.5));
+        assert!(segment.validate().is_ok());
+
+        // Fades stay where they were on the timeline
+        let (first, second) = segment.split_at(600).unwrap();
+        assert_eq!((first.audio.fade_in, first.audio.fade_out), (1000, 0));
+        assert_eq!((second.audio.fade_in, second.audio.fade_out), (400, 500));
+        assert_eq!(second.audio.gain, -6.0);
+
+        let clipped = segment.clip_to(&TimeRange::new(200, 3600)).unwrap();
+        assert_eq!((clipped.audio.fade_in, clipped.audio.fade_out), (800, 300));
+
+        let json = serde_json::to_string(&segment).unwrap();
+        let parsed: Segment = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.audio, segment.audio);
+        let parsed: AudioSettings = serde_json::from_str(r#"{"muted":true}"#).unwrap();
+        assert_eq!(parsed, AudioSettings::default().mute());
+
+        assert!(AudioSettings::default().pan(1.5).validate().is_err());
+        assert!(AudioSettings::gain(f64::INFINITY).validate().is_err());
+    }
+
+    #[test]
+    fn test_segment_clip_to() {
+        // Played at 2x: 2000ms on the timeline show 4000ms of source
+        let mut segment = Segment::video(
+            "mat1",
+            TimeRange::new(1000, 2000),
+            TimeRange::new(500, 4000),
+        );
+        segment.keyframes.opacity = vec![Keyframe::new(0, 0.0), Keyframe::new(2000, 1.0)];
+
+        let clipped = segment.clip_to(&TimeRange::new(1500, 1000)).unwrap();
+        assert_eq!(clipped.id, segment.id);
+        assert_eq!(clipped.target_timerange, TimeRange::new(0, 1000));
+        assert_eq!(clipped.source_timerange, TimeRange::new(1500, 2000));
+        let opacity: Vec<(u32, f64)> = clipped
+            .keyframes
+            .opacity
+            .iter()
+            .map(|k| (k.time, k.value))
+            .collect();
+        assert_eq!(opacity, vec![(0, 0.25), (1000, 0.75)]);
+
+        let tail = segment.clip_to(&TimeRange::new(2000, 5000)).unwrap();
+        assert_eq!(tail.target_timerange, TimeRange::new(0, 1000));
+        assert_eq!(tail.source_timerange, TimeRange::new(2500, 2000));
+
+        assert!(segment.clip_to(&TimeRange::new(3000, 1000)).is_none());
+    }
+
     #[test]
     fn test_conversions() {
         let pos: (i32, i32) = Position::new(100, 200).into();
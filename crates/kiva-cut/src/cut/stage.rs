@@ -4,18 +4,176 @@ use crate::error::{CutError, Result};
 use serde::{Deserialize, Serialize};
 
 /// Stage represents the canvas/viewport for video composition
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Stage {
     /// Stage width in pixels
     pub width: i32,
     /// Stage height in pixels
     pub height: i32,
+    /// Output frame rate
+    #[serde(default)]
+    pub fps: FrameRate,
+    /// What shows where no segment covers the stage
+    #[serde(default)]
+    pub background: StageBackground,
+    /// Output audio sample rate in Hz
+    #[serde(default = "default_sample_rate")]
+    pub sample_rate: i32,
+    /// Output audio channel layout
+    #[serde(default)]
+    pub channel_layout: ChannelLayout,
+    /// Output pixel format, e.g. `yuv420p` or `yuv444p`
+    #[serde(default = "default_pix_fmt")]
+    pub pix_fmt: String,
+}
+
+/// Frame rate as a fraction, e.g. 30000/1001 for NTSC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrameRate {
+    /// Numerator
+    pub num: u32,
+    /// Denominator
+    pub den: u32,
+}
+
+/// Stage background
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StageBackground {
+    /// Solid colour, e.g. `black` or `#202020`
+    Color(String),
+    /// Image file scaled to the stage
+    Image(String),
+}
+
+/// Audio channel layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ChannelLayout {
+    /// One channel
+    #[serde(rename = "mono")]
+    Mono,
+    /// Two channels
+    #[default]
+    #[serde(rename = "stereo")]
+    Stereo,
+    /// Six channels
+    #[serde(rename = "5.1")]
+    Surround51,
+}
+
+fn default_sample_rate() -> i32 {
+    48000
+}
+
+fn default_pix_fmt() -> String {
+    "yuv420p".to_string()
+}
+
+impl FrameRate {
+    /// Create a frame rate of `num / den` frames per second
+    pub fn new(num: u32, den: u32) -> Self {
+        Self { num, den }
+    }
+
+    /// Create a whole frame rate
+    pub fn fps(fps: u32) -> Self {
+        Self::new(fps, 1)
+    }
+
+    /// 29.97 fps
+    pub fn ntsc() -> Self {
+        Self::new(30000, 1001)
+    }
+
+    /// Get frames per second
+    pub fn as_f64(&self) -> f64 {
+        if self.den == 0 {
+            0.0
+        } else {
+            self.num as f64 / self.den as f64
+        }
+    }
+}
+
+impl Default for FrameRate {
+    fn default() -> Self {
+        Self::fps(30)
+    }
+}
+
+impl std::fmt::Display for FrameRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+impl Default for StageBackground {
+    fn default() -> Self {
+        StageBackground::Color("black".to_string())
+    }
+}
+
+impl ChannelLayout {
+    /// Get the ffmpeg layout name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChannelLayout::Mono => "mono",
+            ChannelLayout::Stereo => "stereo",
+            ChannelLayout::Surround51 => "5.1",
+        }
+    }
+
+    /// Get the number of channels
+    pub fn channels(&self) -> i32 {
+        match self {
+            ChannelLayout::Mono => 1,
+            ChannelLayout::Stereo => 2,
+            ChannelLayout::Surround51 => 6,
+        }
+    }
 }
 
 impl Stage {
     /// Create a new stage with specified dimensions
     pub fn new(width: i32, height: i32) -> Self {
-        Self { width, height }
+        Self {
+            width,
+            height,
+            fps: FrameRate::default(),
+            background: StageBackground::default(),
+            sample_rate: default_sample_rate(),
+            channel_layout: ChannelLayout::default(),
+            pix_fmt: default_pix_fmt(),
+        }
+    }
+
+    /// Set the output frame rate
+    pub fn with_fps(mut self, fps: FrameRate) -> Self {
+        self.fps = fps;
+        self
+    }
+
+    /// Set the background
+    pub fn with_background(mut self, background: StageBackground) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Set the output audio format
+    pub fn with_audio(mut self, sample_rate: i32, channel_layout: ChannelLayout) -> Self {
+        self.sample_rate = sample_rate;
+        self.channel_layout = channel_layout;
+        self
+    }
+
+    /// Set the output pixel format
+    pub fn with_pix_fmt<S: Into<String>>(mut self, pix_fmt: S) -> Self {
+        self.pix_fmt = pix_fmt.into();
+        self
     }
 
     /// Create a stage with 1080p resolution (1920x1080)
@@ -80,14 +238,38 @@ impl Stage {
         if self.height <= 0 {
             return Err(CutError::invalid_params("Stage height must be positive"));
         }
-        Ok(())
+        if self.fps.num == 0 || self.fps.den == 0 {
+            return Err(CutError::invalid_params(
+                "Stage frame rate must be positive",
+            ));
+        }
+        if self.sample_rate <= 0 {
+            return Err(CutError::invalid_params(
+                "Stage sample rate must be positive",
+            ));
+        }
+        if self.pix_fmt.is_empty() {
+            return Err(CutError::invalid_params(
+                "Stage pixel format cannot be empty",
+            ));
+        }
+        match &self.background {
+            StageBackground::Color(color) | StageBackground::Image(color) if color.is_empty() => {
+                Err(CutError::invalid_params("Stage background cannot be empty"))
+            }
+            _ => Ok(()),
+        }
     }
 
     /// Scale the stage by a factor
     pub fn scale(&self, factor: f64) -> Self {
         let new_width = (self.width as f64 * factor).round() as i32;
         let new_height = (self.height as f64 * factor).round() as i32;
-        Self::new(new_width, new_height)
+        Self {
+            width: new_width,
+            height: new_height,
+            ..self.clone()
+        }
     }
 
     /// Resize to fit within maximum dimensions while preserving aspect ratio
@@ -97,7 +279,7 @@ impl Stage {
         let scale_factor = width_ratio.min(height_ratio);
 
         if scale_factor >= 1.0 {
-            *self // No need to scale down
+            self.clone() // No need to scale down
         } else {
             self.scale(scale_factor)
         }
@@ -213,6 +395,47 @@ mod tests {
 
         let negative = Stage::new(-100, -100);
         assert!(negative.validate().is_err());
+
+        let no_fps = Stage::new(1920, 1080).with_fps(FrameRate::new(30, 0));
+        assert!(no_fps.validate().is_err());
+
+        let no_background =
+            Stage::new(1920, 1080).with_background(StageBackground::Image(String::new()));
+        assert!(no_background.validate().is_err());
+
+        let no_pix_fmt = Stage::new(1920, 1080).with_pix_fmt("");
+        assert!(no_pix_fmt.validate().is_err());
+    }
+
+    #[test]
+    fn test_output_format() {
+        let stage = Stage::new(1920, 1080);
+        assert_eq!(stage.fps.to_string(), "30");
+        assert_eq!(
+            stage.background,
+            StageBackground::Color("black".to_string())
+        );
+        assert_eq!(stage.sample_rate, 48000);
+        assert_eq!(stage.channel_layout.as_str(), "stereo");
+        assert_eq!(stage.pix_fmt, "yuv420p");
+
+        assert_eq!(FrameRate::ntsc().to_string(), "30000/1001");
+        assert!((FrameRate::ntsc().as_f64() - 29.97).abs() < 0.001);
+
+        // Settings missing from older JSON fall back to the defaults
+        let stage: Stage = serde_json::from_str(r#"{"width":1280,"height":720}"#).unwrap();
+        assert_eq!(stage, Stage::new(1280, 720));
+
+        let stage = Stage::new(1280, 720)
+            .with_fps(FrameRate::fps(25))
+            .with_background(StageBackground::Image("bg.png".to_string()))
+            .with_audio(44100, ChannelLayout::Surround51)
+            .with_pix_fmt("yuv444p");
+        let json = serde_json::to_string(&stage).unwrap();
+        assert!(json.contains(r#""background":{"image":"bg.png"}"#));
+        assert!(json.contains(r#""pix_fmt":"yuv444p""#));
+        assert!(json.contains(r#""channel_layout":"5.1""#));
+        assert_eq!(serde_json::from_str::<Stage>(&json).unwrap(), stage);
     }
 
     #[test]
@@ -0,0 +1,401 @@
+//! Subtitle parsing and writing for subtitle tracks
+//!
+//! SRT, ASS/SSA and WebVTT files are parsed into cues, which map onto
+//! subtitle segments carrying a text payload.
+
+use crate::cut::{
+    segment::{Segment, SegmentType, TextStyle, TimeRange},
+    track::Track,
+};
+use crate::error::{CutError, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use uuid::Uuid;
+
+/// Subtitle file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubtitleFormat {
+    /// SubRip (.srt)
+    Srt,
+    /// Advanced SubStation Alpha (.ass, .ssa)
+    Ass,
+    /// WebVTT (.vtt)
+    WebVtt,
+}
+
+/// How subtitle tracks end up in a video export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitleMode {
+    /// Burn subtitles into the video frames
+    #[default]
+    Burn,
+    /// Mux subtitles as a separate soft subtitle stream
+    Soft,
+}
+
+/// A single timed subtitle cue
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubtitleCue {
+    /// Time range on the timeline
+    pub timerange: TimeRange,
+    /// Cue text, lines separated by `\n`
+    pub text: String,
+}
+
+impl SubtitleFormat {
+    /// Detect the format from a file extension
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let ext = path
+            .as_ref()
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_default();
+        match ext.as_str() {
+            "srt" => Ok(SubtitleFormat::Srt),
+            "ass" | "ssa" => Ok(SubtitleFormat::Ass),
+            "vtt" => Ok(SubtitleFormat::WebVtt),
+            _ => Err(CutError::unsupported_format(format!(
+                "Unknown subtitle format: {}",
+                path.as_ref().display()
+            ))),
+        }
+    }
+}
+
+impl SubtitleCue {
+    /// Create a new cue
+    pub fn new<S: Into<String>>(start: u32, end: u32, text: S) -> Self {
+        Self {
+            timerange: TimeRange::new(start, end.saturating_sub(start)),
+            text: text.into(),
+        }
+    }
+
+    /// Convert to a subtitle segment
+    pub fn to_segment(&self) -> Segment {
+        Segment::new(
+            Uuid::new_v4(),
+            SegmentType::Subtitle,
+            "",
+            self.timerange,
+            self.timerange,
+        )
+        .with_text(TextStyle::new(self.text.clone()))
+    }
+}
+
+/// Parse subtitle file content
+pub fn parse(content: &str, format: SubtitleFormat) -> Result<Vec<SubtitleCue>> {
+    let content = content.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    match format {
+        SubtitleFormat::Srt | SubtitleFormat::WebVtt => parse_blocks(&content),
+        SubtitleFormat::Ass => parse_ass(&content),
+    }
+}
+
+/// Load a subtitle file into a new subtitle track
+pub fn load_track<P: AsRef<Path>>(path: P) -> Result<Track> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Err(CutError::file_not_found(path.display().to_string()));
+    }
+
+    let format = SubtitleFormat::from_path(path)?;
+    let content = std::fs::read_to_string(path)?;
+    let cues = parse(&content, format)?;
+
+    let mut track = Track::subtitle();
+    track.add_segments(cues.iter().map(|cue| cue.to_segment()).collect());
+    Ok(track)
+}
+
+/// Collect the cues of a subtitle track, ordered by start time
+pub fn track_cues(track: &Track) -> Vec<SubtitleCue> {
+    let mut cues: Vec<SubtitleCue> = track
+        .segments
+        .iter()
+        .filter_map(|segment| {
+            segment.text.as_ref().map(|text| SubtitleCue {
+                timerange: segment.target_timerange,
+                text: text.content.clone(),
+            })
+        })
+        .collect();
+    cues.sort_by_key(|cue| cue.timerange.start);
+    cues
+}
+
+/// Write cues as SRT
+pub fn to_srt(cues: &[SubtitleCue]) -> String {
+    let mut srt = String::new();
+    for (i, cue) in cues.iter().enumerate() {
+        srt.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            format_timestamp(cue.timerange.start),
+            format_timestamp(cue.timerange.end()),
+            cue.text
+        ));
+    }
+    srt
+}
+
+/// Parse SRT and WebVTT cue blocks separated by blank lines
+fn parse_blocks(content: &str) -> Result<Vec<SubtitleCue>> {
+    let mut cues = Vec::new();
+
+    // Headers, NOTE, STYLE and REGION blocks have no timing line
+    for block in content.split("\n\n") {
+        let mut lines = block.lines().skip_while(|line| !line.contains("-->"));
+        let Some(timing) = lines.next() else {
+            continue;
+        };
+
+        let (start, end) = timing
+            .split_once("-->")
+            .ok_or_else(|| CutError::invalid_params(format!("Invalid cue timing: {}", timing)))?;
+        let start = parse_timestamp(start.trim())?;
+        // WebVTT cue settings follow the end timestamp
+        let end = parse_timestamp(end.split_whitespace().next().unwrap_or_default())?;
+
+        let text = lines.collect::<Vec<_>>().join("\n");
+        push_cue(&mut cues, start, end, text)?;
+    }
+
+    Ok(cues)
+}
+
+/// Parse the `[Events]` section of an ASS/SSA file
+fn parse_ass(content: &str) -> Result<Vec<SubtitleCue>> {
+    let mut cues = Vec::new();
+    let mut in_events = false;
+    let mut fields: Vec<String> = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_events = line.eq_ignore_ascii_case("[events]");
+            continue;
+        }
+        if !in_events {
+            continue;
+        }
+
+        if let Some(format) = line.strip_prefix("Format:") {
+            fields = format.split(',').map(|f| f.trim().to_lowercase()).collect();
+        } else if let Some(dialogue) = line.strip_prefix("Dialogue:") {
+            let field_index = |name: &str| {
+                fields
+                    .iter()
+                    .position(|f| f == name)
+                    .ok_or_else(|| CutError::invalid_params(format!("Missing ASS field: {}", name)))
+            };
+            let (start_idx, end_idx) = (field_index("start")?, field_index("end")?);
+            let text_idx = field_index("text")?;
+
+            // Only the last field (text) may contain commas
+            let values: Vec<&str> = dialogue.splitn(fields.len(), ',').collect();
+            if values.len() != fields.len() {
+                return Err(CutError::invalid_params(format!(
+                    "Invalid ASS dialogue: {}",
+                    line
+                )));
+            }
+
+            let start = parse_timestamp(values[start_idx].trim())?;
+            let end = parse_timestamp(values[end_idx].trim())?;
+            push_cue(&mut cues, start, end, strip_ass_text(values[text_idx]))?;
+        }
+    }
+
+    Ok(cues)
+}
+
+/// Drop override blocks and convert ASS line breaks
+fn strip_ass_text(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut in_override = false;
+    for c in text.chars() {
+        match c {
+            '{' => in_override = true,
+            '}' if in_override => in_override = false,
+            _ if !in_override => result.push(c),
+            _ => {}
+        }
+    }
+    result.replace("\\N", "\n").replace("\\n", "\n")
+}
+
+fn push_cue(cues: &mut Vec<SubtitleCue>, start: u32, end: u32, text: String) -> Result<()> {
+    if end < start {
+        return Err(CutError::invalid_params(format!(
+            "Subtitle cue ends before it starts: {}",
+            text
+        )));
+    }
+    // Empty cues cannot become segments
+    if end > start && !text.trim().is_empty() {
+        cues.push(SubtitleCue::new(start, end, text));
+    }
+    Ok(())
+}
+
+/// Parse `HH:MM:SS,mmm`, `HH:MM:SS.mmm`, `MM:SS.mmm` or ASS `H:MM:SS.cc` into milliseconds
+fn parse_timestamp(timestamp: &str) -> Result<u32> {
+    let invalid = || CutError::invalid_params(format!("Invalid timestamp: {}", timestamp));
+
+    let (clock, fraction) = match timestamp.rsplit_once(['.', ',']) {
+        Some((clock, fraction)) => (clock, fraction),
+        None => (timestamp, ""),
+    };
+
+    let mut seconds = 0u32;
+    let parts: Vec<&str> = clock.split(':').collect();
+    if parts.len() < 2 || parts.len() > 3 {
+        return Err(invalid());
+    }
+    for part in parts {
+        let value: u32 = part.parse().map_err(|_| invalid())?;
+        seconds = seconds
+            .checked_mul(60)
+            .and_then(|s| s.checked_add(value))
+            .ok_or_else(invalid)?;
+    }
+
+    // Fractions are decimal: ".5" and ".50" are both half a second
+    let mut millis = 0u32;
+    if !fraction.is_empty() {
+        if !fraction.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let digits: String = fraction.chars().chain("000".chars()).take(3).collect();
+        millis = digits.parse().map_err(|_| invalid())?;
+    }
+
+    seconds
+        .checked_mul(1000)
+        .and_then(|ms| ms.checked_add(millis))
+        .ok_or_else(invalid)
+}
+
+/// Format milliseconds as an SRT timestamp
+fn format_timestamp(ms: u32) -> String {
+    format!(
+        "{:02}:{:02}:{:02},{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_srt() {
+        let srt = "1\r\n00:00:01,000 --> 00:00:02,500\r\nHello\r\nworld\r\n\r\n\
+                   2\r\n00:01:00,250 --> 00:01:01,000\r\n<i>Bye</i>\r\n";
+        let cues = parse(srt, SubtitleFormat::Srt).unwrap();
+
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0], SubtitleCue::new(1000, 2500, "Hello\nworld"));
+        assert_eq!(cues[1].timerange, TimeRange::new(60250, 750));
+        assert_eq!(cues[1].text, "<i>Bye</i>");
+
+        assert!(
+            parse(
+                "1\n00:00:02,000 --> 00:00:01,000\nBad\n",
+                SubtitleFormat::Srt
+            )
+            .is_err()
+        );
+        assert!(
+            parse(
+                "1\n00:00:xx,000 --> 00:00:01,000\nBad\n",
+                SubtitleFormat::Srt
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_out_of_range_timestamp() {
+        // 1194 hours is past what fits in u32 milliseconds
+        assert_eq!(parse_timestamp("1193:02:47,295").unwrap(), u32::MAX);
+        assert!(parse_timestamp("1193:02:47,296").is_err());
+        assert!(parse_timestamp("1194:00:00,000").is_err());
+        assert!(parse_timestamp("4294967295:00:00.000").is_err());
+        assert!(
+            parse(
+                "1\n1194:00:00,000 --> 1194:00:01,000\nLate\n",
+                SubtitleFormat::Srt
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_webvtt() {
+        let vtt = "WEBVTT\n\nNOTE a comment\n\ncue-1\n00:01.000 --> 00:02.000 align:start\nHi\n\n\
+                   01:00:00.000 --> 01:00:01.500\nLater\n";
+        let cues = parse(vtt, SubtitleFormat::WebVtt).unwrap();
+
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0], SubtitleCue::new(1000, 2000, "Hi"));
+        assert_eq!(cues[1], SubtitleCue::new(3_600_000, 3_601_500, "Later"));
+    }
+
+    #[test]
+    fn test_parse_ass() {
+        let ass = "[Script Info]\nTitle: test\n\n[Events]\n\
+                   Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+                   Dialogue: 0,0:00:01.50,0:00:03.00,Default,,0,0,0,,{\\b1}Hello{\\b0}, there\\Nfriend\n\
+                   Comment: 0,0:00:04.00,0:00:05.00,Default,,0,0,0,,ignored\n";
+        let cues = parse(ass, SubtitleFormat::Ass).unwrap();
+
+        assert_eq!(
+            cues,
+            vec![SubtitleCue::new(1500, 3000, "Hello, there\nfriend")]
+        );
+    }
+
+    #[test]
+    fn test_srt_round_trip() {
+        let cues = vec![
+            SubtitleCue::new(3_723_004, 3_725_000, "Two\nlines"),
+            SubtitleCue::new(500, 1500, "First"),
+        ];
+        let mut track = Track::subtitle();
+        track.add_segments(cues.iter().map(|cue| cue.to_segment()).collect());
+        assert!(track.validate().is_ok());
+
+        let srt = to_srt(&track_cues(&track));
+        assert_eq!(
+            srt,
+            "1\n00:00:00,500 --> 00:00:01,500\nFirst\n\n\
+             2\n01:02:03,004 --> 01:02:05,000\nTwo\nlines\n\n"
+        );
+        assert_eq!(parse(&srt, SubtitleFormat::Srt).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            SubtitleFormat::from_path("a.SRT").unwrap(),
+            SubtitleFormat::Srt
+        );
+        assert_eq!(
+            SubtitleFormat::from_path("a.ssa").unwrap(),
+            SubtitleFormat::Ass
+        );
+        assert_eq!(
+            SubtitleFormat::from_path("a.vtt").unwrap(),
+            SubtitleFormat::WebVtt
+        );
+        assert!(SubtitleFormat::from_path("a.txt").is_err());
+    }
+}
@@ -1,6 +1,9 @@
 //! Track management for video editing
 
-use crate::cut::segment::Segment;
+use crate::cut::{
+    segment::{Segment, TimeRange},
+    transition::Transition,
+};
 use crate::error::{CutError, Result};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -21,6 +24,57 @@ pub enum TrackType {
     Subtitle,
 }
 
+/// Blend mode names accepted on tracks, mapped to ffmpeg `blend` filter modes.
+///
+/// Names are matched case-insensitively with `-` and `_` ignored, so both the
+/// CSS style `color-dodge` and ffmpeg's `dodge` are accepted.
+const BLEND_MODES: &[(&str, &str)] = &[
+    ("multiply", "multiply"),
+    ("screen", "screen"),
+    ("overlay", "overlay"),
+    ("darken", "darken"),
+    ("lighten", "lighten"),
+    ("colordodge", "dodge"),
+    ("dodge", "dodge"),
+    ("colorburn", "burn"),
+    ("burn", "burn"),
+    ("hardlight", "hardlight"),
+    ("softlight", "softlight"),
+    ("difference", "difference"),
+    ("exclusion", "exclusion"),
+    ("add", "addition"),
+    ("addition", "addition"),
+    ("subtract", "subtract"),
+    ("divide", "divide"),
+    ("average", "average"),
+    ("negation", "negation"),
+    ("linearlight", "linearlight"),
+    ("vividlight", "vividlight"),
+    ("pinlight", "pinlight"),
+    ("hardmix", "hardmix"),
+];
+
+/// Resolve a blend mode name to the ffmpeg `blend` filter mode.
+///
+/// Returns `Ok(None)` for `normal`, which is a plain overlay.
+pub fn ffmpeg_blend_mode(name: &str) -> Result<Option<&'static str>> {
+    let normalized: String = name
+        .chars()
+        .filter(|c| *c != '-' && *c != '_')
+        .collect::<String>()
+        .to_lowercase();
+
+    if normalized == "normal" {
+        return Ok(None);
+    }
+
+    BLEND_MODES
+        .iter()
+        .find(|(mode, _)| *mode == normalized)
+        .map(|(_, ffmpeg_mode)| Some(*ffmpeg_mode))
+        .ok_or_else(|| CutError::invalid_params(format!("Unsupported blend mode: {}", name)))
+}
+
 /// Track represents a timeline track containing segments
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Track {
@@ -41,6 +95,9 @@ pub struct Track {
     pub opacity: f32,
     /// Track blend mode (for video tracks)
     pub blend_mode: Option<String>,
+    /// Transitions between neighbouring segments
+    #[serde(default)]
+    pub transitions: Vec<Transition>,
 }
 
 impl Track {
@@ -55,6 +112,7 @@ impl Track {
             volume: 1.0,
             opacity: 1.0,
             blend_mode: None,
+            transitions: Vec::new(),
         }
     }
 
@@ -99,10 +157,12 @@ impl Track {
         self
     }
 
-    /// Remove a segment by ID
+    /// Remove a segment by ID, along with its transitions
     pub fn remove_segment(&mut self, segment_id: &str) -> bool {
         if let Some(pos) = self.segments.iter().position(|s| s.id == segment_id) {
             self.segments.remove(pos);
+            self.transitions
+                .retain(|t| t.from != segment_id && t.to != segment_id);
             true
         } else {
             false
@@ -119,6 +179,22 @@ impl Track {
         self.segments.iter_mut().find(|s| s.id == segment_id)
     }
 
+    /// Add a transition between two segments
+    pub fn add_transition(&mut self, transition: Transition) -> &mut Self {
+        self.transitions.push(transition);
+        self
+    }
+
+    /// Get the transition out of a segment
+    pub fn transition_from(&self, segment_id: &str) -> Option<&Transition> {
+        self.transitions.iter().find(|t| t.from == segment_id)
+    }
+
+    /// Get the transition into a segment
+    pub fn transition_to(&self, segment_id: &str) -> Option<&Transition> {
+        self.transitions.iter().find(|t| t.to == segment_id)
+    }
+
     /// Set track enabled state
     pub fn set_enabled(&mut self, enabled: bool) -> &mut Self {
         self.enabled = enabled;
@@ -155,6 +231,14 @@ impl Track {
         self
     }
 
+    /// Get the ffmpeg `blend` mode for this track, `None` for a plain overlay
+    pub fn ffmpeg_blend_mode(&self) -> Result<Option<&'static str>> {
+        match &self.blend_mode {
+            Some(mode) => ffmpeg_blend_mode(mode),
+            None => Ok(None),
+        }
+    }
+
     /// Get track duration (end time of last segment)
     pub fn duration(&self) -> u32 {
         self.segments
@@ -188,7 +272,7 @@ impl Track {
         self.segments.sort_by_key(|s| s.target_timerange.start);
     }
 
-    /// Check if segments overlap
+    /// Check if segments overlap, beyond the overlap transitions require
     pub fn has_overlapping_segments(&self) -> bool {
         if self.segments.len() < 2 {
             return false;
@@ -201,8 +285,14 @@ impl Track {
             let current_end = sorted_segments[i].target_timerange.start
                 + sorted_segments[i].target_timerange.duration;
             let next_start = sorted_segments[i + 1].target_timerange.start;
-
-            if current_end > next_start {
+            let transition_overlap = self
+                .transitions
+                .iter()
+                .find(|t| t.from == sorted_segments[i].id && t.to == sorted_segments[i + 1].id)
+                .map(|t| t.duration)
+                .unwrap_or(0);
+
+            if current_end > next_start + transition_overlap {
                 return true;
             }
         }
@@ -242,6 +332,9 @@ impl Track {
             ));
         }
 
+        // Validate blend mode
+        self.ffmpeg_blend_mode()?;
+
         // Validate all segments
         for (i, segment) in self.segments.iter().enumerate() {
             segment.validate().map_err(|e| {
@@ -249,6 +342,74 @@ impl Track {
             })?;
         }
 
+        // Validate transitions
+        for transition in &self.transitions {
+            self.validate_transition(transition)?;
+        }
+
+        Ok(())
+    }
+
+    /// Check that a transition joins two neighbouring segments which overlap
+    /// by exactly the transition duration
+    fn validate_transition(&self, transition: &Transition) -> Result<()> {
+        transition.ffmpeg_transition()?;
+
+        let find = |id: &str| {
+            self.get_segment(id).ok_or_else(|| {
+                CutError::invalid_params(format!("Transition segment '{}' not found", id))
+            })
+        };
+        let from = find(&transition.from)?;
+        let to = find(&transition.to)?;
+
+        if transition.duration == 0 {
+            return Err(CutError::invalid_params(
+                "Transition duration must be positive",
+            ));
+        }
+        if transition.duration > from.target_timerange.duration
+            || transition.duration > to.target_timerange.duration
+        {
+            return Err(CutError::invalid_params(format!(
+                "Transition from '{}' to '{}' is longer than its segments",
+                transition.from, transition.to
+            )));
+        }
+        if to.target_timerange.start + transition.duration != from.target_end_time() {
+            return Err(CutError::invalid_params(format!(
+                "Segment '{}' must start {}ms before '{}' ends",
+                transition.to, transition.duration, transition.from
+            )));
+        }
+
+        let count = |id: &str, outgoing: bool| {
+            self.transitions
+                .iter()
+                .filter(|t| if outgoing { t.from == id } else { t.to == id })
+                .count()
+        };
+        if count(&transition.from, true) > 1 || count(&transition.to, false) > 1 {
+            return Err(CutError::invalid_params(format!(
+                "Segment '{}' or '{}' has more than one transition on the same side",
+                transition.from, transition.to
+            )));
+        }
+
+        // No other segment may start between the two
+        let neighbour = self.segments.iter().any(|s| {
+            s.id != from.id
+                && s.id != to.id
+                && s.target_timerange.start >= from.target_timerange.start
+                && s.target_timerange.start <= to.target_timerange.start
+        });
+        if neighbour {
+            return Err(CutError::invalid_params(format!(
+                "Segments '{}' and '{}' are not neighbours",
+                transition.from, transition.to
+            )));
+        }
+
         Ok(())
     }
 
@@ -297,6 +458,34 @@ impl Track {
             })
             .collect()
     }
+
+    /// Copy of the track clipped to a timeline range and rebased to zero.
+    ///
+    /// Transitions are kept only if their overlap lies inside the range.
+    pub fn clip_to_range(&self, range: &TimeRange) -> Track {
+        let segments: Vec<Segment> = self
+            .segments_in_range(range.start, range.end())
+            .into_iter()
+            .filter_map(|segment| segment.clip_to(range))
+            .collect();
+        let transitions = self
+            .transitions
+            .iter()
+            .filter(|t| {
+                let from = self.get_segment(&t.from);
+                let to = self.get_segment(&t.to);
+                from.is_some_and(|s| s.target_end_time() <= range.end())
+                    && to.is_some_and(|s| s.target_timerange.start >= range.start)
+            })
+            .cloned()
+            .collect();
+
+        Track {
+            segments,
+            transitions,
+            ..self.clone()
+        }
+    }
 }
 
 impl Default for Track {
@@ -335,6 +524,7 @@ mod tests {
     use crate::{
         Dimension,
         cut::segment::{Position, Segment, TimeRange},
+        cut::transition::Transition,
     };
 
     fn create_test_segment(id: &str, start: u32, duration: u32) -> Segment {
@@ -349,6 +539,12 @@ mod tests {
                 height: 1080,
             }),
             position: Some(Position { x: 0, y: 0 }),
+            fit: None,
+            crop: None,
+            transform: Default::default(),
+            audio: Default::default(),
+            text: None,
+            keyframes: Default::default(),
         }
     }
 
@@ -551,6 +747,56 @@ mod tests {
         let mut invalid_opacity_track = Track::new("track1", TrackType::Video);
         invalid_opacity_track.opacity = -0.5;
         assert!(invalid_opacity_track.validate().is_err());
+
+        let mut invalid_blend_track = Track::new("track1", TrackType::Video);
+        invalid_blend_track.set_blend_mode("luminosity");
+        assert!(invalid_blend_track.validate().is_err());
+    }
+
+    #[test]
+    fn test_transitions() {
+        let mut track = Track::new("track1", TrackType::Video);
+        track.add_segment(create_test_segment("seg1", 0, 2000));
+        track.add_segment(create_test_segment("seg2", 1500, 2000));
+        assert!(track.has_overlapping_segments());
+
+        // The overlap is what the transition needs
+        track.add_transition(Transition::crossfade("seg1", "seg2", 500));
+        assert!(track.validate().is_ok());
+        assert!(!track.has_overlapping_segments());
+        assert_eq!(track.transition_from("seg1").unwrap().to, "seg2");
+        assert_eq!(track.transition_to("seg2").unwrap().from, "seg1");
+
+        track.transitions[0].duration = 400;
+        assert!(track.validate().is_err());
+
+        track.transitions[0] = Transition::new("seg1", "seg2", "spin", 500);
+        assert!(track.validate().is_err());
+
+        track.transitions[0] = Transition::crossfade("seg1", "missing", 500);
+        assert!(track.validate().is_err());
+
+        track.transitions[0] = Transition::crossfade("seg1", "seg2", 500);
+        track.add_segment(create_test_segment("seg3", 1000, 100));
+        assert!(track.validate().is_err());
+
+        track.remove_segment("seg2");
+        assert!(track.transitions.is_empty());
+    }
+
+    #[test]
+    fn test_blend_mode_mapping() {
+        assert_eq!(ffmpeg_blend_mode("normal").unwrap(), None);
+        assert_eq!(ffmpeg_blend_mode("multiply").unwrap(), Some("multiply"));
+        assert_eq!(ffmpeg_blend_mode("Color-Dodge").unwrap(), Some("dodge"));
+        assert_eq!(ffmpeg_blend_mode("soft_light").unwrap(), Some("softlight"));
+        assert_eq!(ffmpeg_blend_mode("add").unwrap(), Some("addition"));
+        assert!(ffmpeg_blend_mode("hue").is_err());
+
+        let mut track = Track::video();
+        assert_eq!(track.ffmpeg_blend_mode().unwrap(), None);
+        track.set_blend_mode("screen");
+        assert_eq!(track.ffmpeg_blend_mode().unwrap(), Some("screen"));
     }
 
     #[test]
@@ -574,6 +820,44 @@ mod tests {
         assert_eq!(range_segments3.len(), 0);
     }
 
+    #[test]
+    fn test_clip_to_range() {
+        let mut track = Track::new("track1", TrackType::Video);
+        track.add_segment(create_test_segment("seg1", 0, 1000)); // 0-1000
+        track.add_segment(create_test_segment("seg2", 800, 1000)); // 800-1800
+        track.add_segment(create_test_segment("seg3", 1500, 1000)); // 1500-2500
+        track.add_segment(create_test_segment("seg4", 3000, 1000)); // 3000-4000
+        track.add_transition(Transition::new("seg1", "seg2", "fade", 200));
+        track.add_transition(Transition::new("seg2", "seg3", "fade", 300));
+        assert!(track.validate().is_ok());
+
+        let clipped = track.clip_to_range(&TimeRange::new(500, 1200));
+        let ranges: Vec<(&str, u32, u32, u32)> = clipped
+            .segments
+            .iter()
+            .map(|s| {
+                (
+                    s.id.as_str(),
+                    s.target_timerange.start,
+                    s.target_timerange.duration,
+                    s.source_timerange.start,
+                )
+            })
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                ("seg1", 0, 500, 500),
+                ("seg2", 300, 900, 0),
+                ("seg3", 1000, 200, 0)
+            ]
+        );
+        // The second overlap is cut by the out point
+        assert_eq!(clipped.transitions.len(), 1);
+        assert_eq!(clipped.transitions[0].to, "seg2");
+        assert!(clipped.validate().is_ok());
+    }
+
     #[test]
     fn test_display() {
         let track_type = TrackType::Video;
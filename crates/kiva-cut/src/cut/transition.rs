@@ -0,0 +1,135 @@
+//! Transitions between neighbouring segments on a track
+
+use crate::error::{CutError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Transition names accepted on tracks, mapped to ffmpeg `xfade` transitions.
+///
+/// Besides the ffmpeg names, the generic `crossfade`, `wipe` and `slide` are
+/// accepted as shorthands for their most common direction.
+const XFADE_TRANSITIONS: &[(&str, &str)] = &[
+    ("crossfade", "fade"),
+    ("wipe", "wipeleft"),
+    ("slide", "slideleft"),
+    ("fade", "fade"),
+    ("fadeblack", "fadeblack"),
+    ("fadewhite", "fadewhite"),
+    ("fadegrays", "fadegrays"),
+    ("dissolve", "dissolve"),
+    ("wipeleft", "wipeleft"),
+    ("wiperight", "wiperight"),
+    ("wipeup", "wipeup"),
+    ("wipedown", "wipedown"),
+    ("wipetl", "wipetl"),
+    ("wipetr", "wipetr"),
+    ("wipebl", "wipebl"),
+    ("wipebr", "wipebr"),
+    ("slideleft", "slideleft"),
+    ("slideright", "slideright"),
+    ("slideup", "slideup"),
+    ("slidedown", "slidedown"),
+    ("smoothleft", "smoothleft"),
+    ("smoothright", "smoothright"),
+    ("smoothup", "smoothup"),
+    ("smoothdown", "smoothdown"),
+    ("circlecrop", "circlecrop"),
+    ("rectcrop", "rectcrop"),
+    ("circleopen", "circleopen"),
+    ("circleclose", "circleclose"),
+    ("vertopen", "vertopen"),
+    ("vertclose", "vertclose"),
+    ("horzopen", "horzopen"),
+    ("horzclose", "horzclose"),
+    ("distance", "distance"),
+    ("radial", "radial"),
+    ("pixelize", "pixelize"),
+    ("diagtl", "diagtl"),
+    ("diagtr", "diagtr"),
+    ("diagbl", "diagbl"),
+    ("diagbr", "diagbr"),
+    ("hlslice", "hlslice"),
+    ("hrslice", "hrslice"),
+    ("vuslice", "vuslice"),
+    ("vdslice", "vdslice"),
+    ("hblur", "hblur"),
+    ("squeezeh", "squeezeh"),
+    ("squeezev", "squeezev"),
+    ("zoomin", "zoomin"),
+];
+
+/// Resolve a transition name to the ffmpeg `xfade` transition.
+///
+/// Names are matched case-insensitively with `-` and `_` ignored.
+pub fn ffmpeg_transition(name: &str) -> Result<&'static str> {
+    let normalized: String = name
+        .chars()
+        .filter(|c| *c != '-' && *c != '_')
+        .collect::<String>()
+        .to_lowercase();
+
+    XFADE_TRANSITIONS
+        .iter()
+        .find(|(transition, _)| *transition == normalized)
+        .map(|(_, xfade)| *xfade)
+        .ok_or_else(|| CutError::invalid_params(format!("Unsupported transition: {}", name)))
+}
+
+/// Transition from one segment into the next one on the same track.
+///
+/// The incoming segment starts `duration` ms before the outgoing segment
+/// ends; the two play together while the transition runs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transition {
+    /// Outgoing segment ID
+    pub from: String,
+    /// Incoming segment ID
+    pub to: String,
+    /// Transition type, e.g. `crossfade`, `wipeleft` or `slideup`
+    #[serde(rename = "type")]
+    pub transition_type: String,
+    /// Duration in milliseconds
+    pub duration: u32,
+}
+
+impl Transition {
+    /// Create a new transition
+    pub fn new<S1, S2, S3>(from: S1, to: S2, transition_type: S3, duration: u32) -> Self
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+        S3: Into<String>,
+    {
+        Self {
+            from: from.into(),
+            to: to.into(),
+            transition_type: transition_type.into(),
+            duration,
+        }
+    }
+
+    /// Create a crossfade transition
+    pub fn crossfade<S1: Into<String>, S2: Into<String>>(from: S1, to: S2, duration: u32) -> Self {
+        Self::new(from, to, "crossfade", duration)
+    }
+
+    /// Get the ffmpeg `xfade` transition
+    pub fn ffmpeg_transition(&self) -> Result<&'static str> {
+        ffmpeg_transition(&self.transition_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transition_mapping() {
+        assert_eq!(ffmpeg_transition("crossfade").unwrap(), "fade");
+        assert_eq!(ffmpeg_transition("Wipe").unwrap(), "wipeleft");
+        assert_eq!(ffmpeg_transition("slide-up").unwrap(), "slideup");
+        assert!(ffmpeg_transition("spin").is_err());
+
+        let transition = Transition::crossfade("a", "b", 500);
+        assert_eq!(transition.ffmpeg_transition().unwrap(), "fade");
+    }
+}
@@ -1,5 +1,7 @@
 //! Error handling for the cluv library
 
+use crate::ffmpeg::diagnostics::FFmpegFailure;
+
 /// Result type alias for cluv operations
 pub type Result<T> = std::result::Result<T, CutError>;
 
@@ -10,6 +12,10 @@ pub enum CutError {
     #[error("FFmpeg error: {0}")]
     FFmpeg(String),
 
+    /// FFmpeg run failed, with the parsed cause and log tail
+    #[error("FFmpeg failed: {0}")]
+    FFmpegFailed(Box<FFmpegFailure>),
+
     /// FFprobe execution error
     #[error("FFprobe error: {0}")]
     FFprobe(String),
@@ -42,6 +48,10 @@ pub enum CutError {
     #[error("Regex error: {0}")]
     Regex(#[from] regex::Error),
 
+    /// Operation cancelled through a cancel handle
+    #[error("Operation cancelled")]
+    Cancelled,
+
     /// Custom error with message
     #[error("{0}")]
     Custom(String),
@@ -53,6 +63,14 @@ impl CutError {
         CutError::FFmpeg(msg.into())
     }
 
+    /// Parsed cause of a failed FFmpeg run, if this is one
+    pub fn ffmpeg_failure(&self) -> Option<&FFmpegFailure> {
+        match self {
+            CutError::FFmpegFailed(failure) => Some(failure),
+            _ => None,
+        }
+    }
+
     /// Create a new FFprobe error
     pub fn ffprobe<S: Into<String>>(msg: S) -> Self {
         CutError::FFprobe(msg.into())
@@ -0,0 +1,320 @@
+//! Adaptive bitrate packaging
+//!
+//! One decoded video stream is split into scaled renditions, which are
+//! packaged as HLS variant playlists with a master playlist, or as a DASH
+//! manifest.
+
+use crate::error::{CutError, Result};
+use crate::ffmpeg::{FFmpeg, codec::Format, filter::Filter, output::Output, stream::Stream};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Packaging of an adaptive bitrate ladder
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Packaging {
+    /// HLS variant playlists and a master playlist
+    #[default]
+    Hls,
+    /// DASH manifest
+    Dash,
+}
+
+/// One rung of an adaptive bitrate ladder
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rendition {
+    /// Output height; the width keeps the aspect ratio
+    pub height: i32,
+    /// Video bitrate in kbps
+    pub video_bitrate: i32,
+    /// Audio bitrate in kbps
+    pub audio_bitrate: i32,
+}
+
+/// Adaptive bitrate ladder
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbrLadder {
+    /// Packaging format
+    #[serde(default)]
+    pub packaging: Packaging,
+    /// Renditions, highest quality first
+    pub renditions: Vec<Rendition>,
+    /// Segment duration in seconds
+    #[serde(default = "default_segment_duration")]
+    pub segment_duration: u32,
+}
+
+fn default_segment_duration() -> u32 {
+    6
+}
+
+impl Rendition {
+    /// Create a rendition
+    pub fn new(height: i32, video_bitrate: i32, audio_bitrate: i32) -> Self {
+        Self {
+            height,
+            video_bitrate,
+            audio_bitrate,
+        }
+    }
+}
+
+impl AbrLadder {
+    /// Create an HLS ladder
+    pub fn hls(renditions: Vec<Rendition>) -> Self {
+        Self {
+            packaging: Packaging::Hls,
+            renditions,
+            segment_duration: default_segment_duration(),
+        }
+    }
+
+    /// Create a DASH ladder
+    pub fn dash(renditions: Vec<Rendition>) -> Self {
+        Self {
+            packaging: Packaging::Dash,
+            ..Self::hls(renditions)
+        }
+    }
+
+    /// Set the segment duration in seconds
+    pub fn segment_duration(mut self, seconds: u32) -> Self {
+        self.segment_duration = seconds;
+        self
+    }
+
+    /// Get the container format of the packaging
+    pub fn format(&self) -> Format {
+        match self.packaging {
+            Packaging::Hls => Format::HLS,
+            Packaging::Dash => Format::DASH,
+        }
+    }
+
+    /// Validate the ladder
+    pub fn validate(&self) -> Result<()> {
+        if self.renditions.is_empty() {
+            return Err(CutError::invalid_params(
+                "Bitrate ladder needs at least one rendition",
+            ));
+        }
+        if self
+            .renditions
+            .iter()
+            .any(|r| r.height <= 0 || r.video_bitrate <= 0 || r.audio_bitrate <= 0)
+        {
+            return Err(CutError::invalid_params(
+                "Rendition height and bitrates must be positive",
+            ));
+        }
+        if self.segment_duration == 0 {
+            return Err(CutError::invalid_params(
+                "Segment duration must be positive",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Split `video` into the renditions and package them into `output`.
+    ///
+    /// The output path is the master playlist (HLS) or the manifest (DASH);
+    /// variant playlists and segments are written next to it. Codec
+    /// settings already on `output` apply to every rendition.
+    pub fn apply(
+        &self,
+        ffmpeg: &mut FFmpeg,
+        video: Stream,
+        audio: Option<Stream>,
+        mut output: Output,
+    ) -> Output {
+        let count = self.renditions.len();
+        let videos = if count > 1 {
+            ffmpeg.add_filter_with_outputs(Filter::split(count as i32), [video], count)
+        } else {
+            vec![video]
+        };
+
+        output.video_bitrate = None;
+        output.audio_bitrate = None;
+        output.format = Some(self.format());
+        for (i, (rendition, stream)) in self.renditions.iter().zip(videos).enumerate() {
+            let scaled = ffmpeg.add_filter(Filter::scale(-2, rendition.height), [stream]);
+            output = output.map_stream(scaled).option(
+                format!("b:v:{}", i),
+                format!("{}k", rendition.video_bitrate),
+            );
+        }
+
+        let path = Path::new(&output.path).to_path_buf();
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let stem = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("index")
+            .to_string();
+
+        match self.packaging {
+            Packaging::Hls => {
+                // Every rendition carries audio and a filter output maps only once, so asplit it
+                let audios = match audio {
+                    Some(audio) if count > 1 => {
+                        ffmpeg.add_filter_with_outputs(Filter::asplit(count as i32), [audio], count)
+                    }
+                    Some(audio) => vec![audio],
+                    None => Vec::new(),
+                };
+                let has_audio = !audios.is_empty();
+                for (i, (rendition, stream)) in self.renditions.iter().zip(audios).enumerate() {
+                    output = output.map_stream(stream).option(
+                        format!("b:a:{}", i),
+                        format!("{}k", rendition.audio_bitrate),
+                    );
+                }
+
+                let stream_map: Vec<String> = self
+                    .renditions
+                    .iter()
+                    .enumerate()
+                    .map(|(i, r)| {
+                        if has_audio {
+                            format!("v:{i},a:{i},name:{}p", r.height)
+                        } else {
+                            format!("v:{i},name:{}p", r.height)
+                        }
+                    })
+                    .collect();
+
+                let master = path
+                    .file_name()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or("index.m3u8")
+                    .to_string();
+                output.path = dir
+                    .join(format!("{}_%v.m3u8", stem))
+                    .to_string_lossy()
+                    .into_owned();
+                output.hls_time = Some(self.segment_duration as i32);
+                output.hls_playlist_type = Some("vod".to_string());
+                output.hls_segment_filename = Some(
+                    dir.join(format!("{}_%v_%03d.ts", stem))
+                        .to_string_lossy()
+                        .into_owned(),
+                );
+                output = output
+                    .option("master_pl_name", master)
+                    .option("var_stream_map", stream_map.join(" "));
+            }
+            Packaging::Dash => {
+                // All renditions share one audio stream at the highest audio bitrate
+                let mut adaptation_sets = "id=0,streams=v".to_string();
+                if let Some(audio) = audio {
+                    let bitrate = self
+                        .renditions
+                        .iter()
+                        .map(|r| r.audio_bitrate)
+                        .max()
+                        .unwrap_or_default();
+                    output = output
+                        .map_stream(audio)
+                        .option("b:a:0", format!("{}k", bitrate));
+                    adaptation_sets.push_str(" id=1,streams=a");
+                }
+                output = output
+                    .option("seg_duration", self.segment_duration.to_string())
+                    .option("use_template", "1")
+                    .option("use_timeline", "1")
+                    .option(
+                        "init_seg_name",
+                        format!("{}_init_$RepresentationID$.m4s", stem),
+                    )
+                    .option(
+                        "media_seg_name",
+                        format!("{}_$RepresentationID$_$Number%05d$.m4s", stem),
+                    )
+                    .option("adaptation_sets", adaptation_sets);
+            }
+        }
+
+        // Force keyframes every segment so segment boundaries line up across renditions
+        output.option(
+            "force_key_frames",
+            format!("expr:gte(t,n_forced*{})", self.segment_duration),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffmpeg::input::Input;
+
+    fn ladder_args(ladder: &AbrLadder, path: &str) -> Vec<String> {
+        let mut ffmpeg = FFmpeg::new();
+        let input = ffmpeg.add_input(Input::with_simple("in.mp4"));
+        let output = ladder.apply(
+            &mut ffmpeg,
+            input.v(),
+            Some(input.a()),
+            Output::with_simple(path),
+        );
+        ffmpeg.add_output(output);
+        ffmpeg.build_args()
+    }
+
+    #[test]
+    fn test_hls_ladder() {
+        let ladder = AbrLadder::hls(vec![
+            Rendition::new(720, 2800, 128),
+            Rendition::new(360, 800, 64),
+        ])
+        .segment_duration(4);
+        let args = ladder_args(&ladder, "out/master.m3u8");
+        let line = args.join(" ");
+
+        assert!(line.contains("split=2"));
+        assert!(line.contains("asplit=2"));
+        assert!(line.contains("scale=-2:360"));
+        assert!(line.contains("-b:v:0 2800k"));
+        assert!(line.contains("-b:a:1 64k"));
+        assert!(line.contains("-f hls"));
+        assert!(line.contains("-hls_time 4"));
+        assert!(line.contains("-hls_segment_filename out/master_%v_%03d.ts"));
+        assert!(line.contains("-master_pl_name master.m3u8"));
+        assert!(line.contains("-force_key_frames expr:gte(t,n_forced*4)"));
+        assert!(args.contains(&"v:0,a:0,name:720p v:1,a:1,name:360p".to_string()));
+        assert_eq!(args.last().unwrap(), "out/master_%v.m3u8");
+    }
+
+    #[test]
+    fn test_dash_ladder() {
+        let ladder = AbrLadder::dash(vec![
+            Rendition::new(1080, 5000, 128),
+            Rendition::new(480, 1400, 96),
+        ]);
+        let args = ladder_args(&ladder, "out/stream.mpd");
+        let line = args.join(" ");
+
+        assert!(line.contains("-f dash"));
+        assert!(line.contains("-b:a:0 128k"));
+        assert!(!line.contains("asplit"));
+        assert!(line.contains("-seg_duration 6"));
+        assert!(line.contains("-media_seg_name stream_$RepresentationID$_$Number%05d$.m4s"));
+        assert!(args.contains(&"id=0,streams=v id=1,streams=a".to_string()));
+        assert_eq!(args.last().unwrap(), "out/stream.mpd");
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(AbrLadder::hls(Vec::new()).validate().is_err());
+        let ladder = AbrLadder::dash(vec![Rendition::new(720, 0, 128)]);
+        assert!(ladder.validate().is_err());
+        let ladder = AbrLadder::hls(vec![Rendition::new(720, 2800, 128)]);
+        assert!(ladder.clone().segment_duration(0).validate().is_err());
+        assert!(ladder.validate().is_ok());
+
+        let json = r#"{"packaging":"dash","renditions":[{"height":720,"video_bitrate":2800,"audio_bitrate":128}]}"#;
+        let ladder: AbrLadder = serde_json::from_str(json).unwrap();
+        assert_eq!(ladder.packaging, Packaging::Dash);
+        assert_eq!(ladder.segment_duration, 6);
+    }
+}
@@ -54,6 +54,23 @@ pub enum AudioCodec {
     Custom(String),
 }
 
+/// Subtitle codec types
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubtitleCodec {
+    /// MP4/MOV timed text
+    MovText,
+    /// WebVTT subtitles
+    WebVTT,
+    /// SubRip subtitles
+    SRT,
+    /// Advanced SubStation Alpha subtitles
+    ASS,
+    /// Copy stream without re-encoding
+    Copy,
+    /// Custom codec string
+    Custom(String),
+}
+
 /// Container/format types
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Format {
@@ -81,6 +98,8 @@ pub enum Format {
     PNG,
     /// HLS format
     HLS,
+    /// MPEG-DASH format
+    DASH,
     /// MPEG-TS format
     MPEGTS,
     /// Image2 format (for image sequences)
@@ -146,6 +165,25 @@ impl AudioCodec {
     }
 }
 
+impl SubtitleCodec {
+    /// Get the FFmpeg codec string
+    pub fn as_str(&self) -> &str {
+        match self {
+            SubtitleCodec::MovText => "mov_text",
+            SubtitleCodec::WebVTT => "webvtt",
+            SubtitleCodec::SRT => "srt",
+            SubtitleCodec::ASS => "ass",
+            SubtitleCodec::Copy => "copy",
+            SubtitleCodec::Custom(s) => s,
+        }
+    }
+
+    /// Create a custom subtitle codec
+    pub fn custom<S: Into<String>>(codec: S) -> Self {
+        SubtitleCodec::Custom(codec.into())
+    }
+}
+
 impl Format {
     /// Get the FFmpeg format string
     pub fn as_str(&self) -> &str {
@@ -162,6 +200,7 @@ impl Format {
             Format::JPEG => "image2",
             Format::PNG => "image2",
             Format::HLS => "hls",
+            Format::DASH => "dash",
             Format::MPEGTS => "mpegts",
             Format::IMAGE2 => "image2",
             Format::Custom(s) => s,
@@ -188,6 +227,7 @@ impl Format {
             Format::JPEG => "jpg",
             Format::PNG => "png",
             Format::HLS => "m3u8",
+            Format::DASH => "mpd",
             Format::MPEGTS => "ts",
             Format::IMAGE2 => "jpg",
             Format::Custom(_) => "",
@@ -205,6 +245,7 @@ impl Format {
                 | Format::MOV
                 | Format::FLV
                 | Format::HLS
+                | Format::DASH
                 | Format::MPEGTS
         )
     }
@@ -232,6 +273,12 @@ impl fmt::Display for AudioCodec {
     }
 }
 
+impl fmt::Display for SubtitleCodec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
 impl fmt::Display for Format {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.as_str())
@@ -285,6 +332,25 @@ impl From<String> for AudioCodec {
     }
 }
 
+impl From<&str> for SubtitleCodec {
+    fn from(s: &str) -> Self {
+        match s {
+            "mov_text" | "tx3g" => SubtitleCodec::MovText,
+            "webvtt" | "vtt" => SubtitleCodec::WebVTT,
+            "srt" | "subrip" => SubtitleCodec::SRT,
+            "ass" | "ssa" => SubtitleCodec::ASS,
+            "copy" => SubtitleCodec::Copy,
+            _ => SubtitleCodec::Custom(s.to_string()),
+        }
+    }
+}
+
+impl From<String> for SubtitleCodec {
+    fn from(s: String) -> Self {
+        SubtitleCodec::from(s.as_str())
+    }
+}
+
 impl From<&str> for Format {
     fn from(s: &str) -> Self {
         match s {
@@ -299,7 +365,8 @@ impl From<&str> for Format {
             "wav" => Format::WAV,
             "jpeg" | "jpg" => Format::JPEG,
             "png" => Format::PNG,
-            "hls" => Format::HLS,
+            "hls" | "m3u8" => Format::HLS,
+            "dash" | "mpd" => Format::DASH,
             "mpegts" | "ts" => Format::MPEGTS,
             "image2" => Format::IMAGE2,
             _ => Format::Custom(s.to_string()),
@@ -401,6 +468,13 @@ mod tests {
         assert!(!AudioCodec::Copy.requires_encoding());
     }
 
+    #[test]
+    fn test_subtitle_codec_conversions() {
+        assert_eq!(SubtitleCodec::MovText.as_str(), "mov_text");
+        assert_eq!(SubtitleCodec::from("vtt"), SubtitleCodec::WebVTT);
+        assert_eq!(SubtitleCodec::from("subrip").to_string(), "srt");
+    }
+
     #[test]
     fn test_format_properties() {
         assert!(Format::MP4.is_video());
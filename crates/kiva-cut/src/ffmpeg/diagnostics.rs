@@ -0,0 +1,507 @@
+//! Diagnostics for failed FFmpeg runs
+//!
+//! FFmpeg reports failures as free-form log lines. The parser here maps the
+//! common ones to typed errors, pointing back at the input or filter of the
+//! command that caused them, and keeps the tail of the log for display.
+
+use regex::Regex;
+use std::fmt;
+use std::sync::LazyLock;
+
+/// Number of log lines kept in [`FFmpegFailure::log_tail`]
+pub const LOG_TAIL_LINES: usize = 20;
+
+static UNKNOWN_ENCODER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"Unknown encoder '([^']+)'").expect("valid regex"));
+static ENCODER_NOT_FOUND: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"Encoder \(codec ([\w-]+)\) not found|Encoder '?([\w-]+)'? not found")
+        .expect("valid regex")
+});
+static PARSED_FILTER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[Parsed_(.+)_(\d+) @ [^\]]+\]\s*(.*)").expect("valid regex"));
+static FILTER_ERROR: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"No such filter: '([^']+)'|Error initializing filter '([^']+)'|Error applying option '[^']+' to filter '([^']+)'",
+    )
+    .expect("valid regex")
+});
+static FILTER_LABEL_ERROR: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:Output with label|Invalid stream specifier:?) '?\[?([\w]+)\]?'?")
+        .expect("valid regex")
+});
+static INPUT_CONTEXT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[in#(\d+)").expect("valid regex"));
+static OPENING_INPUT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"Error opening input file (.+?)\.?$").expect("valid regex"));
+static LOG_PREFIX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:\[[^\]]+\]\s*)*(?:Error opening (?:input|output)(?: file)?:?\s*)?")
+        .expect("valid regex")
+});
+static DECODING_STREAM: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)error while decoding stream #(\d+):(\d+)").expect("valid regex")
+});
+static DECODER_STREAM: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\[[vas]ist#(\d+):(\d+)[^\]]*\].*(?i:decod)").expect("valid regex")
+});
+static STATS_TIME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"time=(\d+):(\d+):(\d+(?:\.\d+)?)").expect("valid regex"));
+
+/// Kind of an FFmpeg failure
+#[derive(Debug, Clone, PartialEq)]
+pub enum FFmpegErrorKind {
+    /// The requested encoder is not built into this FFmpeg
+    UnknownEncoder {
+        /// Encoder name
+        encoder: String,
+    },
+    /// A filter rejected its arguments or could not be set up
+    InvalidFilterArgument {
+        /// Filter name, if reported
+        filter: Option<String>,
+        /// Index of the filter in the command's filtergraph
+        index: Option<usize>,
+        /// Output label of the offending filter
+        label: Option<String>,
+        /// FFmpeg's message
+        message: String,
+    },
+    /// An input file does not exist
+    InputNotFound {
+        /// Index of the input in the command
+        input: Option<usize>,
+        /// Path as reported by FFmpeg
+        path: String,
+    },
+    /// A file could not be read or written
+    PermissionDenied {
+        /// Index of the input in the command, none for outputs
+        input: Option<usize>,
+        /// Path as reported by FFmpeg
+        path: String,
+    },
+    /// The output disk is full
+    NoSpaceLeft,
+    /// An input is corrupt or could not be decoded
+    DecodingError {
+        /// Index of the input in the command
+        input: Option<usize>,
+        /// Stream index within the input
+        stream: Option<usize>,
+        /// Output time in seconds where decoding failed, if known
+        timestamp: Option<f64>,
+        /// FFmpeg's message
+        message: String,
+    },
+    /// Any other failure
+    Other {
+        /// Last error line of the log
+        message: String,
+    },
+}
+
+/// Failed FFmpeg run, parsed from its log
+#[derive(Debug, Clone, PartialEq)]
+pub struct FFmpegFailure {
+    /// What went wrong
+    pub kind: FFmpegErrorKind,
+    /// Process exit code
+    pub exit_code: Option<i32>,
+    /// Last lines of the log, for display
+    pub log_tail: String,
+}
+
+/// Command context used to point failures back at inputs and filters
+#[derive(Debug, Clone, Default)]
+pub struct CommandContext {
+    /// Input paths, by input index
+    pub inputs: Vec<String>,
+    /// Filter names and output labels, by filtergraph index
+    pub filters: Vec<(String, String)>,
+}
+
+impl FFmpegFailure {
+    /// Parse the log of a failed run
+    pub fn parse(stderr: &str, exit_code: Option<i32>, context: &CommandContext) -> Self {
+        // Stats lines end in a carriage return and share a line with what follows
+        let lines: Vec<&str> = stderr
+            .split(['\n', '\r'])
+            .map(str::trim_end)
+            .filter(|l| !l.trim().is_empty())
+            .collect();
+        let log_tail = lines[lines.len().saturating_sub(LOG_TAIL_LINES)..].join("\n");
+
+        // Root causes first: a full disk also shows up as muxing and filter errors
+        let kind = no_space_left(&lines)
+            .or_else(|| permission_denied(&lines, context))
+            .or_else(|| input_not_found(&lines, context))
+            .or_else(|| unknown_encoder(&lines))
+            .or_else(|| invalid_filter(&lines, context))
+            .or_else(|| decoding_error(&lines, context))
+            .unwrap_or_else(|| FFmpegErrorKind::Other {
+                message: lines.last().map(|l| l.to_string()).unwrap_or_default(),
+            });
+
+        Self {
+            kind,
+            exit_code,
+            log_tail,
+        }
+    }
+}
+
+fn no_space_left(lines: &[&str]) -> Option<FFmpegErrorKind> {
+    lines
+        .iter()
+        .any(|l| l.contains("No space left on device"))
+        .then_some(FFmpegErrorKind::NoSpaceLeft)
+}
+
+fn permission_denied(lines: &[&str], context: &CommandContext) -> Option<FFmpegErrorKind> {
+    let line = lines.iter().find(|l| l.contains(": Permission denied"))?;
+    let path = reported_path(line, ": Permission denied");
+    let input = input_context(line).or_else(|| input_index(&path, context));
+    Some(FFmpegErrorKind::PermissionDenied { input, path })
+}
+
+fn input_not_found(lines: &[&str], context: &CommandContext) -> Option<FFmpegErrorKind> {
+    for (i, line) in lines.iter().enumerate() {
+        if !line.contains("No such file or directory") {
+            continue;
+        }
+
+        // Newer FFmpeg names the file on a separate line
+        let mut path = reported_path(line, ": No such file or directory");
+        if path.is_empty() {
+            path = lines[i..]
+                .iter()
+                .chain(lines[..i].iter().rev())
+                .find_map(|l| OPENING_INPUT.captures(l))
+                .map(|c| c[1].to_string())
+                .unwrap_or_default();
+        }
+
+        let input = input_context(line).or_else(|| input_index(&path, context));
+        if input.is_some() {
+            return Some(FFmpegErrorKind::InputNotFound { input, path });
+        }
+    }
+    None
+}
+
+fn unknown_encoder(lines: &[&str]) -> Option<FFmpegErrorKind> {
+    lines.iter().find_map(|line| {
+        let encoder = UNKNOWN_ENCODER
+            .captures(line)
+            .map(|c| c[1].to_string())
+            .or_else(|| {
+                let c = ENCODER_NOT_FOUND.captures(line)?;
+                c.get(1).or(c.get(2)).map(|m| m.as_str().to_string())
+            })?;
+        Some(FFmpegErrorKind::UnknownEncoder { encoder })
+    })
+}
+
+fn invalid_filter(lines: &[&str], context: &CommandContext) -> Option<FFmpegErrorKind> {
+    for line in lines {
+        if let Some(c) = PARSED_FILTER.captures(line) {
+            let filter = c[1].to_string();
+            let index: Option<usize> = c[2].parse().ok();
+            let label = index
+                .and_then(|i| context.filters.get(i))
+                .filter(|(name, _)| *name == filter)
+                .map(|(_, label)| label.clone());
+            return Some(FFmpegErrorKind::InvalidFilterArgument {
+                filter: Some(filter),
+                index,
+                label,
+                message: c[3].to_string(),
+            });
+        }
+
+        if let Some(c) = FILTER_ERROR.captures(line) {
+            let filter = c
+                .iter()
+                .skip(1)
+                .flatten()
+                .next()
+                .map(|m| m.as_str().to_string());
+            let index = filter
+                .as_ref()
+                .and_then(|f| context.filters.iter().position(|(name, _)| name == f));
+            return Some(FFmpegErrorKind::InvalidFilterArgument {
+                label: index.map(|i| context.filters[i].1.clone()),
+                filter,
+                index,
+                message: line.to_string(),
+            });
+        }
+
+        if let Some(c) = FILTER_LABEL_ERROR.captures(line) {
+            let label = format!("[{}]", &c[1]);
+            let index = context.filters.iter().position(|(_, l)| *l == label);
+            return Some(FFmpegErrorKind::InvalidFilterArgument {
+                filter: index.map(|i| context.filters[i].0.clone()),
+                index,
+                label: Some(label),
+                message: line.to_string(),
+            });
+        }
+    }
+    None
+}
+
+fn decoding_error(lines: &[&str], context: &CommandContext) -> Option<FFmpegErrorKind> {
+    // Prefer the line naming the stream over the decoder's own messages
+    let position = lines
+        .iter()
+        .position(|l| DECODING_STREAM.is_match(l) || DECODER_STREAM.is_match(l))
+        .or_else(|| {
+            lines.iter().position(|l| {
+                l.contains("Invalid data found when processing input")
+                    || l.contains("moov atom not found")
+                    || l.contains("error while decoding")
+            })
+        })?;
+    let line = lines[position];
+
+    let captures = DECODING_STREAM
+        .captures(line)
+        .or_else(|| DECODER_STREAM.captures(line));
+    let (input, stream) = match captures {
+        Some(c) => (c[1].parse().ok(), c[2].parse().ok()),
+        None => {
+            let path = reported_path(line, ": Invalid data found when processing input");
+            (
+                input_context(line).or_else(|| input_index(&path, context)),
+                None,
+            )
+        }
+    };
+
+    // The last stats line before the error tells how far the output got;
+    // runs pass `-stats` so they are printed at the `error` log level
+    let timestamp = lines[..=position].iter().rev().find_map(|l| {
+        let c = STATS_TIME.captures_iter(l).last()?;
+        let hours: f64 = c[1].parse().ok()?;
+        let minutes: f64 = c[2].parse().ok()?;
+        let seconds: f64 = c[3].parse().ok()?;
+        Some((hours * 60.0 + minutes) * 60.0 + seconds)
+    });
+
+    Some(FFmpegErrorKind::DecodingError {
+        input,
+        stream,
+        timestamp,
+        message: line.to_string(),
+    })
+}
+
+/// Path in front of an error suffix, without log context prefixes
+fn reported_path(line: &str, suffix: &str) -> String {
+    let head = line.split(suffix).next().unwrap_or_default();
+    LOG_PREFIX.replace(head, "").trim().to_string()
+}
+
+/// Input index from an `[in#N ...]` log context
+fn input_context(line: &str) -> Option<usize> {
+    INPUT_CONTEXT.captures(line)?[1].parse().ok()
+}
+
+fn input_index(path: &str, context: &CommandContext) -> Option<usize> {
+    if path.is_empty() {
+        return None;
+    }
+    context.inputs.iter().position(|input| input == path)
+}
+
+impl fmt::Display for FFmpegErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FFmpegErrorKind::UnknownEncoder { encoder } => {
+                write!(f, "Unknown encoder '{}'", encoder)
+            }
+            FFmpegErrorKind::InvalidFilterArgument {
+                filter,
+                index,
+                label,
+                message,
+            } => {
+                write!(f, "Invalid filter argument")?;
+                if let Some(filter) = filter {
+                    write!(f, " in '{}'", filter)?;
+                }
+                if let Some(index) = index {
+                    write!(f, " (filter #{})", index)?;
+                }
+                if let Some(label) = label {
+                    write!(f, " {}", label)?;
+                }
+                write!(f, ": {}", message)
+            }
+            FFmpegErrorKind::InputNotFound { input, path } => match input {
+                Some(input) => write!(f, "Input #{} not found: {}", input, path),
+                None => write!(f, "Input not found: {}", path),
+            },
+            FFmpegErrorKind::PermissionDenied { input, path } => match input {
+                Some(input) => write!(f, "Permission denied for input #{}: {}", input, path),
+                None => write!(f, "Permission denied: {}", path),
+            },
+            FFmpegErrorKind::NoSpaceLeft => write!(f, "No space left on device"),
+            FFmpegErrorKind::DecodingError {
+                input,
+                stream,
+                timestamp,
+                message,
+            } => {
+                write!(f, "Decoding error")?;
+                match (input, stream) {
+                    (Some(input), Some(stream)) => write!(f, " in stream #{}:{}", input, stream)?,
+                    (Some(input), None) => write!(f, " in input #{}", input)?,
+                    _ => {}
+                }
+                if let Some(timestamp) = timestamp {
+                    write!(f, " at {:.2}s", timestamp)?;
+                }
+                write!(f, ": {}", message)
+            }
+            FFmpegErrorKind::Other { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl fmt::Display for FFmpegFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(code) = self.exit_code {
+            write!(f, " (exit code {})", code)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> CommandContext {
+        CommandContext {
+            inputs: vec!["a.mp4".to_string(), "missing.mp4".to_string()],
+            filters: vec![
+                ("color".to_string(), "[c0]".to_string()),
+                ("scale".to_string(), "[s1]".to_string()),
+            ],
+        }
+    }
+
+    fn kind(stderr: &str) -> FFmpegErrorKind {
+        FFmpegFailure::parse(stderr, Some(1), &context()).kind
+    }
+
+    #[test]
+    fn test_input_errors() {
+        assert_eq!(
+            kind("missing.mp4: No such file or directory"),
+            FFmpegErrorKind::InputNotFound {
+                input: Some(1),
+                path: "missing.mp4".to_string()
+            }
+        );
+        assert_eq!(
+            kind(
+                "[in#1 @ 0x6000] Error opening input: No such file or directory\n\
+                 Error opening input file missing.mp4."
+            ),
+            FFmpegErrorKind::InputNotFound {
+                input: Some(1),
+                path: "missing.mp4".to_string()
+            }
+        );
+        assert_eq!(
+            kind("[out#0/mp4 @ 0x1] Error opening output /root/out.mp4: Permission denied"),
+            FFmpegErrorKind::PermissionDenied {
+                input: None,
+                path: "/root/out.mp4".to_string()
+            }
+        );
+        assert_eq!(
+            kind("av_interleaved_write_frame(): No space left on device\nError muxing a packet"),
+            FFmpegErrorKind::NoSpaceLeft
+        );
+    }
+
+    #[test]
+    fn test_encoder_and_filter_errors() {
+        assert_eq!(
+            kind("[vost#0:0 @ 0x1] Unknown encoder 'libx265'"),
+            FFmpegErrorKind::UnknownEncoder {
+                encoder: "libx265".to_string()
+            }
+        );
+        assert_eq!(
+            kind("[Parsed_scale_1 @ 0x7f] Invalid size 'abc'\nError reinitializing filters!"),
+            FFmpegErrorKind::InvalidFilterArgument {
+                filter: Some("scale".to_string()),
+                index: Some(1),
+                label: Some("[s1]".to_string()),
+                message: "Invalid size 'abc'".to_string()
+            }
+        );
+        assert!(matches!(
+            kind("[AVFilterGraph @ 0x1] No such filter: 'blurry'"),
+            FFmpegErrorKind::InvalidFilterArgument { filter: Some(f), index: None, .. } if f == "blurry"
+        ));
+    }
+
+    #[test]
+    fn test_decoding_error_and_log_tail() {
+        let stderr = "frame=  10 fps=0.0 q=28.0 size=0kB time=00:00:01.50 bitrate=0kbits/s\n\
+                      [h264 @ 0x1] error while decoding MB 3 4, bytestream -5\n\
+                      Error while decoding stream #0:0: Invalid data found when processing input";
+        let failure = FFmpegFailure::parse(stderr, Some(69), &context());
+        assert!(matches!(
+            failure.kind,
+            FFmpegErrorKind::DecodingError {
+                input: Some(0),
+                stream: Some(0),
+                timestamp: Some(t),
+                ..
+            } if t == 1.5
+        ));
+        assert!(failure.to_string().ends_with("(exit code 69)"));
+
+        // FFmpeg 7 at `-v error -stats`: stats end in `\r`, decoder errors
+        // name the input stream in their log context
+        let stderr = "frame=   48 fps=0.0 q=28.0 size=       0KiB time=00:00:01.88 \
+                      bitrate=   0.2kbits/s speed=3.75x    \r\
+                      [h264 @ 0x5626f0a3e540] error while decoding MB 41 29, bytestream -12\n\
+                      [h264 @ 0x5626f0a3e540] concealing 1510 DC, 1510 AC, 1510 MV errors in P frame\n\
+                      frame=   97 fps= 96 q=28.0 size=     256KiB time=00:00:03.84 \
+                      bitrate= 545.9kbits/s speed=3.79x    \r\
+                      [vist#1:0/h264 @ 0x5626f0a40d80] [dec:h264 @ 0x5626f0a3e580] \
+                      Decoding error: Invalid data found when processing input\n\
+                      [out#0/mp4 @ 0x5626f0a3f1c0] Error muxing a packet\n\
+                      frame=  100 fps= 95 q=-1.0 Lsize=     260KiB time=00:00:03.96 \
+                      bitrate= 537.6kbits/s speed=3.77x    \n";
+        let failure = FFmpegFailure::parse(stderr, Some(183), &context());
+        assert!(matches!(
+            failure.kind,
+            FFmpegErrorKind::DecodingError {
+                input: Some(1),
+                stream: Some(0),
+                timestamp: Some(t),
+                ..
+            } if t == 3.84
+        ));
+
+        let long_log: String = (0..50).map(|i| format!("line {}\n", i)).collect();
+        let failure = FFmpegFailure::parse(&long_log, None, &context());
+        assert_eq!(failure.log_tail.lines().count(), LOG_TAIL_LINES);
+        assert!(failure.log_tail.ends_with("line 49"));
+        assert_eq!(
+            failure.kind,
+            FFmpegErrorKind::Other {
+                message: "line 49".to_string()
+            }
+        );
+    }
+}
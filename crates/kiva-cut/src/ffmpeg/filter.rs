@@ -10,6 +10,11 @@ impl Label {
     pub fn new(s: String) -> Self {
         Self(s)
     }
+
+    /// Create a label with a random name
+    pub fn random() -> Self {
+        Self(format!("{:x}", rand::random::<u32>()))
+    }
 }
 
 impl fmt::Display for Label {
@@ -49,7 +54,7 @@ impl Filter {
     /// Create a new filter with the given name
     pub fn with_name<S: Into<String>>(name: S) -> Self {
         Self {
-            label: Label::new(format!("{:x}", rand::random::<u32>())),
+            label: Label::random(),
             name: name.into(),
             params: Vec::new(),
             inputs: Vec::new(),
@@ -151,7 +156,7 @@ impl Filter {
                 .iter()
                 .map(|output| output.to_stream().to_string())
                 .collect();
-            result.push_str(&format!("[{}]", output_strings.join("][")));
+            result.push_str(&output_strings.join(""));
         } else if !self.label.to_string().is_empty() {
             result.push_str(&format!("{}", self.label));
         }
@@ -182,11 +187,22 @@ impl fmt::Display for Filter {
 impl Filter {
     /// color filter for creating a solid color video
     pub fn color(width: i32, height: i32, duration: f64) -> Self {
+        Self::color_with("black", width, height, duration, "30")
+    }
+
+    /// color filter for creating a solid video of any color and frame rate
+    pub fn color_with<S1: AsRef<str>, S2: Into<String>>(
+        color: S1,
+        width: i32,
+        height: i32,
+        duration: f64,
+        rate: S2,
+    ) -> Self {
         Self::with_name("color").params([
-            "color=black".to_string(),
+            format!("color={}", util::escape_value(color.as_ref())),
             format!("size={}x{}", width, height),
             format!("duration={}", duration),
-            "rate=30".to_string(),
+            format!("rate={}", rate.into()),
         ])
     }
 
@@ -206,6 +222,15 @@ impl Filter {
         Self::with_name("scale").params([w_str, h_str])
     }
 
+    /// Scale filter with size expressions evaluated for every frame
+    pub fn scale_expr(width: &str, height: &str) -> Self {
+        Self::with_name("scale").params([
+            format!("w='{}'", width),
+            format!("h='{}'", height),
+            "eval=frame".to_string(),
+        ])
+    }
+
     pub fn trim(start: f64, end: f64) -> Self {
         Self::with_name("trim").params([format!("start={}", start), format!("end={}", end)])
     }
@@ -215,6 +240,15 @@ impl Filter {
         Self::with_name("scale").params([format!("{}:-2", size)])
     }
 
+    /// Scale filter shrinking video to fit inside a box, keeping the aspect ratio
+    pub fn scale_fit(max_width: i32, max_height: i32) -> Self {
+        Self::with_name("scale").params([
+            format!("w='min(iw,{})'", max_width),
+            format!("h='min(ih,{})'", max_height),
+            "force_original_aspect_ratio=decrease".to_string(),
+        ])
+    }
+
     /// Crop filter for cutting video
     pub fn crop(width: i32, height: i32, x: i32, y: i32) -> Self {
         Self::with_name("crop").params([
@@ -225,6 +259,41 @@ impl Filter {
         ])
     }
 
+    /// Pad filter placing the frame at `x`:`y` on a larger canvas
+    pub fn pad<S1: Into<String>, S2: Into<String>, S3: Into<String>>(
+        width: i32,
+        height: i32,
+        x: S1,
+        y: S2,
+        color: S3,
+    ) -> Self {
+        Self::with_name("pad").params([
+            width.to_string(),
+            height.to_string(),
+            x.into(),
+            y.into(),
+            color.into(),
+        ])
+    }
+
+    /// Format filter for converting the pixel format
+    pub fn format<S: Into<String>>(pix_fmt: S) -> Self {
+        Self::with_name("format").param(pix_fmt.into())
+    }
+
+    /// Colorchannelmixer filter scaling the alpha channel (0.0 to 1.0)
+    pub fn alpha(alpha: f32) -> Self {
+        Self::with_name("colorchannelmixer").param(format!("aa={}", alpha))
+    }
+
+    /// Blend filter, the first input is the top layer
+    pub fn blend<S: Into<String>>(mode: S, opacity: f32) -> Self {
+        Self::with_name("blend").params([
+            format!("all_mode={}", mode.into()),
+            format!("all_opacity={}", opacity),
+        ])
+    }
+
     /// Delogo filter for removing logos/watermarks
     pub fn delogo(x: i32, y: i32, width: i32, height: i32) -> Self {
         Self::with_name("delogo").params([
@@ -245,6 +314,42 @@ impl Filter {
             .params([format!("{}:{}", x, y), format!("enable={}", enable_expr)])
     }
 
+    /// Overlay filter with per-frame position expressions
+    pub fn overlay_expr(x: &str, y: &str, enable_expr: String) -> Self {
+        Self::with_name("overlay").params([
+            format!("x='{}'", x),
+            format!("y='{}'", y),
+            format!("enable={}", enable_expr),
+        ])
+    }
+
+    /// Drawtext filter rendering literal text (no `%{...}` expansion)
+    pub fn drawtext(text: &str) -> Self {
+        Self::with_name("drawtext").params([
+            format!("text={}", util::escape_value(text)),
+            "expansion=none".to_string(),
+        ])
+    }
+
+    /// Subtitles filter burning a subtitle file into the video
+    pub fn subtitles(path: &str) -> Self {
+        Self::with_name("subtitles").param(format!("filename={}", util::escape_value(path)))
+    }
+
+    /// ASS filter burning an ASS/SSA subtitle file into the video
+    pub fn ass(path: &str) -> Self {
+        Self::with_name("ass").param(format!("filename={}", util::escape_value(path)))
+    }
+
+    /// Xfade filter transitioning from the first input into the second
+    pub fn xfade<S: Into<String>>(transition: S, duration: f64, offset: f64) -> Self {
+        Self::with_name("xfade").params([
+            format!("transition={}", transition.into()),
+            format!("duration={}", duration),
+            format!("offset={}", offset),
+        ])
+    }
+
     /// FPS filter for changing frame rate
     pub fn fps<S: Into<String>>(fps: S) -> Self {
         Self::with_name("fps").param(fps.into())
@@ -255,6 +360,14 @@ impl Filter {
         Self::with_name("select").param(expression.into())
     }
 
+    /// Metadata filter printing the timestamp and metadata of each frame to a file
+    pub fn metadata_print(file: &str) -> Self {
+        Self::with_name("metadata").params([
+            "print".to_string(),
+            format!("file={}", util::escape_value(file)),
+        ])
+    }
+
     /// Tile filter for creating sprite sheets
     pub fn tile(cols: i32, rows: i32) -> Self {
         Self::with_name("tile").params([format!("layout={}x{}", cols, rows)])
@@ -288,6 +401,56 @@ impl Filter {
         Self::with_name("rotate").param(angle.to_string())
     }
 
+    /// Rotate filter with an angle expression in radians.
+    ///
+    /// The output is the diagonal of the input, so no corner is cut off, and
+    /// the uncovered area is transparent.
+    pub fn rotate_expr(angle: &str) -> Self {
+        Self::with_name("rotate").params([
+            format!("a='{}'", angle),
+            "c=none".to_string(),
+            "ow='hypot(iw,ih)'".to_string(),
+            "oh=ow".to_string(),
+        ])
+    }
+
+    /// Rotate filter with an angle in radians and a fixed output size,
+    /// leaving the uncovered area transparent
+    pub fn rotate_to(angle: f64, width: i32, height: i32) -> Self {
+        Self::with_name("rotate").params([
+            format!("a={}", angle),
+            "c=none".to_string(),
+            format!("ow={}", width),
+            format!("oh={}", height),
+        ])
+    }
+
+    /// Colorchannelmixer instance `instance` multiplying the alpha channel
+    /// by `alpha`; `sendcmd_expr` can change its `aa` option per frame
+    pub fn alpha_mixer(instance: &str, alpha: f64) -> Self {
+        Self::with_name(format!("colorchannelmixer@{}", instance)).param(format!("aa={}", alpha))
+    }
+
+    /// Sendcmd filter setting `option` of the `target` filter instance to an
+    /// expression of `T` on every frame
+    pub fn sendcmd_expr(target: &str, option: &str, expr: &str) -> Self {
+        let command = format!("0 [expr] {} {} '{}'", target, option, expr);
+        Self::with_name("sendcmd").param(format!("c={}", util::escape_value(&command)))
+    }
+
+    /// Geq filter multiplying the alpha channel by an expression of `T`.
+    ///
+    /// It evaluates the expression for every pixel, which is slow on large
+    /// frames; prefer `alpha_mixer` driven by `sendcmd_expr`.
+    pub fn alpha_expr(alpha: &str) -> Self {
+        Self::with_name("geq").params([
+            "r='r(X,Y)'".to_string(),
+            "g='g(X,Y)'".to_string(),
+            "b='b(X,Y)'".to_string(),
+            format!("a='alpha(X,Y)*({})'", alpha),
+        ])
+    }
+
     /// Flip horizontal
     pub fn hflip() -> Self {
         Self::with_name("hflip")
@@ -311,11 +474,26 @@ impl Filter {
         Self::with_name("volume").param(volume.to_string())
     }
 
+    /// Volume filter with an expression evaluated for every frame
+    pub fn volume_expr(volume: &str) -> Self {
+        Self::with_name("volume").params([format!("volume='{}'", volume), "eval=frame".to_string()])
+    }
+
+    /// Volume filter with a gain in dB
+    pub fn volume_db(gain: f64) -> Self {
+        Self::with_name("volume").param(format!("{}dB", gain))
+    }
+
+    /// Pan filter weighting the left and right channels of a stereo stream
+    pub fn pan_stereo(left: f64, right: f64) -> Self {
+        Self::with_name("pan").param(format!("stereo|c0={}*c0|c1={}*c1", left, right))
+    }
+
     /// Audio fade in
     pub fn afade_in(start_time: f64, duration: f64) -> Self {
         Self::with_name("afade").params([
             "t=in".to_string(),
-            format!("ss={}", start_time),
+            format!("st={}", start_time),
             format!("d={}", duration),
         ])
     }
@@ -324,11 +502,16 @@ impl Filter {
     pub fn afade_out(start_time: f64, duration: f64) -> Self {
         Self::with_name("afade").params([
             "t=out".to_string(),
-            format!("ss={}", start_time),
+            format!("st={}", start_time),
             format!("d={}", duration),
         ])
     }
 
+    /// Acrossfade filter overlapping the end of the first input with the second
+    pub fn acrossfade(duration: f64) -> Self {
+        Self::with_name("acrossfade").param(format!("d={}", duration))
+    }
+
     /// Audio trim
     pub fn atrim(start_time: f64, duration: f64) -> Self {
         Self::with_name("atrim").params([
@@ -351,6 +534,11 @@ impl Filter {
         Self::with_name("aformat").param(format!("channel_layouts={}", channels))
     }
 
+    /// Audio channel layout by name, e.g. `stereo` or `5.1`
+    pub fn channel_layout<S: Into<String>>(layout: S) -> Self {
+        Self::with_name("aformat").param(format!("channel_layouts={}", layout.into()))
+    }
+
     /// Audio SetPTS filter
     pub fn asetpts<S: Into<String>>(expression: S) -> Self {
         Self::with_name("asetpts").param(expression.into())
@@ -401,8 +589,22 @@ impl Filter {
         Self::with_name("anull")
     }
 
+    /// Silent audio source at 44.1 kHz
     pub fn anullsrc(duration: f32) -> Self {
-        Self::with_name("anullsrc").params([format!("r=44100"), format!("d={duration}s")])
+        Self::with_name("anullsrc").params(["r=44100".to_string(), format!("d={duration}s")])
+    }
+
+    /// Silent audio source with a sample rate and channel layout
+    pub fn anullsrc_with<S: Into<String>>(
+        duration: f32,
+        sample_rate: i32,
+        channel_layout: S,
+    ) -> Self {
+        Self::with_name("anullsrc").params([
+            format!("r={sample_rate}"),
+            format!("cl={}", channel_layout.into()),
+            format!("d={duration}s"),
+        ])
     }
 }
 
@@ -479,6 +681,38 @@ pub mod util {
         }
     }
 
+    /// Drawtext font option; font file paths and fontconfig family names
+    /// use different keys
+    pub fn font_option(font: &str) -> String {
+        let is_file = font.contains(['/', '\\'])
+            || std::path::Path::new(font)
+                .extension()
+                .is_some_and(|ext| matches!(ext.to_str(), Some("ttf" | "otf" | "ttc")));
+        let key = if is_file { "fontfile" } else { "font" };
+        format!("{}={}", key, escape_value(font))
+    }
+
+    /// Escape a filter option value for use inside a filter graph
+    pub fn escape_value(value: &str) -> String {
+        // Option level first, then filter graph level
+        let mut option = String::with_capacity(value.len());
+        for c in value.chars() {
+            if matches!(c, '\\' | '\'' | ':') {
+                option.push('\\');
+            }
+            option.push(c);
+        }
+
+        let mut graph = String::with_capacity(option.len());
+        for c in option.chars() {
+            if matches!(c, '\\' | '\'' | '[' | ']' | ',' | ';') {
+                graph.push('\\');
+            }
+            graph.push(c);
+        }
+        graph
+    }
+
     /// Create time-based expression for filters
     pub fn time_expression(start_time: f64) -> String {
         format!("gte(t,{})", start_time)
@@ -598,4 +832,55 @@ mod tests {
         let frame_expr = util::frame_expression(&[0, 5, 10]);
         assert_eq!(frame_expr, "eq(n,0)+eq(n,5)+eq(n,10)");
     }
+
+    #[test]
+    fn test_alpha_commands() {
+        assert!(
+            Filter::alpha_mixer("fade0", 0.5)
+                .build()
+                .starts_with("colorchannelmixer@fade0=aa=0.5[")
+        );
+        assert!(
+            Filter::sendcmd_expr("colorchannelmixer@fade0", "aa", "if(lt(T,1),T,1)")
+                .build()
+                .starts_with(
+                    r"sendcmd=c=0 \[expr\] colorchannelmixer@fade0 aa \\\'if(lt(T\,1)\,T\,1)\\\'["
+                )
+        );
+    }
+
+    #[test]
+    fn test_drawtext_filter() {
+        assert_eq!(util::escape_value("Hello"), "Hello");
+        assert_eq!(util::escape_value("a:b"), "a\\\\:b");
+        assert_eq!(util::escape_value("it's [1,2]"), "it\\\\\\'s \\[1\\,2\\]");
+
+        let filter = Filter::drawtext("12:00");
+        assert!(
+            filter
+                .build()
+                .starts_with("drawtext=text=12\\\\:00:expansion=none")
+        );
+    }
+
+    #[test]
+    fn test_expression_filters() {
+        let scale = Filter::scale_expr("if(lt(t,1),100,200)", "-1").build();
+        assert!(scale.starts_with("scale=w='if(lt(t,1),100,200)':h='-1':eval=frame"));
+
+        let fit = Filter::scale_fit(320, 240).build();
+        assert!(fit.starts_with(
+            "scale=w='min(iw,320)':h='min(ih,240)':force_original_aspect_ratio=decrease"
+        ));
+
+        let volume = Filter::volume_expr("0.5+0.5*t").build();
+        assert!(volume.starts_with("volume=volume='0.5+0.5*t':eval=frame"));
+
+        let overlay = Filter::overlay_expr("10*(t-1)", "20", "'between(t,1,2)'".to_string());
+        assert!(
+            overlay
+                .build()
+                .starts_with("overlay=x='10*(t-1)':y='20':enable='between(t,1,2)'")
+        );
+    }
 }
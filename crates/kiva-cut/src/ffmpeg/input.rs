@@ -118,6 +118,10 @@ impl Input {
         Stream::may_audio(self.idx as i32)
     }
 
+    pub fn s(&self) -> Stream {
+        Stream::subtitle(self.idx as i32)
+    }
+
     /// Alias for v() - get video stream
     pub fn video(&self) -> Stream {
         self.v()
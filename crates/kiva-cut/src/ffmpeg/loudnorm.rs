@@ -0,0 +1,213 @@
+//! Two-pass EBU R128 loudness normalization
+//!
+//! The first pass runs `loudnorm` with `print_format=json` and reads the
+//! measurements from the log into [`LoudnormParams`]. The second pass feeds
+//! them back so `loudnorm` can apply a linear gain instead of compressing
+//! dynamically.
+
+use crate::error::{CutError, Result};
+use crate::ffmpeg::{codec::Format, filter::Filter, output::Output, stream::Stream};
+use crate::ffprobe::LoudnormParams;
+use crate::options::{FFmpegOptions, LogLevel};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Sample rate restored after `loudnorm`, which works at 192 kHz
+pub const OUTPUT_SAMPLE_RATE: i32 = 48000;
+
+/// Loudness target
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoudnessTarget {
+    /// Integrated loudness in LUFS
+    pub integrated: f32,
+    /// Maximum true peak in dBTP
+    pub true_peak: f32,
+    /// Loudness range in LU
+    pub lra: f32,
+}
+
+impl LoudnessTarget {
+    /// Create a target
+    pub fn new(integrated: f32, true_peak: f32, lra: f32) -> Self {
+        Self {
+            integrated,
+            true_peak,
+            lra,
+        }
+    }
+
+    /// -14 LUFS, -1 dBTP, as used by most streaming platforms
+    pub fn streaming() -> Self {
+        Self::new(-14.0, -1.0, 11.0)
+    }
+
+    /// -23 LUFS, -1 dBTP, EBU R128 broadcast delivery
+    pub fn broadcast() -> Self {
+        Self::new(-23.0, -1.0, 7.0)
+    }
+
+    /// Validate the target against the ranges `loudnorm` accepts
+    pub fn validate(&self) -> Result<()> {
+        if !(-70.0..=-5.0).contains(&self.integrated) {
+            return Err(CutError::invalid_params(
+                "Integrated loudness must be between -70 and -5 LUFS",
+            ));
+        }
+        if !(-9.0..=0.0).contains(&self.true_peak) {
+            return Err(CutError::invalid_params(
+                "True peak must be between -9 and 0 dBTP",
+            ));
+        }
+        if !(1.0..=50.0).contains(&self.lra) {
+            return Err(CutError::invalid_params(
+                "Loudness range must be between 1 and 50 LU",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Build the analysis filter of the first pass
+    pub fn analyze_filter(&self) -> Filter {
+        Filter::with_name("loudnorm")
+            .params(self.target_params())
+            .param("print_format=json")
+    }
+
+    /// Build the filter of the second pass; without measurements `loudnorm`
+    /// normalizes dynamically in a single pass
+    pub fn normalize_filter(&self, measured: Option<&LoudnormParams>) -> Result<Filter> {
+        let filter = Filter::with_name("loudnorm").params(self.target_params());
+        let Some(measured) = measured else {
+            return Ok(filter);
+        };
+
+        let value = |value: Option<f32>, name: &str| {
+            value.ok_or_else(|| CutError::missing_param(format!("measured {}", name)))
+        };
+        Ok(filter.params([
+            format!("measured_I={}", value(measured.input_i, "input_i")?),
+            format!("measured_TP={}", value(measured.input_tp, "input_tp")?),
+            format!("measured_LRA={}", value(measured.input_lra, "input_lra")?),
+            format!(
+                "measured_thresh={}",
+                value(measured.input_thresh, "input_thresh")?
+            ),
+            format!("offset={}", measured.target_offset.unwrap_or_default()),
+            "linear=true".to_string(),
+        ]))
+    }
+
+    fn target_params(&self) -> [String; 3] {
+        [
+            format!("I={}", self.integrated),
+            format!("TP={}", self.true_peak),
+            format!("LRA={}", self.lra),
+        ]
+    }
+}
+
+/// FFmpeg options for an analysis pass; `loudnorm` prints its measurements
+/// at the info log level
+pub fn analysis_options(options: &FFmpegOptions) -> FFmpegOptions {
+    let mut options = options.clone();
+    if !matches!(
+        options.log_level,
+        LogLevel::Info | LogLevel::Verbose | LogLevel::Debug
+    ) {
+        options.log_level = LogLevel::Info;
+    }
+    options
+}
+
+/// Output discarding the analyzed stream
+pub fn null_output(stream: Stream) -> Output {
+    Output::with_simple("-")
+        .map_stream(stream)
+        .format(Format::custom("null"))
+}
+
+/// Parse the JSON block `loudnorm=print_format=json` writes to the log
+pub fn parse_log(log: &str) -> Result<LoudnormParams> {
+    let missing = || CutError::ffmpeg("No loudnorm measurements in the FFmpeg log");
+    // Take the last loudnorm block, all its values are strings
+    let start = log.rfind("Parsed_loudnorm").ok_or_else(missing)?;
+    let block = &log[start..];
+    let open = block.find('{').ok_or_else(missing)?;
+    let close = block[open..].find('}').ok_or_else(missing)? + open;
+    let values: HashMap<String, String> = serde_json::from_str(&block[open..=close])?;
+
+    let number = |key: &str| values.get(key).and_then(|v| v.trim().parse::<f32>().ok());
+    Ok(LoudnormParams {
+        input_i: number("input_i"),
+        input_tp: number("input_tp"),
+        input_lra: number("input_lra"),
+        input_thresh: number("input_thresh"),
+        output_i: number("output_i"),
+        output_tp: number("output_tp"),
+        output_lra: number("output_lra"),
+        output_thresh: number("output_thresh"),
+        normalization_type: values.get("normalization_type").cloned(),
+        target_offset: number("target_offset"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = r#"size=N/A time=00:00:10.00 bitrate=N/A speed= 412x
+[Parsed_loudnorm_0 @ 0x55d0c3a0]
+{
+	"input_i" : "-27.61",
+	"input_tp" : "-4.47",
+	"input_lra" : "18.06",
+	"input_thresh" : "-39.20",
+	"output_i" : "-16.58",
+	"output_tp" : "-1.50",
+	"output_lra" : "14.78",
+	"output_thresh" : "-27.71",
+	"normalization_type" : "dynamic",
+	"target_offset" : "0.58"
+}
+"#;
+
+    #[test]
+    fn test_parse_log() {
+        let params = parse_log(LOG).unwrap();
+        assert_eq!(params.input_i, Some(-27.61));
+        assert_eq!(params.input_thresh, Some(-39.2));
+        assert_eq!(params.normalization_type.as_deref(), Some("dynamic"));
+        assert_eq!(params.target_offset, Some(0.58));
+
+        assert!(parse_log("size=N/A time=00:00:10.00").is_err());
+    }
+
+    #[test]
+    fn test_filters() {
+        let target = LoudnessTarget::streaming();
+        assert!(
+            target
+                .analyze_filter()
+                .build()
+                .starts_with("loudnorm=I=-14:TP=-1:LRA=11:print_format=json")
+        );
+
+        let params = parse_log(LOG).unwrap();
+        let filter = target.normalize_filter(Some(&params)).unwrap().build();
+        assert!(filter.starts_with(
+            "loudnorm=I=-14:TP=-1:LRA=11:measured_I=-27.61:measured_TP=-4.47:\
+             measured_LRA=18.06:measured_thresh=-39.2:offset=0.58:linear=true"
+        ));
+
+        let filter = target.normalize_filter(None).unwrap().build();
+        assert!(filter.starts_with("loudnorm=I=-14:TP=-1:LRA=11["));
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(LoudnessTarget::streaming().validate().is_ok());
+        assert!(LoudnessTarget::broadcast().validate().is_ok());
+        assert!(LoudnessTarget::new(-80.0, -1.0, 11.0).validate().is_err());
+        assert!(LoudnessTarget::new(-14.0, 1.0, 11.0).validate().is_err());
+    }
+}
@@ -1,20 +1,39 @@
 //! FFmpeg command builder and executor module
 
+pub mod abr;
 pub mod codec;
+pub mod diagnostics;
 pub mod filter;
 pub mod input;
+pub mod loudnorm;
 pub mod output;
+pub mod progress;
 pub mod stream;
 pub mod util;
+pub mod watermark;
 
 use crate::error::{CutError, Result};
 use crate::ffmpeg::stream::{Stream, StreamInput, Streamable};
 
-use crate::ffmpeg::{filter::Filter, input::Input, output::Output};
+use crate::ffmpeg::{
+    diagnostics::{CommandContext, FFmpegFailure},
+    filter::{Filter, Label},
+    input::Input,
+    output::Output,
+    progress::{CancelHandle, Progress, ProgressParser},
+};
 use crate::options::FFmpegOptions;
+use regex::Regex;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::sync::atomic::{AtomicU32, Ordering};
-use tokio::process::Command;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+
+/// How long a cancelled FFmpeg gets to finalize its outputs before it is killed
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// FFmpeg command builder and executor
 #[derive(Debug)]
@@ -72,6 +91,26 @@ impl FFmpeg {
         filter_copy.to_stream()
     }
 
+    /// Add a filter producing several outputs (e.g. `split`), one stream per output
+    pub fn add_filter_with_outputs<I, S>(
+        &mut self,
+        mut filter: Filter,
+        inputs: I,
+        outputs: usize,
+    ) -> Vec<Stream>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<StreamInput>,
+    {
+        let streams: Vec<Stream> = (0..outputs)
+            .map(|_| Stream::any(0).with_label(Label::random().to_string()))
+            .collect();
+        filter.inputs = inputs.into_iter().map(|s| s.into()).collect();
+        filter.outputs = streams.iter().map(StreamInput::from).collect();
+        self.filters.push(filter);
+        streams
+    }
+
     /// Add an output to the FFmpeg command
     pub fn add_output(&mut self, output: Output) {
         self.outputs.push(output);
@@ -135,16 +174,26 @@ impl FFmpeg {
 
     /// Execute the FFmpeg command
     pub async fn run(&mut self) -> Result<()> {
+        self.run_with_log().await.map(|_| ())
+    }
+
+    /// Execute the FFmpeg command and return its log.
+    ///
+    /// Analysis filters such as `loudnorm` print their results there; a dry
+    /// run returns an empty log.
+    pub async fn run_with_log(&mut self) -> Result<String> {
         if self.ffmpeg_options.dry_run {
             self.dry_run();
-            return Ok(());
+            return Ok(String::new());
         }
 
         if self.ffmpeg_options.debug {
             self.dry_run();
         }
 
-        let args = self.build_args();
+        // Stats lines tell failure diagnostics how far the output got
+        let mut args = vec!["-stats".to_string()];
+        args.extend(self.build_args());
         let mut cmd = Command::new(&self.ffmpeg_options.binary_path);
         cmd.args(&args)
             .stdout(Stdio::piped())
@@ -161,22 +210,203 @@ impl FFmpeg {
             .map_err(|e| CutError::ffmpeg(format!("Failed to execute FFmpeg: {}", e)))?;
 
         if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(CutError::ffmpeg(format!(
-                "FFmpeg command failed with exit code {:?}: {}",
-                output.status.code(),
-                stderr
-            )));
+            return Err(self.failure(output.status.code(), &output.stderr));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stderr).into_owned())
+    }
+
+    /// Execute the FFmpeg command, reporting progress and honouring cancellation.
+    ///
+    /// Progress is read from `-progress pipe:1`; percentages need the total
+    /// output duration in milliseconds. On cancellation FFmpeg is asked to
+    /// stop with `q` and killed if it does not exit in time, the files its
+    /// outputs wrote are removed and [`CutError::Cancelled`] is returned.
+    pub async fn run_with_progress<F>(
+        &mut self,
+        total_duration: Option<u32>,
+        cancel: &CancelHandle,
+        mut on_progress: F,
+    ) -> Result<()>
+    where
+        F: FnMut(Progress),
+    {
+        if self.ffmpeg_options.dry_run {
+            self.dry_run();
+            return Ok(());
+        }
+
+        if self.ffmpeg_options.debug {
+            self.dry_run();
+        }
+
+        let mut args = vec![
+            "-progress".to_string(),
+            "pipe:1".to_string(),
+            "-stats".to_string(),
+        ];
+        args.extend(self.build_args());
+        let mut cmd = Command::new(&self.ffmpeg_options.binary_path);
+        cmd.args(&args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        // Set environment variables
+        for (key, value) in &self.ffmpeg_options.env_vars {
+            cmd.env(key, value);
+        }
+
+        // Files already there are not removed on cancellation
+        let existing = self.output_files();
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| CutError::ffmpeg(format!("Failed to execute FFmpeg: {}", e)))?;
+
+        // Drain stderr concurrently so a full pipe cannot stall FFmpeg
+        let mut stderr = child.stderr.take().expect("stderr is piped");
+        let stderr_task = tokio::spawn(async move {
+            let mut buf = Vec::new();
+            let _ = stderr.read_to_end(&mut buf).await;
+            buf
+        });
+
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let mut lines = BufReader::new(stdout).lines();
+        let mut parser = ProgressParser::new(total_duration);
+        loop {
+            tokio::select! {
+                line = lines.next_line() => match line? {
+                    Some(line) => {
+                        if let Some(progress) = parser.feed_line(&line) {
+                            on_progress(progress);
+                        }
+                    }
+                    None => break,
+                },
+                _ = cancel.cancelled() => return self.stop(&mut child, &existing).await,
+            }
+        }
+
+        let status = tokio::select! {
+            status = child.wait() => status?,
+            _ = cancel.cancelled() => return self.stop(&mut child, &existing).await,
+        };
+
+        if !status.success() {
+            let stderr = stderr_task.await.unwrap_or_default();
+            return Err(self.failure(status.code(), &stderr));
         }
 
         Ok(())
     }
+
+    /// Parse the log of a failed run into a typed error
+    fn failure(&self, exit_code: Option<i32>, stderr: &[u8]) -> CutError {
+        let context = CommandContext {
+            inputs: self.inputs.iter().map(|i| i.path.clone()).collect(),
+            filters: self
+                .filters
+                .iter()
+                .map(|f| {
+                    let label = match f.outputs.first() {
+                        Some(output) => output.to_stream().to_string(),
+                        None => f.label.to_string(),
+                    };
+                    (f.name.clone(), label)
+                })
+                .collect(),
+        };
+        let stderr = String::from_utf8_lossy(stderr);
+        CutError::FFmpegFailed(Box::new(FFmpegFailure::parse(&stderr, exit_code, &context)))
+    }
+
+    /// Remove the output files of an aborted run, and the playlists and
+    /// segments it created from name templates, leaving pipes and URLs alone
+    fn remove_outputs(&self, existing: &HashSet<PathBuf>) {
+        for output in self.outputs.iter().filter(|o| !o.is_stream()) {
+            if Path::new(&output.path).is_file() {
+                let _ = std::fs::remove_file(&output.path);
+            }
+        }
+        for path in self.output_files().difference(existing) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    /// Files on disk matching the name templates of the outputs
+    fn output_files(&self) -> HashSet<PathBuf> {
+        self.outputs
+            .iter()
+            .flat_map(|output| output.file_templates())
+            .flat_map(|template| files_matching(&template))
+            .collect()
+    }
+
+    /// Ask FFmpeg to quit so it finalizes its muxers, kill it when it does
+    /// not exit in time and remove the files its outputs wrote
+    async fn stop(&self, child: &mut Child, existing: &HashSet<PathBuf>) -> Result<()> {
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = stdin.write_all(b"q").await;
+        }
+        if tokio::time::timeout(STOP_TIMEOUT, child.wait())
+            .await
+            .is_err()
+        {
+            child.kill().await?;
+        }
+        self.remove_outputs(existing);
+        Err(CutError::Cancelled)
+    }
+}
+
+/// Files in the template's directory whose names match an ffmpeg file name
+/// template such as `out_%v_%03d.ts` or `seg_$RepresentationID$_$Number%05d$.m4s`
+fn files_matching(template: &Path) -> Vec<PathBuf> {
+    let Some(name) = template.file_name().and_then(|n| n.to_str()) else {
+        return Vec::new();
+    };
+    let dir = match template.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    let marker = Regex::new(r"%%|%v|%\d*d|\$[A-Za-z]+(%\d*d)?\$").unwrap();
+    let mut pattern = "^".to_string();
+    let mut last = 0;
+    for m in marker.find_iter(name) {
+        pattern.push_str(&regex::escape(&name[last..m.start()]));
+        pattern.push_str(if m.as_str() == "%%" { "%" } else { ".+" });
+        last = m.end();
+    }
+    pattern.push_str(&regex::escape(&name[last..]));
+    pattern.push('$');
+    let pattern = Regex::new(&pattern).unwrap();
+
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .is_some_and(|n| pattern.is_match(n))
+        })
+        .map(|entry| dir.join(entry.file_name()))
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ffmpeg::diagnostics::FFmpegErrorKind;
     use crate::options::LogLevel;
+    #[cfg(unix)]
+    use crate::test_support::fake_binary;
 
     #[test]
     fn test_custom_options() {
@@ -191,4 +421,121 @@ mod tests {
         assert!(args.contains(&"debug".to_string()));
         assert!(!args.contains(&"-y".to_string()));
     }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_with_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary = fake_binary(
+            dir.path(),
+            "ffmpeg",
+            "echo frame=10\necho out_time_us=1000000\necho progress=continue\necho progress=end",
+        );
+
+        let mut ffmpeg = FFmpeg::new();
+        ffmpeg.set_ffmpeg_options(FFmpegOptions::new().binary_path(binary));
+        let mut reports = Vec::new();
+        ffmpeg
+            .run_with_progress(Some(4000), &CancelHandle::new(), |p| reports.push(p))
+            .await
+            .unwrap();
+
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].percent, Some(25.0));
+        assert!(reports[1].done);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_with_progress_cancel() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().display();
+        let binary = fake_binary(
+            dir.path(),
+            "ffmpeg",
+            &format!(
+                "echo out_time_us=1000000\necho progress=continue\n\
+                 touch {out}/master_0.m3u8 {out}/master_0_000.ts {out}/master_1_000.ts\n\
+                 read key\n[ \"$key\" = q ] && touch {out}/master.m3u8"
+            ),
+        );
+        let kept = dir.path().join("master_notes.txt");
+        std::fs::write(&kept, b"notes").unwrap();
+        let stale = dir.path().join("master_2_000.ts");
+        std::fs::write(&stale, b"earlier run").unwrap();
+
+        let mut ffmpeg = FFmpeg::new();
+        ffmpeg.set_ffmpeg_options(FFmpegOptions::new().binary_path(binary));
+        ffmpeg.add_output(
+            Output::with_simple(format!("{out}/master_%v.m3u8"))
+                .hls_segment_filename(format!("{out}/master_%v_%03d.ts"))
+                .option("master_pl_name", "master.m3u8"),
+        );
+
+        let cancel = CancelHandle::new();
+        let result = ffmpeg
+            .run_with_progress(None, &cancel, |_| cancel.cancel())
+            .await;
+
+        assert!(matches!(result, Err(CutError::Cancelled)));
+        let mut left: Vec<String> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        assert_eq!(left, ["ffmpeg", "master_2_000.ts", "master_notes.txt"]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_with_progress_cancel_partial_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary = fake_binary(
+            dir.path(),
+            "ffmpeg",
+            "echo out_time_us=1000000\necho progress=continue\nread key",
+        );
+        let output = dir.path().join("out.mp4");
+        std::fs::write(&output, b"partial").unwrap();
+
+        let mut ffmpeg = FFmpeg::new();
+        ffmpeg.set_ffmpeg_options(FFmpegOptions::new().binary_path(binary));
+        ffmpeg.add_output(Output::with_simple(output.to_string_lossy()));
+
+        let cancel = CancelHandle::new();
+        let result = ffmpeg
+            .run_with_progress(None, &cancel, |_| cancel.cancel())
+            .await;
+
+        assert!(matches!(result, Err(CutError::Cancelled)));
+        assert!(!output.exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_failure_diagnostics() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary = fake_binary(
+            dir.path(),
+            "ffmpeg",
+            "echo 'gone.mp4: No such file or directory' >&2\nexit 254",
+        );
+
+        let mut ffmpeg = FFmpeg::new();
+        ffmpeg.set_ffmpeg_options(FFmpegOptions::new().binary_path(binary));
+        ffmpeg.add_input(Input::with_simple("a.mp4"));
+        ffmpeg.add_input(Input::with_simple("gone.mp4"));
+
+        let error = ffmpeg.run().await.unwrap_err();
+        let failure = error.ffmpeg_failure().unwrap();
+        assert_eq!(
+            failure.kind,
+            FFmpegErrorKind::InputNotFound {
+                input: Some(1),
+                path: "gone.mp4".to_string()
+            }
+        );
+        assert_eq!(failure.exit_code, Some(254));
+        assert_eq!(failure.log_tail, "gone.mp4: No such file or directory");
+    }
 }
@@ -1,9 +1,10 @@
 //! Output handling for FFmpeg operations
 
-use crate::ffmpeg::codec::{AudioCodec, Format, PixelFormat, VideoCodec};
+use crate::ffmpeg::codec::{AudioCodec, Format, PixelFormat, SubtitleCodec, VideoCodec};
 use crate::ffmpeg::stream::StreamInput;
 use std::collections::HashMap;
 use std::fmt;
+use std::path::{Path, PathBuf};
 
 /// FFmpeg output configuration
 #[derive(Debug, Clone)]
@@ -14,6 +15,8 @@ pub struct Output {
     pub video_codec: Option<VideoCodec>,
     /// Audio codec
     pub audio_codec: Option<AudioCodec>,
+    /// Subtitle codec
+    pub subtitle_codec: Option<SubtitleCodec>,
     /// Output format
     pub format: Option<Format>,
     /// Pixel format
@@ -63,6 +66,7 @@ impl Output {
             path: path.into(),
             video_codec: None,
             audio_codec: None,
+            subtitle_codec: None,
             format: None,
             pixel_format: None,
             video_bitrate: None,
@@ -100,6 +104,12 @@ impl Output {
         self
     }
 
+    /// Set subtitle codec
+    pub fn subtitle_codec(mut self, codec: SubtitleCodec) -> Self {
+        self.subtitle_codec = Some(codec);
+        self
+    }
+
     /// Set output format
     pub fn format(mut self, format: Format) -> Self {
         self.format = Some(format);
@@ -256,14 +266,28 @@ impl Output {
         }
 
         // Add video codec
-        if let Some(ref codec) = self.video_codec {
-            args.push("-c:v".to_string());
-            args.push(codec.to_string());
+        match self.video_codec {
+            Some(VideoCodec::None) => args.push("-vn".to_string()),
+            Some(ref codec) => {
+                args.push("-c:v".to_string());
+                args.push(codec.to_string());
+            }
+            None => {}
         }
 
         // Add audio codec
-        if let Some(ref codec) = self.audio_codec {
-            args.push("-c:a".to_string());
+        match self.audio_codec {
+            Some(AudioCodec::None) => args.push("-an".to_string()),
+            Some(ref codec) => {
+                args.push("-c:a".to_string());
+                args.push(codec.to_string());
+            }
+            None => {}
+        }
+
+        // Add subtitle codec
+        if let Some(ref codec) = self.subtitle_codec {
+            args.push("-c:s".to_string());
             args.push(codec.to_string());
         }
 
@@ -394,6 +418,35 @@ impl Output {
 
         args
     }
+
+    /// Check if the output goes to a pipe or a URL instead of a file
+    pub fn is_stream(&self) -> bool {
+        self.path == "-" || self.path.starts_with("pipe:") || self.path.contains("://")
+    }
+
+    /// Name templates of the files the muxer writes: the output itself, HLS
+    /// segments and master playlist, and DASH init and media segments
+    pub fn file_templates(&self) -> Vec<PathBuf> {
+        if self.is_stream() {
+            return Vec::new();
+        }
+        let path = Path::new(&self.path);
+        let dir = path.parent().unwrap_or(Path::new(""));
+
+        let mut templates = vec![path.to_path_buf()];
+        if let Some(ref filename) = self.hls_segment_filename {
+            templates.push(PathBuf::from(filename));
+        }
+        for (key, value) in &self.options {
+            if matches!(
+                key.as_str(),
+                "master_pl_name" | "init_seg_name" | "media_seg_name"
+            ) {
+                templates.push(dir.join(value));
+            }
+        }
+        templates
+    }
 }
 
 impl fmt::Display for Output {
@@ -486,6 +539,7 @@ impl OutputBuilder {
             path,
             video_codec: self.video_codec,
             audio_codec: self.audio_codec,
+            subtitle_codec: None,
             format: self.format,
             pixel_format: self.pixel_format,
             video_bitrate: self.video_bitrate,
@@ -539,6 +593,12 @@ mod tests {
         assert!(args.contains(&"aac".to_string()));
         assert!(args.contains(&"-crf".to_string()));
         assert!(args.contains(&"23".to_string()));
+
+        let args = Output::with_simple("output.mp3")
+            .video_codec(VideoCodec::None)
+            .audio_codec(AudioCodec::MP3)
+            .to_args();
+        assert_eq!(args, vec!["-vn", "-c:a", "libmp3lame", "output.mp3"]);
     }
 
     #[test]
@@ -0,0 +1,186 @@
+//! Progress reporting and cancellation for running FFmpeg commands
+//!
+//! FFmpeg writes `key=value` lines to the `-progress` target and ends each
+//! report with a `progress=continue` or `progress=end` line.
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+use tokio::sync::Notify;
+
+/// Progress report of a running FFmpeg command
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Progress {
+    /// Frames written so far
+    pub frame: u64,
+    /// Current encoding frame rate
+    pub fps: f64,
+    /// Output time written so far, in milliseconds
+    pub out_time: u32,
+    /// Encoding speed relative to real time, if known
+    pub speed: Option<f64>,
+    /// Completion percentage (0.0 to 100.0), if the total duration is known
+    pub percent: Option<f64>,
+    /// Whether this is the final report
+    pub done: bool,
+}
+
+/// Parser turning `-progress` output lines into [`Progress`] reports
+#[derive(Debug, Clone, Default)]
+pub struct ProgressParser {
+    /// Total output duration in milliseconds
+    total_duration: Option<u32>,
+    current: Progress,
+}
+
+impl ProgressParser {
+    /// Create a parser; percentages need the total output duration in milliseconds
+    pub fn new(total_duration: Option<u32>) -> Self {
+        Self {
+            total_duration: total_duration.filter(|d| *d > 0),
+            current: Progress::default(),
+        }
+    }
+
+    /// Feed one output line, returning a report when one is complete
+    pub fn feed_line(&mut self, line: &str) -> Option<Progress> {
+        let (key, value) = line.trim().split_once('=')?;
+        let value = value.trim();
+        match key {
+            "frame" => self.current.frame = value.parse().unwrap_or(self.current.frame),
+            "fps" => self.current.fps = value.parse().unwrap_or(self.current.fps),
+            // `out_time_ms` is in microseconds as well
+            "out_time_us" | "out_time_ms" => {
+                if let Ok(micros) = value.parse::<u64>() {
+                    self.current.out_time = (micros / 1000) as u32;
+                }
+            }
+            "out_time" => {
+                if let Some(millis) = parse_time(value) {
+                    self.current.out_time = millis;
+                }
+            }
+            "speed" => self.current.speed = value.trim_end_matches('x').trim().parse().ok(),
+            "progress" => {
+                let done = value == "end";
+                self.current.done = done;
+                self.current.percent = self.total_duration.map(|total| {
+                    if done {
+                        100.0
+                    } else {
+                        (self.current.out_time as f64 / total as f64 * 100.0).min(100.0)
+                    }
+                });
+                return Some(self.current.clone());
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+/// Parse `HH:MM:SS.micros` into milliseconds
+fn parse_time(value: &str) -> Option<u32> {
+    let mut parts = value.splitn(3, ':');
+    let hours: f64 = parts.next()?.parse().ok()?;
+    let minutes: f64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    let millis = ((hours * 60.0 + minutes) * 60.0 + seconds) * 1000.0;
+    (millis >= 0.0).then_some(millis.round() as u32)
+}
+
+/// Handle cancelling a running FFmpeg command.
+///
+/// Clones share the same state, so one clone can be handed to the runner
+/// and another kept by the caller.
+#[derive(Debug, Clone, Default)]
+pub struct CancelHandle {
+    cancelled: Arc<AtomicBool>,
+    notify: Arc<Notify>,
+}
+
+impl CancelHandle {
+    /// Create a new handle
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    /// Check if cancellation was requested
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Wait until cancellation is requested
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_parser() {
+        let mut parser = ProgressParser::new(Some(10_000));
+        let lines = [
+            "frame=120",
+            "fps=59.94",
+            "out_time_us=2500000",
+            "out_time=00:00:02.500000",
+            "speed=1.98x",
+        ];
+        for line in lines {
+            assert_eq!(parser.feed_line(line), None);
+        }
+
+        let progress = parser.feed_line("progress=continue").unwrap();
+        assert_eq!(progress.frame, 120);
+        assert_eq!(progress.out_time, 2500);
+        assert_eq!(progress.speed, Some(1.98));
+        assert_eq!(progress.percent, Some(25.0));
+        assert!(!progress.done);
+
+        parser.feed_line("speed=N/A");
+        let progress = parser.feed_line("progress=end").unwrap();
+        assert_eq!(progress.speed, None);
+        assert_eq!(progress.percent, Some(100.0));
+        assert!(progress.done);
+
+        assert_eq!(parse_time("01:02:03.5"), Some(3_723_500));
+        assert_eq!(
+            ProgressParser::new(None)
+                .feed_line("progress=end")
+                .unwrap()
+                .percent,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancel_handle() {
+        let handle = CancelHandle::new();
+        let waiter = handle.clone();
+        let task = tokio::spawn(async move { waiter.cancelled().await });
+
+        handle.cancel();
+        task.await.unwrap();
+        assert!(handle.is_cancelled());
+
+        // Waiting after cancellation returns immediately
+        handle.cancelled().await;
+    }
+}
@@ -0,0 +1,494 @@
+//! Watermarks and logo removal
+//!
+//! Image or text watermarks are anchored to a corner of the video, and
+//! delogo regions blur out existing logos; both can be limited to time
+//! windows.
+
+use crate::error::{CutError, Result};
+use crate::ffmpeg::{
+    FFmpeg,
+    filter::{Filter, util},
+    input::Input,
+    stream::Stream,
+};
+use serde::{Deserialize, Serialize};
+
+/// Corner a watermark is anchored to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Corner {
+    /// Top left corner
+    TopLeft,
+    /// Top right corner
+    TopRight,
+    /// Bottom left corner
+    BottomLeft,
+    /// Bottom right corner
+    #[default]
+    BottomRight,
+    /// Centre of the video, margins offset from it
+    Center,
+}
+
+/// A time window in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeWindow {
+    /// Start time in milliseconds
+    pub start: u32,
+    /// End time in milliseconds
+    pub end: u32,
+}
+
+/// What a watermark shows
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum WatermarkContent {
+    /// An image file
+    Image {
+        /// Image path
+        path: String,
+    },
+    /// A line of text
+    Text {
+        /// Text content
+        content: String,
+        /// Font file path or fontconfig family name
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        font: Option<String>,
+        /// Font size in pixels, used without a relative scale
+        #[serde(default = "default_font_size")]
+        font_size: u32,
+        /// Font color
+        #[serde(default = "default_color")]
+        color: String,
+    },
+}
+
+/// An image or text watermark
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Watermark {
+    /// What the watermark shows
+    pub content: WatermarkContent,
+    /// Corner the watermark is anchored to
+    #[serde(default)]
+    pub corner: Corner,
+    /// Horizontal distance from the corner in pixels
+    #[serde(default)]
+    pub margin_x: i32,
+    /// Vertical distance from the corner in pixels
+    #[serde(default)]
+    pub margin_y: i32,
+    /// Size relative to the video width: the image width, or the font size
+    /// of text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<f32>,
+    /// Opacity (0.0 to 1.0)
+    #[serde(default = "default_opacity")]
+    pub opacity: f32,
+    /// Windows the watermark shows in; empty shows it throughout
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub windows: Vec<TimeWindow>,
+}
+
+/// A rectangle to remove a logo from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DelogoRegion {
+    /// X offset in pixels
+    pub x: i32,
+    /// Y offset in pixels
+    pub y: i32,
+    /// Width in pixels
+    pub width: i32,
+    /// Height in pixels
+    pub height: i32,
+    /// Windows the logo shows in; empty covers the whole video
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub windows: Vec<TimeWindow>,
+}
+
+fn default_font_size() -> u32 {
+    24
+}
+
+fn default_color() -> String {
+    "white".to_string()
+}
+
+fn default_opacity() -> f32 {
+    1.0
+}
+
+impl Corner {
+    /// Get the position name used by `util::logo_position`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Corner::TopLeft => "top-left",
+            Corner::TopRight => "top-right",
+            Corner::BottomLeft => "bottom-left",
+            Corner::BottomRight => "bottom-right",
+            Corner::Center => "center",
+        }
+    }
+}
+
+impl TimeWindow {
+    /// Create a window
+    pub fn new(start: u32, end: u32) -> Self {
+        Self { start, end }
+    }
+
+    /// Clip the window to `start..end` and shift it to begin at `start`,
+    /// `None` when it falls outside
+    pub fn clip_to(&self, start: u32, end: u32) -> Option<Self> {
+        let clipped_start = self.start.max(start);
+        let clipped_end = self.end.min(end);
+        (clipped_start < clipped_end).then(|| Self::new(clipped_start - start, clipped_end - start))
+    }
+}
+
+impl Watermark {
+    /// Create an image watermark in the bottom right corner
+    pub fn image<S: Into<String>>(path: S) -> Self {
+        Self::with_content(WatermarkContent::Image { path: path.into() })
+    }
+
+    /// Create a text watermark in the bottom right corner
+    pub fn text<S: Into<String>>(content: S) -> Self {
+        Self::with_content(WatermarkContent::Text {
+            content: content.into(),
+            font: None,
+            font_size: default_font_size(),
+            color: default_color(),
+        })
+    }
+
+    fn with_content(content: WatermarkContent) -> Self {
+        Self {
+            content,
+            corner: Corner::default(),
+            margin_x: 0,
+            margin_y: 0,
+            scale: None,
+            opacity: default_opacity(),
+            windows: Vec::new(),
+        }
+    }
+
+    /// Anchor to a corner with margins in pixels
+    pub fn anchor(mut self, corner: Corner, margin_x: i32, margin_y: i32) -> Self {
+        self.corner = corner;
+        self.margin_x = margin_x;
+        self.margin_y = margin_y;
+        self
+    }
+
+    /// Size the watermark relative to the video width
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.scale = Some(scale);
+        self
+    }
+
+    /// Set the opacity
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    /// Show the watermark between `start` and `end` milliseconds
+    pub fn window(mut self, start: u32, end: u32) -> Self {
+        self.windows.push(TimeWindow::new(start, end));
+        self
+    }
+
+    /// Clip the windows to `start..end`, shifted to begin at `start`; `None`
+    /// when the watermark does not show in that part
+    pub fn clip_to(&self, start: u32, end: u32) -> Option<Self> {
+        let windows = clip_windows(&self.windows, start, end)?;
+        Some(Self {
+            windows,
+            ..self.clone()
+        })
+    }
+
+    /// Set the font, size and color of a text watermark
+    pub fn font_style<S: Into<String>>(
+        mut self,
+        font: Option<String>,
+        size: u32,
+        color: S,
+    ) -> Self {
+        if let WatermarkContent::Text {
+            font: ref mut f,
+            font_size: ref mut s,
+            color: ref mut c,
+            ..
+        } = self.content
+        {
+            *f = font;
+            *s = size;
+            *c = color.into();
+        }
+        self
+    }
+
+    /// Validate the watermark
+    pub fn validate(&self) -> Result<()> {
+        match &self.content {
+            WatermarkContent::Image { path } if path.is_empty() => {
+                return Err(CutError::missing_param("watermark image"));
+            }
+            WatermarkContent::Text { content, .. } if content.is_empty() => {
+                return Err(CutError::missing_param("watermark text"));
+            }
+            _ => {}
+        }
+        if self.scale.is_some_and(|s| !(s > 0.0 && s <= 1.0)) {
+            return Err(CutError::invalid_params(
+                "Watermark scale must be between 0.0 and 1.0",
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.opacity) {
+            return Err(CutError::invalid_params(
+                "Watermark opacity must be between 0.0 and 1.0",
+            ));
+        }
+        validate_windows(&self.windows)
+    }
+
+    /// Draw the watermark onto `video`
+    pub fn apply(&self, ffmpeg: &mut FFmpeg, video: Stream) -> Stream {
+        let enable = enable_expression(&self.windows);
+        match &self.content {
+            WatermarkContent::Image { path } => {
+                let input = ffmpeg.add_input(Input::with_simple(path));
+                let mut logo = input.v();
+                let mut main = video;
+                if let Some(scale) = self.scale {
+                    // Scale the logo to the main width, scale2ref outputs both videos
+                    let outputs = ffmpeg.add_filter_with_outputs(
+                        Filter::with_name("scale2ref")
+                            .params([format!("w=main_w*{}", scale), "h=ow/mdar".to_string()]),
+                        [logo, main],
+                        2,
+                    );
+                    logo = outputs[0].clone();
+                    main = outputs[1].clone();
+                }
+                if self.opacity < 1.0 {
+                    logo = ffmpeg.add_filter(Filter::format("rgba"), [logo]);
+                    logo = ffmpeg.add_filter(Filter::alpha(self.opacity), [logo]);
+                }
+
+                let mut overlay = Filter::with_name("overlay").param(util::logo_position(
+                    self.margin_x,
+                    self.margin_y,
+                    self.corner.as_str(),
+                ));
+                if let Some(enable) = enable {
+                    overlay = overlay.param(format!("enable={}", enable));
+                }
+                ffmpeg.add_filter(overlay, [main, logo])
+            }
+            WatermarkContent::Text {
+                content,
+                font,
+                font_size,
+                color,
+            } => {
+                let (dx, dy) = (self.margin_x, self.margin_y);
+                let (x, y) = match self.corner {
+                    Corner::TopLeft => (dx.to_string(), dy.to_string()),
+                    Corner::TopRight => (format!("w-text_w-{}", dx), dy.to_string()),
+                    Corner::BottomLeft => (dx.to_string(), format!("h-text_h-{}", dy)),
+                    Corner::BottomRight => (format!("w-text_w-{}", dx), format!("h-text_h-{}", dy)),
+                    Corner::Center => (
+                        format!("(w-text_w)/2+{}", dx),
+                        format!("(h-text_h)/2+{}", dy),
+                    ),
+                };
+                let size = match self.scale {
+                    Some(scale) => format!("'w*{}'", scale),
+                    None => font_size.to_string(),
+                };
+
+                let mut filter = Filter::drawtext(content);
+                if let Some(font) = font {
+                    filter = filter.param(util::font_option(font));
+                }
+                filter = filter.params([
+                    format!("fontsize={}", size),
+                    format!("fontcolor={}", util::escape_value(color)),
+                    format!("x={}", x),
+                    format!("y={}", y),
+                ]);
+                if self.opacity < 1.0 {
+                    filter = filter.param(format!("alpha={}", self.opacity));
+                }
+                if let Some(enable) = enable {
+                    filter = filter.param(format!("enable={}", enable));
+                }
+                ffmpeg.add_filter(filter, [video])
+            }
+        }
+    }
+}
+
+impl DelogoRegion {
+    /// Create a region covering the whole video
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+            windows: Vec::new(),
+        }
+    }
+
+    /// Remove the logo between `start` and `end` milliseconds
+    pub fn window(mut self, start: u32, end: u32) -> Self {
+        self.windows.push(TimeWindow::new(start, end));
+        self
+    }
+
+    /// Clip the windows to `start..end`, shifted to begin at `start`; `None`
+    /// when the logo does not show in that part
+    pub fn clip_to(&self, start: u32, end: u32) -> Option<Self> {
+        let windows = clip_windows(&self.windows, start, end)?;
+        Some(Self {
+            windows,
+            ..self.clone()
+        })
+    }
+
+    /// Validate the region
+    pub fn validate(&self) -> Result<()> {
+        if self.x < 0 || self.y < 0 {
+            return Err(CutError::invalid_params(
+                "Delogo region must be inside the video",
+            ));
+        }
+        if self.width <= 0 || self.height <= 0 {
+            return Err(CutError::invalid_params(
+                "Delogo region size must be positive",
+            ));
+        }
+        validate_windows(&self.windows)
+    }
+
+    /// Remove the logo from `video`
+    pub fn apply(&self, ffmpeg: &mut FFmpeg, video: Stream) -> Stream {
+        let mut filter = Filter::delogo(self.x, self.y, self.width, self.height);
+        if let Some(enable) = enable_expression(&self.windows) {
+            filter = filter.param(format!("enable={}", enable));
+        }
+        ffmpeg.add_filter(filter, [video])
+    }
+}
+
+/// Clip a set of windows; an empty set covers everything and stays empty,
+/// while `None` tells that none of the windows overlaps
+fn clip_windows(windows: &[TimeWindow], start: u32, end: u32) -> Option<Vec<TimeWindow>> {
+    if windows.is_empty() {
+        return Some(Vec::new());
+    }
+    let clipped: Vec<TimeWindow> = windows
+        .iter()
+        .filter_map(|w| w.clip_to(start, end))
+        .collect();
+    (!clipped.is_empty()).then_some(clipped)
+}
+
+fn validate_windows(windows: &[TimeWindow]) -> Result<()> {
+    if windows.iter().any(|w| w.end <= w.start) {
+        return Err(CutError::invalid_params(
+            "Time window must end after it starts",
+        ));
+    }
+    Ok(())
+}
+
+/// Build the timeline `enable` expression of a set of windows
+fn enable_expression(windows: &[TimeWindow]) -> Option<String> {
+    if windows.is_empty() {
+        return None;
+    }
+    let ranges: Vec<String> = windows
+        .iter()
+        .map(|w| {
+            format!(
+                "between(t,{},{})",
+                w.start as f64 / 1000.0,
+                w.end as f64 / 1000.0
+            )
+        })
+        .collect();
+    Some(format!("'{}'", ranges.join("+")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter_graph(apply: impl FnOnce(&mut FFmpeg, Stream) -> Stream) -> String {
+        let mut ffmpeg = FFmpeg::new();
+        let input = ffmpeg.add_input(Input::with_simple("in.mp4"));
+        apply(&mut ffmpeg, input.v());
+        let args = ffmpeg.build_args();
+        args[args.iter().position(|a| a == "-filter_complex").unwrap() + 1].clone()
+    }
+
+    #[test]
+    fn test_image_watermark() {
+        let watermark = Watermark::image("logo.png")
+            .anchor(Corner::TopRight, 20, 10)
+            .scale(0.1)
+            .opacity(0.6)
+            .window(0, 5000)
+            .window(10_000, 12_500);
+        let graph = filter_graph(|ffmpeg, video| watermark.apply(ffmpeg, video));
+
+        assert!(graph.contains("[1:v][0:v]scale2ref=w=main_w*0.1:h=ow/mdar"));
+        assert!(graph.contains("colorchannelmixer=aa=0.6"));
+        assert!(graph.contains("overlay=W-w-20:10:enable='between(t,0,5)+between(t,10,12.5)'"));
+    }
+
+    #[test]
+    fn test_text_watermark() {
+        let watermark = Watermark::text("© kiva")
+            .font_style(Some("fonts/a.ttf".to_string()), 30, "yellow")
+            .anchor(Corner::BottomLeft, 16, 16);
+        let graph = filter_graph(|ffmpeg, video| watermark.apply(ffmpeg, video));
+        assert!(graph.contains("drawtext=text=© kiva"));
+        assert!(graph.contains("fontfile=fonts/a.ttf"));
+        assert!(graph.contains("fontsize=30:fontcolor=yellow:x=16:y=h-text_h-16"));
+        assert!(!graph.contains("enable"));
+
+        let graph =
+            filter_graph(|ffmpeg, video| watermark.clone().scale(0.05).apply(ffmpeg, video));
+        assert!(graph.contains("fontsize='w*0.05'"));
+    }
+
+    #[test]
+    fn test_delogo() {
+        let region = DelogoRegion::new(10, 20, 100, 40).window(1000, 3000);
+        let graph = filter_graph(|ffmpeg, video| region.apply(ffmpeg, video));
+        assert!(graph.contains("delogo=x=10:y=20:w=100:h=40:enable='between(t,1,3)'"));
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(Watermark::image("").validate().is_err());
+        assert!(Watermark::text("a").opacity(1.5).validate().is_err());
+        assert!(Watermark::text("a").scale(0.0).validate().is_err());
+        assert!(Watermark::text("a").window(5000, 1000).validate().is_err());
+        assert!(Watermark::image("logo.png").scale(0.2).validate().is_ok());
+        assert!(DelogoRegion::new(0, 0, 0, 10).validate().is_err());
+        assert!(DelogoRegion::new(0, 0, 10, 10).validate().is_ok());
+
+        let json = r#"{"content":{"type":"text","content":"hi"},"corner":"top-left"}"#;
+        let watermark: Watermark = serde_json::from_str(json).unwrap();
+        assert_eq!(watermark.corner, Corner::TopLeft);
+        assert_eq!(watermark.opacity, 1.0);
+    }
+}
@@ -1,6 +1,7 @@
 //! FFprobe module for extracting media information
 
 use crate::error::{CutError, Result};
+use crate::ffmpeg::util::fix_even_number;
 use crate::options::FFprobeOptions;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -80,6 +81,7 @@ pub struct StreamInfo {
     pub nb_read_frames: Option<String>,
     pub nb_read_packets: Option<String>,
     pub tags: Option<HashMap<String, String>>,
+    pub side_data_list: Option<Vec<SideData>>,
     // Audio-specific fields
     pub sample_fmt: Option<String>,
     pub sample_rate: Option<String>,
@@ -89,8 +91,16 @@ pub struct StreamInfo {
     pub initial_padding: Option<i32>,
 }
 
+/// Stream side data, such as the display matrix of phone videos
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SideData {
+    pub side_data_type: Option<String>,
+    /// Counter-clockwise rotation in degrees of a display matrix
+    pub rotation: Option<f64>,
+}
+
 /// Loudnorm analysis parameters
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct LoudnormParams {
     pub input_i: Option<f32>,
     pub input_tp: Option<f32>,
@@ -414,6 +424,50 @@ impl StreamInfo {
         self.nb_frames.as_ref().and_then(|nf| nf.parse().ok())
     }
 
+    /// Get frame rate as float
+    pub fn framerate(&self) -> Option<f64> {
+        let (num, den) = self.r_frame_rate.as_ref()?.split_once('/')?;
+        let (num, den): (f64, f64) = (num.parse().ok()?, den.parse().ok()?);
+        (num > 0.0 && den > 0.0).then(|| num / den)
+    }
+
+    /// Get the clockwise rotation in degrees that turns the frames upright,
+    /// from the display matrix or the legacy `rotate` tag
+    pub fn rotation(&self) -> i32 {
+        let matrix = self
+            .side_data_list
+            .iter()
+            .flatten()
+            .find_map(|data| data.rotation)
+            .map(|rotation| -rotation.round() as i32);
+        let tag = self
+            .tags
+            .as_ref()
+            .and_then(|tags| tags.get("rotate"))
+            .and_then(|rotate| rotate.parse().ok());
+        matrix.or(tag).unwrap_or(0).rem_euclid(360)
+    }
+
+    /// Get the sample aspect ratio; `None` for square or unknown pixels
+    pub fn sar(&self) -> Option<f64> {
+        let (num, den) = self.sample_aspect_ratio.as_ref()?.split_once(':')?;
+        let (num, den): (f64, f64) = (num.parse().ok()?, den.parse().ok()?);
+        (num > 0.0 && den > 0.0 && num != den).then(|| num / den)
+    }
+
+    /// Get the size the frames are shown at: square pixels, turned upright
+    pub fn display_dimensions(&self) -> Option<(i32, i32)> {
+        let (mut width, height) = self.width.zip(self.height)?;
+        if let Some(sar) = self.sar() {
+            width = fix_even_number((width as f64 * sar).round() as i32);
+        }
+        if self.rotation() % 180 == 90 {
+            Some((height, width))
+        } else {
+            Some((width, height))
+        }
+    }
+
     /// Get aspect ratio as float
     pub fn aspect_ratio(&self) -> Option<f64> {
         if let (Some(width), Some(height)) = (self.width, self.height) {
@@ -475,6 +529,7 @@ mod tests {
             duration: Some("60.0".to_string()),
             bit_rate: Some("5000000".to_string()),
             nb_frames: Some("1800".to_string()),
+            r_frame_rate: Some("30000/1001".to_string()),
             ..Default::default()
         };
 
@@ -484,6 +539,45 @@ mod tests {
         assert_eq!(video_stream.bitrate_int(), Some(5000000));
         assert_eq!(video_stream.frame_count(), Some(1800));
         assert_eq!(video_stream.aspect_ratio(), Some(16.0 / 9.0));
+        assert_eq!(video_stream.framerate(), Some(30000.0 / 1001.0));
+        assert_eq!(video_stream.rotation(), 0);
+        assert_eq!(video_stream.display_dimensions(), Some((1920, 1080)));
+    }
+
+    #[test]
+    fn test_display_dimensions() {
+        let json = r#"{
+            "index": 0,
+            "codec_type": "video",
+            "width": 1920,
+            "height": 1080,
+            "sample_aspect_ratio": "1:1",
+            "side_data_list": [
+                {"side_data_type": "Display Matrix", "displaymatrix": "", "rotation": -90}
+            ]
+        }"#;
+        let portrait: StreamInfo = serde_json::from_str(json).unwrap();
+        assert_eq!(portrait.rotation(), 90);
+        assert_eq!(portrait.sar(), None);
+        assert_eq!(portrait.display_dimensions(), Some((1080, 1920)));
+
+        let legacy = StreamInfo {
+            width: Some(1280),
+            height: Some(720),
+            tags: Some(HashMap::from([("rotate".to_string(), "270".to_string())])),
+            ..Default::default()
+        };
+        assert_eq!(legacy.rotation(), 270);
+        assert_eq!(legacy.display_dimensions(), Some((720, 1280)));
+
+        // Anamorphic DV: 720x576 shown at 16:9
+        let anamorphic = StreamInfo {
+            width: Some(720),
+            height: Some(576),
+            sample_aspect_ratio: Some("64:45".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(anamorphic.display_dimensions(), Some((1024, 576)));
     }
 }
 
@@ -549,6 +643,7 @@ impl Default for StreamInfo {
             nb_read_frames: None,
             nb_read_packets: None,
             tags: None,
+            side_data_list: None,
             sample_fmt: None,
             sample_rate: None,
             channels: None,
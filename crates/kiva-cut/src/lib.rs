@@ -15,19 +15,29 @@
 //!
 //! ## Example
 //!
+pub mod audio;
 pub mod composer;
 pub mod cut;
 pub mod error;
 pub mod ffmpeg;
 pub mod ffprobe;
+pub mod loudness;
 pub mod options;
 pub mod pkg;
+pub mod queue;
+pub mod screenshot;
+pub mod sprite;
+#[cfg(test)]
+mod test_support;
+pub mod transcode;
+pub mod watermark;
 
 // Re-export main types
 pub use cut::{
-    AudioMaterial, CutProtocol, Dimension, EditSession, Editor, ExportConfig, ExportType,
-    ImageMaterial, Material, MaterialType, Position, Scale, Segment, Stage, TimeRange, Track,
-    TrackType, VideoMaterial,
+    Anchor, AudioMaterial, AudioSettings, ChannelLayout, CropRect, CutProtocol, Dimension,
+    EditSession, Editor, ExportConfig, ExportType, FitMode, FrameRate, ImageMaterial, Material,
+    MaterialType, Position, Scale, Segment, Stage, StageBackground, TextAlign, TextStyle,
+    TimeRange, Track, TrackType, Transform, VideoMaterial,
 };
 pub use error::{CutError, Result};
 pub use options::*;
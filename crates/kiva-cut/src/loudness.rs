@@ -0,0 +1,237 @@
+//! Two-pass loudness normalization of a media file
+//!
+//! A [`LoudnessJob`] measures the audio of its input, then re-encodes the
+//! audio with a linear gain to the target; video streams are copied.
+
+use crate::error::{CutError, Result};
+use crate::ffmpeg::{
+    FFmpeg,
+    codec::{AudioCodec, Format, VideoCodec},
+    filter::Filter,
+    input::Input,
+    loudnorm::{self, LoudnessTarget},
+    output::Output,
+};
+use crate::ffprobe::LoudnormParams;
+use crate::options::FFmpegOptions;
+use std::path::Path;
+
+/// A loudness normalization job from one input file to one output
+#[derive(Debug, Clone)]
+pub struct LoudnessJob {
+    /// Input file path
+    pub input: String,
+    /// Output file path
+    pub output: String,
+    /// Loudness target
+    pub target: LoudnessTarget,
+    /// Audio codec, the container default if not set
+    pub audio_codec: Option<AudioCodec>,
+    /// Audio bitrate in kbps
+    pub audio_bitrate: Option<i32>,
+    /// FFmpeg options
+    pub ffmpeg_options: FFmpegOptions,
+}
+
+impl LoudnessJob {
+    /// Create a job
+    pub fn new<S1: Into<String>, S2: Into<String>>(
+        input: S1,
+        output: S2,
+        target: LoudnessTarget,
+    ) -> Self {
+        Self {
+            input: input.into(),
+            output: output.into(),
+            target,
+            audio_codec: None,
+            audio_bitrate: None,
+            ffmpeg_options: FFmpegOptions::new(),
+        }
+    }
+
+    /// Set the audio codec
+    pub fn audio_codec(mut self, codec: AudioCodec) -> Self {
+        self.audio_codec = Some(codec);
+        self
+    }
+
+    /// Set the audio bitrate in kbps
+    pub fn audio_bitrate(mut self, bitrate: i32) -> Self {
+        self.audio_bitrate = Some(bitrate);
+        self
+    }
+
+    /// Set FFmpeg options
+    pub fn set_ffmpeg_options(mut self, options: FFmpegOptions) -> Self {
+        self.ffmpeg_options = options;
+        self
+    }
+
+    /// Validate the job settings
+    pub fn validate(&self) -> Result<()> {
+        if self.input.is_empty() {
+            return Err(CutError::missing_param("input"));
+        }
+        if self.output.is_empty() {
+            return Err(CutError::missing_param("output"));
+        }
+        if let Some(codec) = &self.audio_codec
+            && !codec.requires_encoding()
+        {
+            return Err(CutError::invalid_params(
+                "Loudness normalization needs the audio to be re-encoded",
+            ));
+        }
+        self.target.validate()
+    }
+
+    /// Build the analysis pass
+    pub fn build_analysis(&self) -> Result<FFmpeg> {
+        self.validate()?;
+
+        let mut ffmpeg = FFmpeg::new();
+        ffmpeg.set_ffmpeg_options(loudnorm::analysis_options(&self.ffmpeg_options));
+        let input = ffmpeg.add_input(Input::with_simple(&self.input));
+        let measured = ffmpeg.add_filter(self.target.analyze_filter(), [input.a()]);
+        ffmpeg.add_output(loudnorm::null_output(measured));
+        Ok(ffmpeg)
+    }
+
+    /// Build the normalization pass with the measured values
+    pub fn build(&self, measured: &LoudnormParams) -> Result<FFmpeg> {
+        self.build_pass(Some(measured))
+    }
+
+    /// Build the normalization pass, a single dynamic one without measured
+    /// values
+    fn build_pass(&self, measured: Option<&LoudnormParams>) -> Result<FFmpeg> {
+        self.validate()?;
+
+        let mut ffmpeg = FFmpeg::new();
+        ffmpeg.set_ffmpeg_options(self.ffmpeg_options.clone());
+        let input = ffmpeg.add_input(Input::with_simple(&self.input));
+
+        let mut output = Output::with_simple(&self.output);
+        let is_video = Path::new(&self.output)
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| Format::from(e).is_video());
+        if is_video {
+            output = output
+                .map_stream(input.may_v())
+                .video_codec(VideoCodec::Copy);
+        }
+
+        let normalized = ffmpeg.add_filter(self.target.normalize_filter(measured)?, [input.a()]);
+        let resampled =
+            ffmpeg.add_filter(Filter::resample(loudnorm::OUTPUT_SAMPLE_RATE), [normalized]);
+        output = output.map_stream(resampled);
+        if let Some(codec) = &self.audio_codec {
+            output = output.audio_codec(codec.clone());
+        }
+        if let Some(bitrate) = self.audio_bitrate {
+            output = output.audio_bitrate(bitrate);
+        }
+        ffmpeg.add_output(output);
+        Ok(ffmpeg)
+    }
+
+    /// Measure the input loudness
+    pub async fn analyze(&self) -> Result<LoudnormParams> {
+        let log = self.build_analysis()?.run_with_log().await?;
+        loudnorm::parse_log(&log)
+    }
+
+    /// Run both passes and return the measured input loudness.
+    ///
+    /// A dry run has nothing to measure, so it prints a single dynamic pass
+    /// and returns empty values.
+    pub async fn run(&self) -> Result<LoudnormParams> {
+        if self.ffmpeg_options.dry_run {
+            self.build_pass(None)?.run().await?;
+            return Ok(LoudnormParams::default());
+        }
+        let measured = self.analyze().await?;
+        self.build(&measured)?.run().await?;
+        Ok(measured)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measured() -> LoudnormParams {
+        LoudnormParams {
+            input_i: Some(-27.5),
+            input_tp: Some(-4.5),
+            input_lra: Some(18.0),
+            input_thresh: Some(-39.0),
+            output_i: None,
+            output_tp: None,
+            output_lra: None,
+            output_thresh: None,
+            normalization_type: None,
+            target_offset: Some(0.5),
+        }
+    }
+
+    #[test]
+    fn test_passes() {
+        let job = LoudnessJob::new("in.mp4", "out.mp4", LoudnessTarget::broadcast())
+            .audio_codec(AudioCodec::AAC)
+            .audio_bitrate(192);
+
+        let args = job.build_analysis().unwrap().build_args();
+        let line = args.join(" ");
+        assert!(line.starts_with("-v info"));
+        assert!(line.contains("loudnorm=I=-23:TP=-1:LRA=7:print_format=json"));
+        assert!(line.ends_with("-f null -"));
+
+        let line = job.build(&measured()).unwrap().build_args().join(" ");
+        assert!(line.contains("-map 0:v?"));
+        assert!(line.contains("-c:v copy"));
+        assert!(line.contains("measured_I=-27.5"));
+        assert!(line.contains("linear=true"));
+        assert!(line.contains("aresample=48000"));
+        assert!(line.contains("-b:a 192k"));
+
+        let job = LoudnessJob::new("in.wav", "out.mp3", LoudnessTarget::streaming());
+        let line = job.build(&measured()).unwrap().build_args().join(" ");
+        assert!(!line.contains("0:v"));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run() {
+        let job = LoudnessJob::new("in.mp4", "out.mp4", LoudnessTarget::broadcast())
+            .set_ffmpeg_options(FFmpegOptions::new().dry_run(true));
+        let measured = job.run().await.unwrap();
+        assert!(measured.input_i.is_none());
+
+        let line = job.build_pass(None).unwrap().build_args().join(" ");
+        assert!(line.contains("loudnorm=I=-23:TP=-1:LRA=7"));
+        assert!(!line.contains("measured_I"));
+    }
+
+    #[test]
+    fn test_validation() {
+        let target = LoudnessTarget::streaming();
+        assert!(
+            LoudnessJob::new("", "out.mp3", target.clone())
+                .validate()
+                .is_err()
+        );
+        assert!(
+            LoudnessJob::new("in.mp4", "out.mp4", target.clone())
+                .audio_codec(AudioCodec::Copy)
+                .validate()
+                .is_err()
+        );
+        let mut missing = measured();
+        missing.input_thresh = None;
+        let job = LoudnessJob::new("in.mp4", "out.mp4", target);
+        assert!(job.build(&missing).is_err());
+        assert!(job.validate().is_ok());
+    }
+}
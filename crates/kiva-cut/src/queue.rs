@@ -0,0 +1,307 @@
+//! Batch runner for many FFmpeg and FFprobe jobs
+//!
+//! A [`JobQueue`] collects jobs with priorities and retry counts, then runs
+//! them on tokio with at most `max_parallel` processes at a time. Results
+//! arrive in completion order on a [`JobStream`]; a failed job is reported
+//! there and does not stop the rest of the batch.
+
+use crate::error::{CutError, Result};
+use crate::ffmpeg::FFmpeg;
+use crate::ffprobe::{FFprobe, MediaInfo};
+use std::sync::Arc;
+use tokio::sync::{Semaphore, mpsc};
+
+/// Identifier of a queued job, unique within its queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JobId(pub usize);
+
+/// A job the queue can run
+#[derive(Debug)]
+pub enum Job {
+    /// An FFmpeg command
+    FFmpeg(FFmpeg),
+    /// An FFprobe command
+    FFprobe(FFprobe),
+}
+
+/// What a successful job produced
+#[derive(Debug, Clone)]
+pub enum JobOutput {
+    /// FFmpeg log, which carries the results of analysis filters
+    Log(String),
+    /// Probed media information
+    Probe(Box<MediaInfo>),
+}
+
+/// Result of one job
+#[derive(Debug)]
+pub struct JobResult {
+    /// Job identifier
+    pub id: JobId,
+    /// Number of times the job ran
+    pub attempts: u32,
+    /// Output of the last attempt
+    pub result: Result<JobOutput>,
+}
+
+/// Results of a job queue in completion order
+#[derive(Debug)]
+pub struct JobStream {
+    receiver: mpsc::UnboundedReceiver<JobResult>,
+}
+
+/// Queue of jobs run with a concurrency limit
+#[derive(Debug)]
+pub struct JobQueue {
+    max_parallel: usize,
+    retries: u32,
+    jobs: Vec<QueuedJob>,
+}
+
+#[derive(Debug)]
+struct QueuedJob {
+    id: JobId,
+    priority: i32,
+    retries: u32,
+    job: Job,
+}
+
+impl From<FFmpeg> for Job {
+    fn from(ffmpeg: FFmpeg) -> Self {
+        Job::FFmpeg(ffmpeg)
+    }
+}
+
+impl From<FFprobe> for Job {
+    fn from(ffprobe: FFprobe) -> Self {
+        Job::FFprobe(ffprobe)
+    }
+}
+
+impl Job {
+    /// Run the job once
+    pub async fn run(&mut self) -> Result<JobOutput> {
+        match self {
+            Job::FFmpeg(ffmpeg) => ffmpeg.run_with_log().await.map(JobOutput::Log),
+            Job::FFprobe(ffprobe) => ffprobe
+                .run()
+                .await
+                .map(|info| JobOutput::Probe(Box::new(info))),
+        }
+    }
+}
+
+impl JobResult {
+    /// Check if the job succeeded
+    pub fn is_ok(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+impl JobStream {
+    /// Wait for the next finished job; `None` once every job has reported
+    pub async fn next(&mut self) -> Option<JobResult> {
+        self.receiver.recv().await
+    }
+
+    /// Wait for all remaining jobs
+    pub async fn collect(mut self) -> Vec<JobResult> {
+        let mut results = Vec::new();
+        while let Some(result) = self.next().await {
+            results.push(result);
+        }
+        results
+    }
+}
+
+impl JobQueue {
+    /// Create a queue running at most `max_parallel` jobs at a time
+    pub fn new(max_parallel: usize) -> Self {
+        Self {
+            max_parallel,
+            retries: 0,
+            jobs: Vec::new(),
+        }
+    }
+
+    /// Set the retry count of jobs added afterwards with `push`
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Add a job with the default priority 0
+    pub fn push<J: Into<Job>>(&mut self, job: J) -> JobId {
+        self.push_with(job, 0, self.retries)
+    }
+
+    /// Add a job; higher priorities start first, and a failed job is run
+    /// again up to `retries` times
+    pub fn push_with<J: Into<Job>>(&mut self, job: J, priority: i32, retries: u32) -> JobId {
+        let id = JobId(self.jobs.len());
+        self.jobs.push(QueuedJob {
+            id,
+            priority,
+            retries,
+            job: job.into(),
+        });
+        id
+    }
+
+    /// Get the number of queued jobs
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    /// Check if no job is queued
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    /// Start the jobs and return their results as they finish.
+    ///
+    /// Must be called inside a tokio runtime.
+    pub fn run(self) -> Result<JobStream> {
+        if self.max_parallel == 0 {
+            return Err(CutError::invalid_params(
+                "Job queue needs at least one parallel job",
+            ));
+        }
+
+        let mut jobs = self.jobs;
+        // Stable sort: jobs of equal priority run in the order they were added
+        jobs.sort_by_key(|job| std::cmp::Reverse(job.priority));
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let permits = Arc::new(Semaphore::new(self.max_parallel));
+        tokio::spawn(async move {
+            for mut queued in jobs {
+                let Ok(permit) = permits.clone().acquire_owned().await else {
+                    break;
+                };
+                let sender = sender.clone();
+                tokio::spawn(async move {
+                    let mut attempts = 0;
+                    let result = loop {
+                        attempts += 1;
+                        match queued.job.run().await {
+                            Err(CutError::Cancelled) => break Err(CutError::Cancelled),
+                            Err(_) if attempts <= queued.retries => continue,
+                            result => break result,
+                        }
+                    };
+                    drop(permit);
+                    let _ = sender.send(JobResult {
+                        id: queued.id,
+                        attempts,
+                        result,
+                    });
+                });
+            }
+        });
+
+        Ok(JobStream { receiver })
+    }
+
+    /// Run every job and wait for all results, in completion order
+    pub async fn run_all(self) -> Result<Vec<JobResult>> {
+        Ok(self.run()?.collect().await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffmpeg::output::Output;
+    use crate::options::{FFmpegOptions, FFprobeOptions};
+    #[cfg(unix)]
+    use crate::test_support::fake_binary;
+
+    #[cfg(unix)]
+    fn ffmpeg_job(binary: &str, output: &str) -> FFmpeg {
+        let mut ffmpeg = FFmpeg::new();
+        ffmpeg.set_ffmpeg_options(FFmpegOptions::new().binary_path(binary));
+        ffmpeg.add_output(Output::with_simple(output));
+        ffmpeg
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_priorities_and_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("order.log");
+        let binary = fake_binary(
+            dir.path(),
+            "ffmpeg",
+            &format!(
+                "for last; do :; done\necho $last >> {}\n[ $last != bad ]",
+                log.display()
+            ),
+        );
+
+        let mut queue = JobQueue::new(1);
+        let low = queue.push_with(ffmpeg_job(&binary, "low"), -1, 0);
+        let bad = queue.push(ffmpeg_job(&binary, "bad"));
+        let normal = queue.push(ffmpeg_job(&binary, "normal"));
+        let high = queue.push_with(ffmpeg_job(&binary, "high"), 5, 0);
+        assert_eq!(queue.len(), 4);
+
+        let results = queue.run_all().await.unwrap();
+        let order: Vec<JobId> = results.iter().map(|r| r.id).collect();
+        assert_eq!(order, vec![high, bad, normal, low]);
+        assert_eq!(
+            std::fs::read_to_string(&log).unwrap(),
+            "high\nbad\nnormal\nlow\n"
+        );
+        assert!(!results[1].is_ok());
+        assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 3);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_retries_and_probes() {
+        let dir = tempfile::tempdir().unwrap();
+        let counter = dir.path().join("attempts");
+        let flaky = fake_binary(
+            dir.path(),
+            "ffmpeg",
+            &format!(
+                "n=$(cat {0} 2>/dev/null || echo 0)\nn=$((n+1))\necho $n > {0}\necho attempt $n >&2\n[ $n -ge 3 ]",
+                counter.display()
+            ),
+        );
+        let ffprobe = fake_binary(
+            dir.path(),
+            "ffprobe",
+            r#"echo '{"streams":[],"format":{"duration":"1.5"}}'"#,
+        );
+
+        let mut queue = JobQueue::new(2).retries(2);
+        let flaky_id = queue.push(ffmpeg_job(&flaky, "out.mp4"));
+        let probe_id = queue.push(
+            FFprobe::new()
+                .set_options(FFprobeOptions::new().binary_path(ffprobe))
+                .input("in.mp4"),
+        );
+
+        let mut stream = queue.run().unwrap();
+        let mut seen = Vec::new();
+        while let Some(result) = stream.next().await {
+            match result.result.unwrap() {
+                JobOutput::Log(log) => {
+                    assert_eq!(result.id, flaky_id);
+                    assert_eq!(result.attempts, 3);
+                    assert!(log.contains("attempt 3"));
+                }
+                JobOutput::Probe(info) => {
+                    assert_eq!(result.id, probe_id);
+                    assert_eq!(info.duration_seconds(), Some(1.5));
+                }
+            }
+            seen.push(result.id);
+        }
+        assert_eq!(seen.len(), 2);
+
+        assert!(JobQueue::new(0).run().is_err());
+    }
+}
@@ -0,0 +1,536 @@
+//! Screenshot and thumbnail extraction
+//!
+//! A [`ScreenshotJob`] writes numbered images into an output directory and
+//! returns each image path with the timestamp of the frame it shows.
+
+use crate::error::{CutError, Result};
+use crate::ffmpeg::{
+    FFmpeg,
+    codec::{Format, VideoCodec},
+    filter::{Filter, util},
+    input::Input,
+    output::Output,
+    stream::Stream,
+};
+use crate::ffprobe::{FFprobe, MediaInfo};
+use crate::options::{FFmpegOptions, FFprobeOptions};
+use std::path::PathBuf;
+
+/// Which frames to extract
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScreenshotMode {
+    /// A single frame at a timestamp in milliseconds
+    Single(u32),
+    /// N evenly spaced frames
+    Count(u32),
+    /// One frame every N milliseconds
+    Interval(u32),
+    /// Keyframes only
+    Keyframes,
+    /// Frames whose scene change score exceeds the threshold (0.0 to 1.0)
+    SceneChange(f64),
+}
+
+/// Image format of the screenshots
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageFormat {
+    #[default]
+    Jpeg,
+    Png,
+    Webp,
+}
+
+/// A generated screenshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Screenshot {
+    /// Image file path
+    pub path: PathBuf,
+    /// Frame timestamp in milliseconds
+    pub timestamp: u32,
+}
+
+/// A screenshot extraction job
+#[derive(Debug, Clone)]
+pub struct ScreenshotJob {
+    /// Input file path
+    pub input: String,
+    /// Directory the images are written to
+    pub output_dir: PathBuf,
+    /// File name prefix, images are named `{prefix}_0001.jpg` and so on
+    pub prefix: String,
+    /// Which frames to extract
+    pub mode: ScreenshotMode,
+    /// Image format
+    pub format: ImageFormat,
+    /// Maximum image size; larger frames are shrunk keeping the aspect ratio
+    pub max_size: Option<(i32, i32)>,
+    /// FFmpeg options
+    pub ffmpeg_options: FFmpegOptions,
+    /// FFprobe options
+    pub ffprobe_options: FFprobeOptions,
+}
+
+impl ScreenshotMode {
+    /// Check if the mode needs the input duration and frame rate
+    fn needs_probe(&self) -> bool {
+        matches!(self, ScreenshotMode::Count(_))
+    }
+}
+
+impl ImageFormat {
+    /// Get the file extension
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Png => "png",
+            ImageFormat::Webp => "webp",
+        }
+    }
+
+    /// Get the image encoder
+    pub fn codec(&self) -> VideoCodec {
+        match self {
+            ImageFormat::Jpeg => VideoCodec::MJPEG,
+            ImageFormat::Png => VideoCodec::PNG,
+            ImageFormat::Webp => VideoCodec::custom("libwebp"),
+        }
+    }
+}
+
+impl ScreenshotJob {
+    /// Create a job writing JPEG images into `output_dir`
+    pub fn new<S: Into<String>, P: Into<PathBuf>>(
+        input: S,
+        output_dir: P,
+        mode: ScreenshotMode,
+    ) -> Self {
+        Self {
+            input: input.into(),
+            output_dir: output_dir.into(),
+            prefix: "frame".to_string(),
+            mode,
+            format: ImageFormat::default(),
+            max_size: None,
+            ffmpeg_options: FFmpegOptions::new(),
+            ffprobe_options: FFprobeOptions::new(),
+        }
+    }
+
+    /// Set the file name prefix
+    pub fn prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Set the image format
+    pub fn format(mut self, format: ImageFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Set the maximum image size
+    pub fn max_size(mut self, width: i32, height: i32) -> Self {
+        self.max_size = Some((width, height));
+        self
+    }
+
+    /// Set FFmpeg options
+    pub fn set_ffmpeg_options(mut self, options: FFmpegOptions) -> Self {
+        self.ffmpeg_options = options;
+        self
+    }
+
+    /// Set FFprobe options
+    pub fn set_ffprobe_options(mut self, options: FFprobeOptions) -> Self {
+        self.ffprobe_options = options;
+        self
+    }
+
+    /// Validate the job settings
+    pub fn validate(&self) -> Result<()> {
+        if self.input.is_empty() {
+            return Err(CutError::missing_param("input"));
+        }
+        if self.prefix.is_empty() {
+            return Err(CutError::missing_param("prefix"));
+        }
+        match self.mode {
+            ScreenshotMode::Count(0) => {
+                return Err(CutError::invalid_params(
+                    "Screenshot count must be positive",
+                ));
+            }
+            ScreenshotMode::Interval(0) => {
+                return Err(CutError::invalid_params(
+                    "Screenshot interval must be positive",
+                ));
+            }
+            ScreenshotMode::SceneChange(threshold) if !(threshold > 0.0 && threshold < 1.0) => {
+                return Err(CutError::invalid_params(
+                    "Scene change threshold must be between 0.0 and 1.0",
+                ));
+            }
+            _ => {}
+        }
+        if let Some((width, height)) = self.max_size
+            && (width <= 0 || height <= 0)
+        {
+            return Err(CutError::invalid_params("Max size must be positive"));
+        }
+        Ok(())
+    }
+
+    /// Build the FFmpeg command; `Count` mode needs the probed input
+    pub fn build(&self, info: Option<&MediaInfo>) -> Result<FFmpeg> {
+        self.validate()?;
+
+        let mut ffmpeg = FFmpeg::new();
+        ffmpeg.set_ffmpeg_options(self.ffmpeg_options.clone());
+
+        let mut input = Input::with_simple(&self.input);
+        match self.mode {
+            ScreenshotMode::Single(time) => input = input.start_time(time as f32 / 1000.0),
+            // Decode keyframes only
+            ScreenshotMode::Keyframes => input = input.option("skip_frame", "nokey"),
+            _ => {}
+        }
+        let input = ffmpeg.add_input(input);
+
+        let mut stream: Stream = input.v();
+        match self.mode {
+            ScreenshotMode::Single(_) => {}
+            ScreenshotMode::Count(_) => {
+                let (frames, _) = self.planned_frames(info)?;
+                let select = Filter::select(format!("'{}'", util::frame_expression(&frames)));
+                stream = ffmpeg.add_filter(select, [stream]);
+            }
+            ScreenshotMode::Interval(_)
+            | ScreenshotMode::Keyframes
+            | ScreenshotMode::SceneChange(_) => {
+                if let ScreenshotMode::Interval(interval) = self.mode {
+                    stream = ffmpeg.add_filter(Filter::fps(format!("1000/{}", interval)), [stream]);
+                }
+                // Referencing `scene` makes select tag every frame with
+                // lavfi.scene_score, without which metadata prints nothing
+                let expression = match self.mode {
+                    ScreenshotMode::SceneChange(threshold) => format!("gt(scene,{})", threshold),
+                    _ => "gte(scene,0)".to_string(),
+                };
+                stream = ffmpeg.add_filter(Filter::select(format!("'{}'", expression)), [stream]);
+                let log = self.frame_log_path();
+                stream =
+                    ffmpeg.add_filter(Filter::metadata_print(&log.to_string_lossy()), [stream]);
+            }
+        }
+        if let Some((width, height)) = self.max_size {
+            stream = ffmpeg.add_filter(Filter::scale_fit(width, height), [stream]);
+        }
+
+        let pattern =
+            self.output_dir
+                .join(format!("{}_%04d.{}", self.prefix, self.format.extension()));
+        let mut output = Output::with_simple(pattern.to_string_lossy())
+            .map_stream(stream)
+            .video_codec(self.format.codec())
+            .format(Format::IMAGE2);
+        if self.format == ImageFormat::Jpeg {
+            output = output.option("q:v", "2");
+        }
+        output = match self.mode {
+            ScreenshotMode::Single(_) => output.max_frames(1),
+            // Write the selected frames only, without duplicating any
+            _ => output.option("fps_mode", "vfr"),
+        };
+        ffmpeg.add_output(output);
+        Ok(ffmpeg)
+    }
+
+    /// Extract the screenshots, returning them in timestamp order.
+    ///
+    /// Fails without touching anything when images with the job's names are
+    /// already in the output directory. A dry run returns no screenshots.
+    pub async fn run(&self) -> Result<Vec<Screenshot>> {
+        self.validate()?;
+        let info = if self.mode.needs_probe() {
+            Some(
+                FFprobe::new()
+                    .set_options(self.ffprobe_options.clone())
+                    .input(&self.input)
+                    .run()
+                    .await?,
+            )
+        } else {
+            None
+        };
+
+        let mut ffmpeg = self.build(info.as_ref())?;
+        if self.ffmpeg_options.dry_run {
+            ffmpeg.run().await?;
+            return Ok(Vec::new());
+        }
+
+        std::fs::create_dir_all(&self.output_dir)?;
+        // Images with our names may belong to someone else, never overwrite them
+        if let Some(path) = self.conflicting_files()?.into_iter().next() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("{} already exists", path.display()),
+            )
+            .into());
+        }
+
+        ffmpeg.run().await?;
+
+        let timestamps = match self.mode {
+            ScreenshotMode::Single(time) => vec![time],
+            ScreenshotMode::Count(_) => self.planned_frames(info.as_ref())?.1,
+            ScreenshotMode::Interval(_)
+            | ScreenshotMode::Keyframes
+            | ScreenshotMode::SceneChange(_) => {
+                let log = self.frame_log_path();
+                let content = std::fs::read_to_string(&log)?;
+                let _ = std::fs::remove_file(&log);
+                parse_frame_times(&content)
+            }
+        };
+
+        Ok(self
+            .existing_images()
+            .into_iter()
+            .zip(timestamps)
+            .map(|(path, timestamp)| Screenshot { path, timestamp })
+            .collect())
+    }
+
+    /// Image path for a 0-based index
+    fn image_path(&self, index: usize) -> PathBuf {
+        self.output_dir.join(format!(
+            "{}_{:04}.{}",
+            self.prefix,
+            index + 1,
+            self.format.extension()
+        ))
+    }
+
+    /// Consecutively numbered images present in the output directory
+    fn existing_images(&self) -> Vec<PathBuf> {
+        (0..)
+            .map(|i| self.image_path(i))
+            .take_while(|path| path.exists())
+            .collect()
+    }
+
+    /// Files in the output directory the job would overwrite
+    fn conflicting_files(&self) -> Result<Vec<PathBuf>> {
+        let image_prefix = format!("{}_", self.prefix);
+        let image_suffix = format!(".{}", self.format.extension());
+        let log = self.frame_log_path();
+
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(&self.output_dir)? {
+            let path = entry?.path();
+            let is_image = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix(&image_prefix))
+                .and_then(|name| name.strip_suffix(&image_suffix))
+                .is_some_and(|number| {
+                    !number.is_empty() && number.chars().all(|c| c.is_ascii_digit())
+                });
+            if is_image || path == log {
+                files.push(path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// File the selected frame timestamps are printed to
+    fn frame_log_path(&self) -> PathBuf {
+        self.output_dir.join(format!("{}_frames.txt", self.prefix))
+    }
+
+    /// Frame numbers and timestamps in ms of evenly spaced frames
+    fn planned_frames(&self, info: Option<&MediaInfo>) -> Result<(Vec<i32>, Vec<u32>)> {
+        let ScreenshotMode::Count(count) = self.mode else {
+            return Ok((Vec::new(), Vec::new()));
+        };
+        let duration = info.and_then(|info| info.duration_seconds());
+        let fps = info
+            .and_then(|info| info.first_video_stream())
+            .and_then(|stream| stream.framerate());
+        let (Some(duration), Some(fps)) = (duration, fps) else {
+            return Err(CutError::invalid_params(format!(
+                "Cannot read the duration and frame rate of {}",
+                self.input
+            )));
+        };
+
+        // The middle frame of each stretch, away from black intros and outros
+        let mut frames: Vec<i32> = (0..count)
+            .map(|i| (duration * (2 * i + 1) as f64 / (2 * count) as f64 * fps) as i32)
+            .collect();
+        frames.dedup();
+        let timestamps = frames
+            .iter()
+            .map(|&frame| (frame as f64 * 1000.0 / fps).round() as u32)
+            .collect();
+        Ok((frames, timestamps))
+    }
+}
+
+/// Parse frame timestamps in ms from `metadata=print` output
+fn parse_frame_times(content: &str) -> Vec<u32> {
+    content
+        .lines()
+        .filter(|line| line.starts_with("frame:"))
+        .filter_map(|line| {
+            line.split_whitespace()
+                .find_map(|field| field.strip_prefix("pts_time:"))
+                .and_then(|time| time.parse::<f64>().ok())
+        })
+        .map(|seconds| (seconds * 1000.0).round() as u32)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffprobe::{FormatInfo, StreamInfo};
+
+    fn media_info(duration: &str, fps: &str) -> MediaInfo {
+        MediaInfo {
+            format: Some(FormatInfo {
+                duration: Some(duration.to_string()),
+                ..Default::default()
+            }),
+            streams: vec![StreamInfo {
+                codec_type: Some("video".to_string()),
+                r_frame_rate: Some(fps.to_string()),
+                ..Default::default()
+            }],
+        }
+    }
+
+    #[test]
+    fn test_single_and_interval() {
+        let job = ScreenshotJob::new("in.mp4", "shots", ScreenshotMode::Single(1500))
+            .format(ImageFormat::Png)
+            .max_size(320, 240);
+        let line = job.build(None).unwrap().build_args().join(" ");
+        assert!(line.contains("-ss 1.5 -i in.mp4"));
+        assert!(line.contains("scale=w='min(iw,320)':h='min(ih,240)'"));
+        assert!(line.contains("-c:v png"));
+        assert!(line.contains("-vframes 1 -f image2"));
+        assert!(line.ends_with("shots/frame_%04d.png"));
+
+        let job = ScreenshotJob::new("in.mp4", "shots", ScreenshotMode::Interval(2000))
+            .format(ImageFormat::Webp);
+        let line = job.build(None).unwrap().build_args().join(" ");
+        assert!(line.contains("fps=1000/2000"));
+        assert!(line.contains("metadata=print:file=shots/frame_frames.txt"));
+        assert!(line.contains("-fps_mode vfr"));
+        assert!(line.contains("-c:v libwebp"));
+        assert!(line.ends_with("shots/frame_%04d.webp"));
+    }
+
+    #[test]
+    fn test_count() {
+        let job = ScreenshotJob::new("in.mp4", "shots", ScreenshotMode::Count(4));
+        assert!(job.build(None).is_err());
+
+        let info = media_info("10.0", "25/1");
+        let (frames, timestamps) = job.planned_frames(Some(&info)).unwrap();
+        assert_eq!(frames, vec![31, 93, 156, 218]);
+        assert_eq!(timestamps, vec![1240, 3720, 6240, 8720]);
+
+        let line = job.build(Some(&info)).unwrap().build_args().join(" ");
+        assert!(line.contains("select='eq(n,31)+eq(n,93)+eq(n,156)+eq(n,218)'"));
+        assert!(line.contains("-q:v 2"));
+        assert!(line.contains("-fps_mode vfr"));
+        assert!(!line.contains("-vsync"));
+    }
+
+    #[test]
+    fn test_keyframes_and_scenes() {
+        let job = ScreenshotJob::new("in.mp4", "shots", ScreenshotMode::Keyframes).prefix("key");
+        let line = job.build(None).unwrap().build_args().join(" ");
+        assert!(line.contains("-skip_frame nokey"));
+        assert!(line.contains("select='gte(scene,0)'"));
+        assert!(line.contains("metadata=print:file=shots/key_frames.txt"));
+
+        let job = ScreenshotJob::new("in.mp4", "shots", ScreenshotMode::SceneChange(0.3));
+        let line = job.build(None).unwrap().build_args().join(" ");
+        assert!(!line.contains("skip_frame"));
+        assert!(line.contains("select='gt(scene,0.3)'"));
+
+        let log = "frame:0    pts:0       pts_time:0\n\
+                   lavfi.scene_score=0.000000\n\
+                   frame:1    pts:124124  pts_time:4.137467\n\
+                   lavfi.scene_score=0.512000\n";
+        assert_eq!(parse_frame_times(log), vec![0, 4137]);
+    }
+
+    #[test]
+    fn test_validation() {
+        let job = |mode| ScreenshotJob::new("in.mp4", "shots", mode);
+        assert!(job(ScreenshotMode::Count(0)).validate().is_err());
+        assert!(job(ScreenshotMode::Interval(0)).validate().is_err());
+        assert!(job(ScreenshotMode::SceneChange(1.5)).validate().is_err());
+        assert!(
+            job(ScreenshotMode::Keyframes)
+                .max_size(0, 240)
+                .validate()
+                .is_err()
+        );
+        assert!(job(ScreenshotMode::SceneChange(0.4)).validate().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_run_keeps_existing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let taken = dir.path().join("frame_0003.jpg");
+        std::fs::write(&taken, b"someone else's").unwrap();
+        std::fs::write(dir.path().join("frame_notes.jpg"), b"").unwrap();
+        std::fs::write(dir.path().join("frame_0001.png"), b"").unwrap();
+
+        let job = ScreenshotJob::new("in.mp4", dir.path(), ScreenshotMode::Interval(1000));
+        assert_eq!(job.conflicting_files().unwrap(), vec![taken.clone()]);
+        let error = job.run().await.unwrap_err();
+        assert!(
+            matches!(error, CutError::Io(ref e) if e.kind() == std::io::ErrorKind::AlreadyExists)
+        );
+        assert_eq!(std::fs::read(&taken).unwrap(), b"someone else's");
+    }
+
+    #[tokio::test]
+    async fn test_dry_run() {
+        let dir = tempfile::tempdir().unwrap();
+        for mode in [
+            ScreenshotMode::Interval(1000),
+            ScreenshotMode::Keyframes,
+            ScreenshotMode::SceneChange(0.3),
+        ] {
+            let job = ScreenshotJob::new("in.mp4", dir.path().join("shots"), mode)
+                .set_ffmpeg_options(FFmpegOptions::new().dry_run(true));
+            assert!(job.run().await.unwrap().is_empty());
+        }
+        assert!(!dir.path().join("shots").exists());
+    }
+
+    #[test]
+    fn test_existing_images() {
+        let dir = tempfile::tempdir().unwrap();
+        let job = ScreenshotJob::new("in.mp4", dir.path(), ScreenshotMode::Interval(1000));
+        for name in ["frame_0001.jpg", "frame_0002.jpg", "frame_0004.jpg"] {
+            std::fs::write(dir.path().join(name), b"").unwrap();
+        }
+        assert_eq!(
+            job.existing_images(),
+            vec![
+                dir.path().join("frame_0001.jpg"),
+                dir.path().join("frame_0002.jpg")
+            ]
+        );
+    }
+}
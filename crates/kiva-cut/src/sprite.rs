@@ -0,0 +1,330 @@
+//! Sprite sheet generation for seek-preview thumbnails
+//!
+//! Thumbnails taken at a fixed interval are tiled into JPEG sheets, and a
+//! WebVTT file maps each time range to its `sheet.jpg#xywh=` region.
+
+use crate::cut::material::Material;
+use crate::error::{CutError, Result};
+use crate::ffmpeg::{
+    FFmpeg,
+    codec::{Format, VideoCodec},
+    filter::Filter,
+    input::Input,
+    output::Output,
+};
+use crate::options::FFmpegOptions;
+use std::path::PathBuf;
+
+/// A thumbnail region on a sprite sheet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpriteCue {
+    /// Start time in milliseconds
+    pub start: u32,
+    /// End time in milliseconds
+    pub end: u32,
+    /// Sheet file name, relative to the WebVTT file
+    pub sheet: String,
+    /// X offset of the thumbnail in pixels
+    pub x: i32,
+    /// Y offset of the thumbnail in pixels
+    pub y: i32,
+    /// Thumbnail width in pixels
+    pub width: i32,
+    /// Thumbnail height in pixels
+    pub height: i32,
+}
+
+/// Generated sprite sheets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpriteSheet {
+    /// Sheet image paths
+    pub sheets: Vec<PathBuf>,
+    /// WebVTT thumbnail track path
+    pub vtt: PathBuf,
+    /// Thumbnail regions in time order
+    pub cues: Vec<SpriteCue>,
+}
+
+/// A sprite sheet job for a video material
+#[derive(Debug, Clone)]
+pub struct SpriteJob {
+    /// Source material
+    pub material: Material,
+    /// Directory the sheets and the WebVTT file are written to
+    pub output_dir: PathBuf,
+    /// File name prefix, sheets are named `{prefix}_001.jpg` and so on
+    pub prefix: String,
+    /// Thumbnails per row
+    pub cols: u32,
+    /// Thumbnail rows per sheet
+    pub rows: u32,
+    /// Time between thumbnails in milliseconds
+    pub interval: u32,
+    /// Thumbnail width in pixels
+    pub thumb_width: i32,
+    /// Thumbnail height in pixels; non-positive keeps the material aspect ratio
+    pub thumb_height: i32,
+    /// FFmpeg options
+    pub ffmpeg_options: FFmpegOptions,
+}
+
+impl SpriteCue {
+    /// Get the WebVTT cue payload, e.g. `sprite_001.jpg#xywh=0,0,160,90`
+    pub fn target(&self) -> String {
+        format!(
+            "{}#xywh={},{},{},{}",
+            self.sheet, self.x, self.y, self.width, self.height
+        )
+    }
+}
+
+impl SpriteJob {
+    /// Create a job with 10x10 sheets of 160px wide thumbnails every 5 seconds
+    pub fn new<P: Into<PathBuf>>(material: Material, output_dir: P) -> Self {
+        Self {
+            material,
+            output_dir: output_dir.into(),
+            prefix: "sprite".to_string(),
+            cols: 10,
+            rows: 10,
+            interval: 5000,
+            thumb_width: 160,
+            thumb_height: 0,
+            ffmpeg_options: FFmpegOptions::new(),
+        }
+    }
+
+    /// Set the file name prefix
+    pub fn prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Set the sheet layout
+    pub fn layout(mut self, cols: u32, rows: u32) -> Self {
+        self.cols = cols;
+        self.rows = rows;
+        self
+    }
+
+    /// Set the time between thumbnails in milliseconds
+    pub fn interval(mut self, interval: u32) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Set the thumbnail size; a non-positive height keeps the aspect ratio
+    pub fn thumb_size(mut self, width: i32, height: i32) -> Self {
+        self.thumb_width = width;
+        self.thumb_height = height;
+        self
+    }
+
+    /// Set FFmpeg options
+    pub fn set_ffmpeg_options(mut self, options: FFmpegOptions) -> Self {
+        self.ffmpeg_options = options;
+        self
+    }
+
+    /// Validate the job settings
+    pub fn validate(&self) -> Result<()> {
+        let Material::Video(video) = &self.material else {
+            return Err(CutError::invalid_params(
+                "Sprite sheets need a video material",
+            ));
+        };
+        if video.src.is_empty() {
+            return Err(CutError::invalid_params("Material source cannot be empty"));
+        }
+        if video.duration.is_none_or(|d| d == 0) {
+            return Err(CutError::missing_param("material duration"));
+        }
+        if self.cols == 0 || self.rows == 0 {
+            return Err(CutError::invalid_params("Sprite layout must be positive"));
+        }
+        if self.interval == 0 {
+            return Err(CutError::invalid_params("Sprite interval must be positive"));
+        }
+        if self.thumb_width <= 0 {
+            return Err(CutError::invalid_params("Thumbnail width must be positive"));
+        }
+        if self.thumb_height <= 0 && video.dimension.width <= 0 {
+            return Err(CutError::invalid_params(
+                "Thumbnail height needs the material dimension",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Resolve the thumbnail size
+    pub fn thumb_dimension(&self) -> (i32, i32) {
+        if self.thumb_height > 0 {
+            return (self.thumb_width, self.thumb_height);
+        }
+        let height = match self.material.dimensions() {
+            Some(d) if d.width > 0 => self.thumb_width as f64 * d.height as f64 / d.width as f64,
+            _ => 0.0,
+        };
+        // Round to an even height, which encoders expect
+        (self.thumb_width, ((height / 2.0).round() as i32 * 2).max(2))
+    }
+
+    /// Compute the thumbnail regions
+    pub fn cues(&self) -> Result<Vec<SpriteCue>> {
+        self.validate()?;
+        let duration = self.material.duration().unwrap_or_default();
+        let (width, height) = self.thumb_dimension();
+        let per_sheet = self.cols * self.rows;
+
+        let count = duration.div_ceil(self.interval);
+        Ok((0..count)
+            .map(|i| {
+                let index = i % per_sheet;
+                SpriteCue {
+                    start: i * self.interval,
+                    end: ((i + 1) * self.interval).min(duration),
+                    sheet: self.sheet_name(i / per_sheet),
+                    x: (index % self.cols) as i32 * width,
+                    y: (index / self.cols) as i32 * height,
+                    width,
+                    height,
+                }
+            })
+            .collect())
+    }
+
+    /// Build the FFmpeg command
+    pub fn build(&self) -> Result<FFmpeg> {
+        self.validate()?;
+        let (width, height) = self.thumb_dimension();
+
+        let mut ffmpeg = FFmpeg::new();
+        ffmpeg.set_ffmpeg_options(self.ffmpeg_options.clone());
+        let input = ffmpeg.add_input(Input::with_simple(self.material.src()));
+
+        let fps = ffmpeg.add_filter(Filter::fps(format!("1000/{}", self.interval)), [input.v()]);
+        let scaled = ffmpeg.add_filter(Filter::scale(width, height), [fps]);
+        let tiled = ffmpeg.add_filter(Filter::tile(self.cols as i32, self.rows as i32), [scaled]);
+
+        let pattern = self.output_dir.join(format!("{}_%03d.jpg", self.prefix));
+        ffmpeg.add_output(
+            Output::with_simple(pattern.to_string_lossy())
+                .map_stream(tiled)
+                .video_codec(VideoCodec::MJPEG)
+                .format(Format::IMAGE2)
+                .option("q:v", "3"),
+        );
+        Ok(ffmpeg)
+    }
+
+    /// Generate the sheets and the WebVTT file
+    pub async fn run(&self) -> Result<SpriteSheet> {
+        let cues = self.cues()?;
+        std::fs::create_dir_all(&self.output_dir)?;
+        self.build()?.run().await?;
+
+        let mut sheets: Vec<PathBuf> = Vec::new();
+        for cue in &cues {
+            let path = self.output_dir.join(&cue.sheet);
+            if !sheets.contains(&path) {
+                sheets.push(path);
+            }
+        }
+
+        let vtt = self.output_dir.join(format!("{}.vtt", self.prefix));
+        std::fs::write(&vtt, to_webvtt(&cues))?;
+        Ok(SpriteSheet { sheets, vtt, cues })
+    }
+
+    /// Sheet file name for a 0-based index
+    fn sheet_name(&self, index: u32) -> String {
+        format!("{}_{:03}.jpg", self.prefix, index + 1)
+    }
+}
+
+/// Write thumbnail cues as a WebVTT file
+pub fn to_webvtt(cues: &[SpriteCue]) -> String {
+    let mut vtt = String::from("WEBVTT\n\n");
+    for cue in cues {
+        vtt.push_str(&format!(
+            "{} --> {}\n{}\n\n",
+            format_timestamp(cue.start),
+            format_timestamp(cue.end),
+            cue.target()
+        ));
+    }
+    vtt
+}
+
+/// Format milliseconds as a WebVTT timestamp
+fn format_timestamp(ms: u32) -> String {
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cut::material::VideoMaterial;
+
+    fn material(duration: u32) -> Material {
+        Material::Video(VideoMaterial::new("v1", "in.mp4", 1920, 1080).with_duration(duration))
+    }
+
+    #[test]
+    fn test_cues() {
+        let job = SpriteJob::new(material(23_500), "sprites")
+            .layout(2, 2)
+            .interval(5000);
+        assert_eq!(job.thumb_dimension(), (160, 90));
+
+        let cues = job.cues().unwrap();
+        assert_eq!(cues.len(), 5);
+        assert_eq!(cues[3].target(), "sprite_001.jpg#xywh=160,90,160,90");
+        assert_eq!(cues[4].target(), "sprite_002.jpg#xywh=0,0,160,90");
+        assert_eq!((cues[4].start, cues[4].end), (20_000, 23_500));
+
+        let vtt = to_webvtt(&cues[..1]);
+        assert_eq!(
+            vtt,
+            "WEBVTT\n\n00:00:00.000 --> 00:00:05.000\nsprite_001.jpg#xywh=0,0,160,90\n\n"
+        );
+    }
+
+    #[test]
+    fn test_build() {
+        let job = SpriteJob::new(material(60_000), "sprites")
+            .layout(5, 4)
+            .interval(2000)
+            .thumb_size(120, 68);
+        let line = job.build().unwrap().build_args().join(" ");
+        assert!(line.contains("fps=1000/2000"));
+        assert!(line.contains("scale=120:68"));
+        assert!(line.contains("tile=layout=5x4"));
+        assert!(line.contains("-c:v mjpeg"));
+        assert!(line.ends_with("sprites/sprite_%03d.jpg"));
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(
+            SpriteJob::new(Material::audio("a.mp3"), "s")
+                .validate()
+                .is_err()
+        );
+        let video = Material::video("in.mp4");
+        assert!(SpriteJob::new(video, "s").validate().is_err());
+        assert!(
+            SpriteJob::new(material(1000), "s")
+                .layout(0, 3)
+                .validate()
+                .is_err()
+        );
+        assert!(SpriteJob::new(material(1000), "s").validate().is_ok());
+    }
+}
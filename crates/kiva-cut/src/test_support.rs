@@ -0,0 +1,14 @@
+//! Helpers shared by the unit tests
+
+use std::path::Path;
+
+/// Write an executable script standing in for ffmpeg or ffprobe
+#[cfg(unix)]
+pub fn fake_binary(dir: &Path, name: &str, body: &str) -> String {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = dir.join(name);
+    std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path.to_string_lossy().into_owned()
+}
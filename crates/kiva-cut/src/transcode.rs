@@ -0,0 +1,664 @@
+//! High-level transcoding with named presets
+//!
+//! A [`TranscodeJob`] describes one input and one output. Presets fill in
+//! codec, quality and container settings, which can then be overridden
+//! field by field before the job compiles into an [`FFmpeg`] command.
+
+use crate::error::{CutError, Result};
+use crate::ffmpeg::{
+    FFmpeg,
+    abr::AbrLadder,
+    codec::{AudioCodec, Format, PixelFormat, VideoCodec},
+    filter::Filter,
+    input::Input,
+    output::Output,
+};
+use crate::options::FFmpegOptions;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+pub use crate::ffmpeg::abr::Rendition;
+
+/// Named transcoding presets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    /// H.264/AAC MP4 at 720p, optimized for progressive web playback
+    Web720p,
+    /// High quality H.265/AAC for archiving
+    ArchiveH265,
+    /// 128k MP3 audio
+    PodcastMp3,
+    /// H.264/AAC HLS with a 1080p/720p/480p bitrate ladder
+    HlsAbr,
+}
+
+/// A transcoding job from one input file to one output
+#[derive(Debug, Clone)]
+pub struct TranscodeJob {
+    /// Input file path
+    pub input: String,
+    /// Output file path; the HLS master playlist or DASH manifest for bitrate ladders
+    pub output: String,
+    /// Preset the job was created from
+    pub preset: Option<Preset>,
+    /// Output container, inferred from the output extension if not set
+    pub format: Option<Format>,
+    /// Video codec, `VideoCodec::None` drops the video stream
+    pub video_codec: Option<VideoCodec>,
+    /// Audio codec, `AudioCodec::None` drops the audio stream
+    pub audio_codec: Option<AudioCodec>,
+    /// Output size; a non-positive side keeps the aspect ratio
+    pub size: Option<(i32, i32)>,
+    /// Constant Rate Factor
+    pub crf: Option<i32>,
+    /// Video bitrate in kbps
+    pub video_bitrate: Option<i32>,
+    /// Audio bitrate in kbps
+    pub audio_bitrate: Option<i32>,
+    /// Audio sample rate in Hz
+    pub sample_rate: Option<u32>,
+    /// Frame rate
+    pub framerate: Option<String>,
+    /// Pixel format
+    pub pixel_format: Option<PixelFormat>,
+    /// GOP size
+    pub gop_size: Option<i32>,
+    /// Encoder speed preset, e.g. `medium`
+    pub encoder_preset: Option<String>,
+    /// MOV flags
+    pub mov_flags: Option<String>,
+    /// HLS segment duration in seconds
+    pub hls_time: Option<i32>,
+    /// Adaptive bitrate ladder; `None` for a single rendition
+    pub ladder: Option<AbrLadder>,
+    /// Custom output options
+    pub options: Vec<(String, String)>,
+    /// FFmpeg options
+    pub ffmpeg_options: FFmpegOptions,
+}
+
+impl Preset {
+    /// Get the preset name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preset::Web720p => "web-720p",
+            Preset::ArchiveH265 => "archive-h265",
+            Preset::PodcastMp3 => "podcast-mp3",
+            Preset::HlsAbr => "hls-abr",
+        }
+    }
+
+    /// Get all presets
+    pub fn all() -> [Preset; 4] {
+        [
+            Preset::Web720p,
+            Preset::ArchiveH265,
+            Preset::PodcastMp3,
+            Preset::HlsAbr,
+        ]
+    }
+
+    /// Get the container used when the output extension is unknown
+    pub fn format(&self) -> Format {
+        match self {
+            Preset::Web720p | Preset::ArchiveH265 => Format::MP4,
+            Preset::PodcastMp3 => Format::MP3,
+            Preset::HlsAbr => Format::HLS,
+        }
+    }
+
+    /// Apply the preset settings to a job
+    fn apply(&self, mut job: TranscodeJob) -> TranscodeJob {
+        job.preset = Some(*self);
+        match self {
+            Preset::Web720p => {
+                job.video_codec = Some(VideoCodec::H264);
+                job.audio_codec = Some(AudioCodec::AAC);
+                job.size = Some((-2, 720));
+                job.crf = Some(23);
+                job.encoder_preset = Some("medium".to_string());
+                job.pixel_format = Some(PixelFormat::Yuv420p);
+                job.audio_bitrate = Some(128);
+                job.mov_flags = Some("+faststart".to_string());
+            }
+            Preset::ArchiveH265 => {
+                job.video_codec = Some(VideoCodec::H265);
+                job.audio_codec = Some(AudioCodec::AAC);
+                job.crf = Some(20);
+                job.encoder_preset = Some("slow".to_string());
+                job.audio_bitrate = Some(192);
+            }
+            Preset::PodcastMp3 => {
+                job.video_codec = Some(VideoCodec::None);
+                job.audio_codec = Some(AudioCodec::MP3);
+                job.audio_bitrate = Some(128);
+                job.sample_rate = Some(44100);
+            }
+            Preset::HlsAbr => {
+                job.video_codec = Some(VideoCodec::H264);
+                job.audio_codec = Some(AudioCodec::AAC);
+                job.encoder_preset = Some("veryfast".to_string());
+                job.pixel_format = Some(PixelFormat::Yuv420p);
+                job.gop_size = Some(48);
+                job.ladder = Some(AbrLadder::hls(vec![
+                    Rendition::new(1080, 5000, 128),
+                    Rendition::new(720, 2800, 128),
+                    Rendition::new(480, 1400, 96),
+                ]));
+                // No scene-cut keyframes, so segment boundaries line up across renditions
+                job.options
+                    .push(("sc_threshold".to_string(), "0".to_string()));
+            }
+        }
+        job
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Preset {
+    type Err = CutError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Preset::all()
+            .into_iter()
+            .find(|preset| preset.name() == s)
+            .ok_or_else(|| CutError::invalid_params(format!("Unknown preset: {}", s)))
+    }
+}
+
+impl TranscodeJob {
+    /// Create a job without any settings
+    pub fn new<S1: Into<String>, S2: Into<String>>(input: S1, output: S2) -> Self {
+        Self {
+            input: input.into(),
+            output: output.into(),
+            preset: None,
+            format: None,
+            video_codec: None,
+            audio_codec: None,
+            size: None,
+            crf: None,
+            video_bitrate: None,
+            audio_bitrate: None,
+            sample_rate: None,
+            framerate: None,
+            pixel_format: None,
+            gop_size: None,
+            encoder_preset: None,
+            mov_flags: None,
+            hls_time: None,
+            ladder: None,
+            options: Vec::new(),
+            ffmpeg_options: FFmpegOptions::new(),
+        }
+    }
+
+    /// Create a job from a preset
+    pub fn with_preset<S1: Into<String>, S2: Into<String>>(
+        input: S1,
+        output: S2,
+        preset: Preset,
+    ) -> Self {
+        preset.apply(Self::new(input, output))
+    }
+
+    /// Set output container
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Set video codec
+    pub fn video_codec(mut self, codec: VideoCodec) -> Self {
+        self.video_codec = Some(codec);
+        self
+    }
+
+    /// Set audio codec
+    pub fn audio_codec(mut self, codec: AudioCodec) -> Self {
+        self.audio_codec = Some(codec);
+        self
+    }
+
+    /// Set output size; a non-positive side keeps the aspect ratio
+    pub fn size(mut self, width: i32, height: i32) -> Self {
+        self.size = Some((width, height));
+        self
+    }
+
+    /// Set CRF
+    pub fn crf(mut self, crf: i32) -> Self {
+        self.crf = Some(crf);
+        self
+    }
+
+    /// Set video bitrate in kbps
+    pub fn video_bitrate(mut self, bitrate: i32) -> Self {
+        self.video_bitrate = Some(bitrate);
+        self
+    }
+
+    /// Set audio bitrate in kbps
+    pub fn audio_bitrate(mut self, bitrate: i32) -> Self {
+        self.audio_bitrate = Some(bitrate);
+        self
+    }
+
+    /// Set audio sample rate in Hz
+    pub fn sample_rate(mut self, rate: u32) -> Self {
+        self.sample_rate = Some(rate);
+        self
+    }
+
+    /// Set frame rate
+    pub fn framerate<S: Into<String>>(mut self, fps: S) -> Self {
+        self.framerate = Some(fps.into());
+        self
+    }
+
+    /// Set pixel format
+    pub fn pixel_format(mut self, format: PixelFormat) -> Self {
+        self.pixel_format = Some(format);
+        self
+    }
+
+    /// Set GOP size
+    pub fn gop_size(mut self, size: i32) -> Self {
+        self.gop_size = Some(size);
+        self
+    }
+
+    /// Set encoder speed preset
+    pub fn encoder_preset<S: Into<String>>(mut self, preset: S) -> Self {
+        self.encoder_preset = Some(preset.into());
+        self
+    }
+
+    /// Set HLS segment duration in seconds
+    pub fn hls_time(mut self, time: i32) -> Self {
+        self.hls_time = Some(time);
+        self
+    }
+
+    /// Set the adaptive bitrate ladder
+    pub fn ladder(mut self, ladder: AbrLadder) -> Self {
+        self.ladder = Some(ladder);
+        self
+    }
+
+    /// Add a custom output option
+    pub fn option<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.options.push((key.into(), value.into()));
+        self
+    }
+
+    /// Set FFmpeg options
+    pub fn set_ffmpeg_options(mut self, options: FFmpegOptions) -> Self {
+        self.ffmpeg_options = options;
+        self
+    }
+
+    /// Resolve the output container
+    pub fn container(&self) -> Result<Format> {
+        if let Some(ref format) = self.format {
+            return Ok(format.clone());
+        }
+        let extension = Path::new(&self.output)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| Format::from(ext.to_lowercase()));
+        if let Some(ref format) = extension
+            && !matches!(format, Format::Custom(_))
+        {
+            return Ok(format.clone());
+        }
+        if let Some(ref ladder) = self.ladder {
+            return Ok(ladder.format());
+        }
+        if let Some(preset) = self.preset {
+            return Ok(preset.format());
+        }
+        extension.ok_or_else(|| {
+            CutError::invalid_params(format!("Cannot infer the output format of {}", self.output))
+        })
+    }
+
+    /// Validate the job settings and codec/container compatibility
+    pub fn validate(&self) -> Result<()> {
+        if self.input.is_empty() {
+            return Err(CutError::missing_param("input"));
+        }
+        if self.output.is_empty() {
+            return Err(CutError::missing_param("output"));
+        }
+
+        let format = self.container()?;
+        if format.is_image() {
+            return Err(CutError::unsupported_format(format!(
+                "{} is an image format, use screenshots instead",
+                format
+            )));
+        }
+
+        let video = self.video_codec.as_ref();
+        let audio = self.audio_codec.as_ref();
+        let drops_video = matches!(video, Some(VideoCodec::None));
+        let drops_audio = matches!(audio, Some(AudioCodec::None));
+
+        if format.is_audio() {
+            if let Some(codec) = video
+                && !drops_video
+            {
+                return Err(CutError::unsupported_format(format!(
+                    "{} is audio-only and cannot carry {} video",
+                    format, codec
+                )));
+            }
+            if drops_audio {
+                return Err(CutError::invalid_params(format!(
+                    "{} output needs an audio stream",
+                    format
+                )));
+            }
+        }
+        if format.is_video() && drops_video && drops_audio {
+            return Err(CutError::invalid_params("Output has no streams"));
+        }
+
+        if let Some(codec) = video
+            && !video_codec_supported(&format, codec)
+        {
+            return Err(CutError::unsupported_format(format!(
+                "{} video cannot be stored in {}",
+                codec, format
+            )));
+        }
+        if let Some(codec) = audio
+            && !audio_codec_supported(&format, codec)
+        {
+            return Err(CutError::unsupported_format(format!(
+                "{} audio cannot be stored in {}",
+                codec, format
+            )));
+        }
+
+        let reencodes_video = video.is_none_or(|codec| codec.requires_encoding());
+        if !reencodes_video && (self.size.is_some() || self.ladder.is_some()) {
+            return Err(CutError::invalid_params(
+                "Scaling needs the video to be re-encoded",
+            ));
+        }
+        if let Some((width, height)) = self.size
+            && width <= 0
+            && height <= 0
+        {
+            return Err(CutError::invalid_params(
+                "At least one side of the size must be positive",
+            ));
+        }
+
+        if let Some(ref ladder) = self.ladder {
+            if format != ladder.format() {
+                return Err(CutError::invalid_params(format!(
+                    "Bitrate ladder needs {} output",
+                    ladder.format()
+                )));
+            }
+            if self.size.is_some() {
+                return Err(CutError::invalid_params(
+                    "Bitrate ladders set the size per rendition",
+                ));
+            }
+            if self.crf.is_some() || self.video_bitrate.is_some() {
+                return Err(CutError::invalid_params(
+                    "Bitrate ladders set the video bitrate per rendition",
+                ));
+            }
+            ladder.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Compile the output settings of a single-rendition job
+    pub fn to_output(&self) -> Result<Output> {
+        self.validate()?;
+        let format = self.container()?;
+        Ok(self.output_settings(Output::with_simple(&self.output), &format))
+    }
+
+    /// Build the FFmpeg command
+    pub fn build(&self) -> Result<FFmpeg> {
+        self.validate()?;
+        let format = self.container()?;
+
+        let mut ffmpeg = FFmpeg::new();
+        ffmpeg.set_ffmpeg_options(self.ffmpeg_options.clone());
+        let input = ffmpeg.add_input(Input::with_simple(&self.input));
+
+        let keeps_video = !matches!(self.video_codec, Some(VideoCodec::None));
+        let keeps_audio = !matches!(self.audio_codec, Some(AudioCodec::None));
+
+        if let Some(ref ladder) = self.ladder {
+            let output = self.output_settings(Output::with_simple(&self.output), &format);
+            let audio = keeps_audio.then(|| input.a());
+            let output = ladder.apply(&mut ffmpeg, input.v(), audio, output);
+            ffmpeg.add_output(output);
+            return Ok(ffmpeg);
+        }
+
+        let mut output = self.output_settings(Output::with_simple(&self.output), &format);
+        if keeps_video {
+            output = match self.size {
+                Some((width, height)) => {
+                    output.map_stream(ffmpeg.add_filter(Filter::scale(width, height), [input.v()]))
+                }
+                None => output.map_stream(input.may_v()),
+            };
+        }
+        if keeps_audio {
+            output = output.map_stream(input.may_a());
+        }
+        ffmpeg.add_output(output);
+        Ok(ffmpeg)
+    }
+
+    /// Run the job
+    pub async fn run(&self) -> Result<()> {
+        self.build()?.run().await
+    }
+
+    /// Apply the settings shared by every output of the job
+    fn output_settings(&self, mut output: Output, format: &Format) -> Output {
+        output.format = self.format.clone();
+        output.video_codec = self.video_codec.clone();
+        output.audio_codec = self.audio_codec.clone();
+        output.crf = self.crf;
+        output.video_bitrate = self.video_bitrate;
+        output.audio_bitrate = self.audio_bitrate;
+        output.framerate = self.framerate.clone();
+        output.pixel_format = self.pixel_format.clone();
+        output.gop_size = self.gop_size;
+        output.mov_flags = self.mov_flags.clone();
+        output.options = self.options.clone();
+
+        if let Some(ref preset) = self.encoder_preset
+            && self
+                .video_codec
+                .as_ref()
+                .is_some_and(|c| c.requires_encoding())
+        {
+            output = output.option("preset", preset.clone());
+        }
+        if let Some(rate) = self.sample_rate {
+            output = output.option("ar", rate.to_string());
+        }
+        // Apple players only recognize HEVC tagged hvc1
+        if self.video_codec == Some(VideoCodec::H265) && matches!(format, Format::MP4 | Format::MOV)
+        {
+            output = output.option("tag:v", "hvc1");
+        }
+        if *format == Format::HLS {
+            output.hls_time = self.hls_time;
+            output.hls_playlist_type = Some("vod".to_string());
+        }
+        output
+    }
+}
+
+/// Check if a container can store a video codec; custom values are not checked
+fn video_codec_supported(format: &Format, codec: &VideoCodec) -> bool {
+    use VideoCodec::*;
+
+    if matches!(codec, Copy | None | Custom(_)) {
+        return true;
+    }
+    match format {
+        Format::MP4 => matches!(codec, H264 | H265 | VP9 | AV1),
+        Format::MOV => matches!(codec, H264 | H265 | MJPEG | JPEG | PNG),
+        Format::WEBM => matches!(codec, VP8 | VP9 | AV1),
+        Format::HLS | Format::MPEGTS => matches!(codec, H264 | H265),
+        Format::DASH => matches!(codec, H264 | H265 | VP9 | AV1),
+        Format::FLV => matches!(codec, H264),
+        Format::AVI | Format::MKV | Format::Custom(_) => true,
+        _ => false,
+    }
+}
+
+/// Check if a container can store an audio codec; custom values are not checked
+fn audio_codec_supported(format: &Format, codec: &AudioCodec) -> bool {
+    use AudioCodec::*;
+
+    if matches!(codec, Copy | None | Custom(_)) {
+        return true;
+    }
+    match format {
+        Format::MP4 => matches!(codec, AAC | MP3 | AC3 | Opus | FLAC),
+        Format::MOV => matches!(codec, AAC | MP3 | AC3 | FLAC | PCM),
+        Format::WEBM => matches!(codec, Opus | Vorbis),
+        Format::HLS | Format::MPEGTS => matches!(codec, AAC | MP3 | AC3),
+        Format::DASH => matches!(codec, AAC | AC3 | Opus),
+        Format::FLV => matches!(codec, AAC | MP3),
+        Format::MP3 => matches!(codec, MP3),
+        Format::AAC => matches!(codec, AAC),
+        Format::WAV => matches!(codec, PCM),
+        Format::AVI | Format::MKV | Format::Custom(_) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preset_names() {
+        for preset in Preset::all() {
+            assert_eq!(preset.name().parse::<Preset>().unwrap(), preset);
+        }
+        assert!("web-1080p".parse::<Preset>().is_err());
+    }
+
+    #[test]
+    fn test_web_preset() {
+        let job = TranscodeJob::with_preset("in.mov", "out.mp4", Preset::Web720p);
+        let args = job.build().unwrap().build_args();
+        let line = args.join(" ");
+
+        assert!(line.contains("scale=-2:720"));
+        assert!(line.contains("-c:v libx264 -c:a aac -pix_fmt yuv420p -b:a 128k -crf 23"));
+        assert!(line.contains("-movflags +faststart"));
+        assert!(line.contains("-preset medium"));
+        assert!(line.contains("-map 0:a?"));
+        assert!(line.ends_with("out.mp4"));
+    }
+
+    #[test]
+    fn test_preset_outputs() {
+        let output = TranscodeJob::with_preset("in.mp4", "out.mp4", Preset::ArchiveH265)
+            .to_output()
+            .unwrap();
+        let args = output.to_args();
+        assert!(args.join(" ").contains("-tag:v hvc1"));
+
+        let output = TranscodeJob::with_preset("in.mp4", "out.mkv", Preset::ArchiveH265)
+            .to_output()
+            .unwrap();
+        assert!(!output.to_args().contains(&"hvc1".to_string()));
+
+        let job = TranscodeJob::with_preset("in.mp4", "episode.mp3", Preset::PodcastMp3);
+        let line = job.build().unwrap().build_args().join(" ");
+        assert!(line.contains("-vn"));
+        assert!(line.contains("-c:a libmp3lame"));
+        assert!(line.contains("-ar 44100"));
+        assert!(!line.contains("0:v"));
+    }
+
+    #[test]
+    fn test_hls_ladder() {
+        let job = TranscodeJob::with_preset("in.mp4", "out/master.m3u8", Preset::HlsAbr);
+        let args = job.build().unwrap().build_args();
+        let line = args.join(" ");
+
+        assert!(line.contains("split=3"));
+        assert!(line.contains("scale=-2:1080"));
+        assert!(line.contains("scale=-2:480"));
+        assert!(line.contains("-b:v:1 2800k"));
+        assert!(line.contains("-b:a:2 96k"));
+        assert!(line.contains("-g 48"));
+        assert!(line.contains("-sc_threshold 0"));
+        assert!(line.contains("-hls_time 6"));
+        assert!(line.contains("-hls_segment_filename out/master_%v_%03d.ts"));
+        assert!(line.contains("-master_pl_name master.m3u8"));
+        assert!(
+            args.contains(&"v:0,a:0,name:1080p v:1,a:1,name:720p v:2,a:2,name:480p".to_string())
+        );
+        assert_eq!(args.last().unwrap(), "out/master_%v.m3u8");
+
+        // CRF would override the bitrate of every rendition
+        assert!(job.crf(20).validate().is_err());
+    }
+
+    #[test]
+    fn test_validation() {
+        let job = TranscodeJob::new("in.mp4", "out.webm")
+            .video_codec(VideoCodec::H264)
+            .audio_codec(AudioCodec::Opus);
+        assert!(job.validate().is_err());
+        assert!(job.video_codec(VideoCodec::VP9).validate().is_ok());
+
+        // Audio containers cannot carry video
+        let job = TranscodeJob::new("in.mp4", "out.mp3").video_codec(VideoCodec::H264);
+        assert!(job.validate().is_err());
+        let job = TranscodeJob::new("in.mp4", "out.wav").audio_codec(AudioCodec::AAC);
+        assert!(job.validate().is_err());
+
+        let job = TranscodeJob::with_preset("in.mp4", "out.webm", Preset::Web720p);
+        assert!(job.validate().is_err());
+        let job = TranscodeJob::with_preset("in.mp4", "out.mp4", Preset::HlsAbr);
+        assert!(job.validate().is_err());
+        let job = TranscodeJob::with_preset("in.mp4", "out.jpg", Preset::Web720p);
+        assert!(job.validate().is_err());
+
+        let job = TranscodeJob::new("in.mp4", "out.mp4")
+            .video_codec(VideoCodec::Copy)
+            .size(1280, 720);
+        assert!(job.validate().is_err());
+
+        assert!(TranscodeJob::new("in.mp4", "out").validate().is_err());
+        let job = TranscodeJob::with_preset("in.mp4", "out", Preset::PodcastMp3);
+        assert_eq!(job.container().unwrap(), Format::MP3);
+        let job =
+            TranscodeJob::new("in.mp4", "out.ts").video_codec(VideoCodec::custom("h264_nvenc"));
+        assert!(job.validate().is_ok());
+    }
+}
@@ -0,0 +1,182 @@
+//! Watermarking and logo removal of a media file
+//!
+//! A [`WatermarkJob`] removes delogo regions first, then draws its
+//! watermarks on top; the audio is copied.
+
+use crate::error::{CutError, Result};
+use crate::ffmpeg::{
+    FFmpeg,
+    codec::{AudioCodec, VideoCodec},
+    input::Input,
+    output::Output,
+};
+use crate::options::FFmpegOptions;
+
+pub use crate::ffmpeg::watermark::{Corner, DelogoRegion, TimeWindow, Watermark};
+
+/// A watermark job from one input file to one output
+#[derive(Debug, Clone)]
+pub struct WatermarkJob {
+    /// Input file path
+    pub input: String,
+    /// Output file path
+    pub output: String,
+    /// Watermarks, drawn in order
+    pub watermarks: Vec<Watermark>,
+    /// Regions to remove logos from
+    pub delogo: Vec<DelogoRegion>,
+    /// Video codec, the container default if not set
+    pub video_codec: Option<VideoCodec>,
+    /// Constant Rate Factor
+    pub crf: Option<i32>,
+    /// FFmpeg options
+    pub ffmpeg_options: FFmpegOptions,
+}
+
+impl WatermarkJob {
+    /// Create a job
+    pub fn new<S1: Into<String>, S2: Into<String>>(input: S1, output: S2) -> Self {
+        Self {
+            input: input.into(),
+            output: output.into(),
+            watermarks: Vec::new(),
+            delogo: Vec::new(),
+            video_codec: None,
+            crf: None,
+            ffmpeg_options: FFmpegOptions::new(),
+        }
+    }
+
+    /// Add a watermark
+    pub fn watermark(mut self, watermark: Watermark) -> Self {
+        self.watermarks.push(watermark);
+        self
+    }
+
+    /// Add a delogo region
+    pub fn delogo(mut self, region: DelogoRegion) -> Self {
+        self.delogo.push(region);
+        self
+    }
+
+    /// Set the video codec
+    pub fn video_codec(mut self, codec: VideoCodec) -> Self {
+        self.video_codec = Some(codec);
+        self
+    }
+
+    /// Set the CRF
+    pub fn crf(mut self, crf: i32) -> Self {
+        self.crf = Some(crf);
+        self
+    }
+
+    /// Set FFmpeg options
+    pub fn set_ffmpeg_options(mut self, options: FFmpegOptions) -> Self {
+        self.ffmpeg_options = options;
+        self
+    }
+
+    /// Validate the job settings
+    pub fn validate(&self) -> Result<()> {
+        if self.input.is_empty() {
+            return Err(CutError::missing_param("input"));
+        }
+        if self.output.is_empty() {
+            return Err(CutError::missing_param("output"));
+        }
+        if self.watermarks.is_empty() && self.delogo.is_empty() {
+            return Err(CutError::missing_param("watermark or delogo region"));
+        }
+        if let Some(codec) = &self.video_codec
+            && !codec.requires_encoding()
+        {
+            return Err(CutError::invalid_params(
+                "Watermarks need the video to be re-encoded",
+            ));
+        }
+        for region in &self.delogo {
+            region.validate()?;
+        }
+        for watermark in &self.watermarks {
+            watermark.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Build the FFmpeg command
+    pub fn build(&self) -> Result<FFmpeg> {
+        self.validate()?;
+
+        let mut ffmpeg = FFmpeg::new();
+        ffmpeg.set_ffmpeg_options(self.ffmpeg_options.clone());
+        let input = ffmpeg.add_input(Input::with_simple(&self.input));
+
+        let mut video = input.v();
+        for region in &self.delogo {
+            video = region.apply(&mut ffmpeg, video);
+        }
+        for watermark in &self.watermarks {
+            video = watermark.apply(&mut ffmpeg, video);
+        }
+
+        let mut output = Output::with_simple(&self.output)
+            .map_stream(video)
+            .map_stream(input.may_a())
+            .audio_codec(AudioCodec::Copy);
+        if let Some(codec) = &self.video_codec {
+            output = output.video_codec(codec.clone());
+        }
+        if let Some(crf) = self.crf {
+            output = output.crf(crf);
+        }
+        ffmpeg.add_output(output);
+        Ok(ffmpeg)
+    }
+
+    /// Run the job
+    pub async fn run(&self) -> Result<()> {
+        self.build()?.run().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build() {
+        let job = WatermarkJob::new("in.mp4", "out.mp4")
+            .delogo(DelogoRegion::new(20, 20, 120, 60))
+            .watermark(Watermark::image("logo.png").anchor(Corner::BottomRight, 24, 24))
+            .video_codec(VideoCodec::H264)
+            .crf(20);
+        let args = job.build().unwrap().build_args();
+        let line = args.join(" ");
+        assert!(line.contains("-i in.mp4 -i logo.png"));
+        assert!(line.contains("[0:v]delogo=x=20:y=20:w=120:h=60"));
+        assert!(line.contains("overlay=W-w-24:H-h-24"));
+        assert!(line.contains("-map 0:a?"));
+        assert!(line.contains("-c:a copy"));
+        assert!(line.contains("-crf 20"));
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(WatermarkJob::new("in.mp4", "out.mp4").validate().is_err());
+        let job = WatermarkJob::new("in.mp4", "out.mp4").watermark(Watermark::text("kiva"));
+        assert!(
+            job.clone()
+                .video_codec(VideoCodec::Copy)
+                .validate()
+                .is_err()
+        );
+        assert!(
+            job.clone()
+                .delogo(DelogoRegion::new(0, 0, 10, 10).window(3000, 3000))
+                .validate()
+                .is_err()
+        );
+        assert!(job.validate().is_ok());
+    }
+}